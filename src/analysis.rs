@@ -0,0 +1,1363 @@
+//! The analysis pipeline, downloading and classifying the logs of an experiment.
+
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet, btree_map::Entry},
+    fmt::Write as _,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use futures::StreamExt as _;
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use reqwest::Client;
+use tokio::io::AsyncWriteExt as _;
+
+use crate::{
+    AnalysisError,
+    cache::{
+        CacheManifest, LogStore, MissingLogs, get_cached_log, get_experiment_mode,
+        get_experiment_status, get_log, get_report, get_report_json, get_yanked_crates, log_url,
+        prepare_experiment_dir, prune_cached_log, unix_timestamp, unknown_experiment,
+    },
+    classifiers::ExternalClassifiers,
+    connectivity::Connectivity,
+    engine::{
+        AnalysisReport, BLOCKED_ON, BuildDurations, Config, CrateDuration, DurationRegression,
+        Excerpt, FindingMatch, Fingerprint, LineMatch, LinkerIssue, LinkerItem, LogFindings,
+        LogSize, LogSizes, MissingRunHandling, OtherCrate, OtherGroup, OtherRun,
+        REPORT_SCHEMA_VERSION, Relaxation, ResolverCulprit, ResolverFailure, Results, Sample,
+        SectionReport, SelectedRun, ToolchainMismatch, WarningsReport, YankedHandling,
+        blocking_dependency, crate_id, dependency_chain, failure_fingerprint, fingerprint_line,
+        is_relevant_crate, last_error_line, linker_items, log_duration, percent, process_log,
+        process_refinement, process_warnings, prune_log, relaxed_matches, resolver_failures,
+        run_id, run_role, select_interesting_runs, select_missing_runs, select_section_runs,
+        select_warning_runs, toolchain_mismatch, truncate_long_lines,
+    },
+    metrics,
+    output::{config_snapshot_path, load_analysis, write_config_snapshot},
+};
+
+/// Number of logs listed as the largest logs in the report.
+const LARGEST_LOGS: usize = 10;
+/// Number of runs listed as the slowest builds and of crates listed as slower than their baseline.
+const SLOWEST_BUILDS: usize = 10;
+
+/// The existing analysis of `experiment` if analyzing it again would give the same, i.e. it was made from the same
+/// crater results with the same config, see [`Config::reproducibility_hash`] and [`Config::config_hash`],
+/// `None` otherwise.
+///
+/// The categories ignored by the run that wrote it are counted again, as this run may ignore others.
+pub async fn reusable_analysis(
+    config: &Config,
+    client: &Client,
+    multi: &MultiProgress,
+    experiment: &str,
+) -> Result<Option<AnalysisReport>, AnalysisError> {
+    let mut report = match load_analysis(experiment).await {
+        Ok(report) => report,
+        Err(err) => {
+            log::debug!("No analysis of {experiment} to reuse: {err}");
+            return Ok(None);
+        }
+    };
+    let (Some(hash), Some(config_hash)) = (&report.reproducibility_hash, &report.config_hash)
+    else {
+        return Ok(None);
+    };
+    let config = match &report.mode {
+        Some(mode) => Cow::Owned(config.for_mode(mode)),
+        None => Cow::Borrowed(config),
+    };
+    if config.config_hash() != *config_hash {
+        return Ok(None);
+    }
+    let results_json =
+        get_report_json(client, multi, experiment, config.regenerated_reports).await?;
+    if config.reproducibility_hash(&results_json) != *hash {
+        return Ok(None);
+    }
+
+    let ignored = std::mem::take(&mut report.ignored_findings);
+    report.findings.extend(ignored);
+    report.thousands_separator = config.thousands_separator.clone();
+    Ok(Some(report))
+}
+
+/// Downloads and classifies the selected runs of an experiment.
+///
+/// With `live_report`, a [partial report](live_report_path) with the findings so far is written at that interval
+/// while the logs are classified, so that long analyses can be monitored; it is removed once the analysis completed.
+/// With [`Config::findings_stream`], each classified run is appended to the [findings stream](findings_stream_path) instead.
+pub async fn run_analysis(
+    config: &Arc<Config>,
+    client: &Client,
+    experiment: &str,
+    report_ps: &ProgressBar,
+    multi: &MultiProgress,
+    parallelism: usize,
+    live_report: Option<Duration>,
+) -> Result<AnalysisReport, AnalysisError> {
+    prepare_experiment_dir(experiment)?;
+
+    report_ps.set_message(format!("Getting Crater Report for {experiment}"));
+    report_ps.enable_steady_tick(Duration::from_millis(100));
+    let mode = get_experiment_mode(client, multi, experiment).await;
+    let config = &match &mode {
+        Some(mode) => Arc::new(config.for_mode(mode)),
+        None => config.clone(),
+    };
+    match write_config_snapshot(experiment, config).await {
+        Ok(true) => log::warn!(
+            "{experiment} was analyzed with a different config last time, see '{}' for the config used now",
+            config_snapshot_path(experiment)
+        ),
+        Ok(false) => {}
+        Err(err) => log::warn!("Failed to store the config used for {experiment}: {err}"),
+    }
+    let status = if config.crater_api_url.is_empty() {
+        None
+    } else {
+        get_experiment_status(client, &config.crater_api_url, experiment).await
+    };
+    let status = status.map(|mut status| {
+        status.set_age(unix_timestamp(), config.max_experiment_age_days);
+        if status.stale {
+            log::warn!(
+                "{experiment} is {} days old, its results may be outdated",
+                status.age_days.unwrap_or_default()
+            );
+        }
+        status
+    });
+    let crate_tags = load_crate_tags(config)?;
+    let results_json =
+        match get_report_json(client, multi, experiment, config.regenerated_reports).await {
+            Err(AnalysisError::NotFound(_)) => {
+                return Err(unknown_experiment(client, &config.crater_api_url, experiment).await);
+            }
+            results_json => results_json?,
+        };
+    let reproducibility_hash = config.reproducibility_hash(&results_json);
+    let mut report =
+        Results::from_slice_filtered(&results_json, |krate| is_relevant_crate(config, krate))?;
+    drop(results_json);
+    let reconstructed_logs = report.reconstruct_missing_logs();
+    if !reconstructed_logs.is_empty() {
+        log::warn!(
+            "{} runs of {experiment} have no log recorded, assuming crater's layout for them",
+            reconstructed_logs.len()
+        );
+    }
+    let yanked = match config.yanked {
+        YankedHandling::Ignore => BTreeSet::new(),
+        YankedHandling::Annotate | YankedHandling::Exclude => {
+            report_ps.set_message(format!("Looking up yanked crates of {experiment}"));
+            get_yanked_crates(client, multi, &report.crates).await
+        }
+    };
+    let mut excluded_crates = Vec::new();
+    if config.yanked == YankedHandling::Exclude {
+        (excluded_crates, report.crates) = std::mem::take(&mut report.crates)
+            .into_iter()
+            .partition(|krate| yanked.contains(&krate.name));
+    }
+    report_ps.set_message(format!("Processing Crater Report for {experiment}"));
+
+    let mut other = BTreeMap::new();
+
+    let missing_logs = MissingLogs::load(experiment, config.missing_log_expiry());
+
+    let (regressed_count, interesting_runs) = select_interesting_runs(config, &report);
+    let missing_runs = match config.missing_runs {
+        MissingRunHandling::Report => select_missing_runs(config, &report)
+            .into_iter()
+            .map(|(krate, indices)| {
+                (
+                    krate.name.clone(),
+                    indices.into_iter().map(run_role).collect(),
+                )
+            })
+            .collect(),
+        MissingRunHandling::Ignore => BTreeMap::new(),
+    };
+    let interesting_results_count = interesting_runs.len();
+
+    let (warning_krate_count, warning_runs) = config
+        .warnings
+        .as_ref()
+        .map(|warnings| select_warning_runs(warnings, &report))
+        .unwrap_or_default();
+    let warning_run_count = warning_runs.len();
+
+    let section_runs = config
+        .sections
+        .values()
+        .map(|section| select_section_runs(section, &report))
+        .collect::<Vec<_>>();
+    let section_run_count = section_runs
+        .iter()
+        .map(|(_, runs)| runs.len())
+        .sum::<usize>();
+    let mut sections = config
+        .sections
+        .values()
+        .zip(&section_runs)
+        .map(|(section, (krate_count, runs))| SectionReport {
+            expected_krate_result: section.crate_result.to_string(),
+            expected_run_result: section.run_result.to_string(),
+            krate_count: *krate_count,
+            run_count: runs.len(),
+            findings: BTreeMap::new(),
+            other: Vec::new(),
+        })
+        .collect::<Vec<_>>();
+
+    let run_pb = multi.add(
+        ProgressBar::new(
+            (interesting_results_count + warning_run_count + section_run_count) as u64,
+        )
+        .with_message(format!("Processing logs for {experiment}")),
+    );
+    run_pb.set_style(
+        ProgressStyle::with_template("{msg} {wide_bar} {human_pos}/{human_len} ETA {eta_precise}")
+            .unwrap(),
+    );
+
+    let runs = interesting_runs
+        .into_iter()
+        .map(|selected| (RunKind::Interesting, selected))
+        .chain(
+            warning_runs
+                .into_iter()
+                .map(|selected| (RunKind::Warnings, selected)),
+        )
+        .chain(
+            section_runs
+                .into_iter()
+                .enumerate()
+                .flat_map(|(index, (_, runs))| {
+                    runs.into_iter()
+                        .map(move |selected| (RunKind::Section(index), selected))
+                }),
+        );
+
+    let external_classifiers = ExternalClassifiers::new(config);
+    let redactor = &config
+        .redactor()
+        .map_err(|err| AnalysisError::InvalidConfig(format!("invalid redact pattern: {err}")))?;
+    let ruleset_hash = &config.ruleset_hash();
+    // external classifiers may need the whole log
+    let prune_logs = config.prune_logs && external_classifiers.is_empty();
+
+    let connectivity = Connectivity::default();
+    let store = LogStore::new(config.share_logs)
+        .with_compressions(&config.compressed_logs)
+        .with_memory_budget(config.log_memory_budget_mib);
+    let mut stream = futures::stream::iter(runs)
+        .map(|(kind, selected)| {
+            let experiment = &experiment;
+            let missing_logs = &missing_logs;
+            let store = &store;
+            let connectivity = &connectivity;
+            async move {
+                if !connectivity.ready().await {
+                    return None;
+                }
+                // the log's reservation in the memory budget is held until it is classified
+                let log = get_log(
+                    client,
+                    multi,
+                    store,
+                    missing_logs,
+                    experiment,
+                    &selected.run.log,
+                    ruleset_hash,
+                )
+                .await;
+                let report_failure = connectivity.record(&log, multi).await;
+                match log {
+                    Err(err) => {
+                        if report_failure {
+                            log::warn!("Failed to get log '{}': {err}", selected.run.log);
+                        }
+                        None
+                    }
+                    Ok((log, permit)) => Some((kind, selected, log, permit)),
+                }
+            }
+        })
+        .buffer_unordered(parallelism)
+        .filter_map(std::future::ready)
+        .map(|(kind, selected, log, permit)| {
+            let external_classifiers = &external_classifiers;
+            async move {
+                let _permit = permit;
+                let log_bytes = log.len() as u64;
+                let external = if kind == RunKind::Interesting && !external_classifiers.is_empty() {
+                    external_classifiers
+                        .classify(experiment, &selected.run.log, &log)
+                        .await
+                } else {
+                    BTreeSet::new()
+                };
+                let config = config.clone();
+                let experiment = experiment.to_string();
+                let redactor = redactor.clone();
+                let log_name = selected.run.log.clone();
+                let krate_name = selected.krate.name.clone();
+                let ruleset_hash = ruleset_hash.clone();
+                let (
+                    duration,
+                    linker,
+                    resolver,
+                    unexpected_rustc,
+                    relaxed,
+                    (run_findings, excerpts, chains, error_lines, error_line),
+                ) = tokio::task::spawn_blocking(move || {
+                    let log = truncate_long_lines(&log, config.max_line_length);
+                    // before pruning, which may blank the timestamped lines
+                    let duration = if kind == RunKind::Interesting {
+                        log_duration(&log)
+                    } else {
+                        None
+                    };
+                    let (linker, resolver, unexpected_rustc) = if kind == RunKind::Interesting {
+                        // crater's log paths start with the toolchain of the run
+                        let toolchain = log_name.split('/').next().unwrap_or_default();
+                        (
+                            linker_items(&log),
+                            resolver_failures(&log),
+                            toolchain_mismatch(toolchain, &log),
+                        )
+                    } else {
+                        (BTreeSet::new(), BTreeSet::new(), None)
+                    };
+                    let classified = match kind {
+                        RunKind::Interesting => {
+                            let mut findings = process_log(&config, &log);
+                            if config.reclassify_blocked
+                                && let Some((dependency, line_number)) =
+                                    blocking_dependency(&log, &krate_name)
+                            {
+                                findings = LogFindings::from([(
+                                    format!("{BLOCKED_ON}{dependency}"),
+                                    LineMatch { line_number },
+                                )]);
+                            }
+                            // unmatched logs are kept in full to develop new rules
+                            if prune_logs && !findings.is_empty() {
+                                let pruned =
+                                    prune_log(&log, &findings, |name| config.excerpt_context(name));
+                                if let Err(err) =
+                                    prune_cached_log(&experiment, &log_name, &pruned, &ruleset_hash)
+                                {
+                                    log::warn!("Failed to prune log '{log_name}': {err}");
+                                }
+                            }
+                            let excerpts = findings
+                                .iter()
+                                .map(|(name, line_match)| {
+                                    let mut excerpt = Excerpt::extract(
+                                        &log,
+                                        line_match.line_number,
+                                        config.excerpt_context(name),
+                                    );
+                                    excerpt.redact(&redactor);
+                                    (name.clone(), excerpt)
+                                })
+                                .collect::<Vec<_>>();
+                            let chains = findings
+                                .iter()
+                                .filter_map(|(name, line_match)| {
+                                    let chain = dependency_chain(&log, line_match.line_number)?;
+                                    Some((name.clone(), chain))
+                                })
+                                .collect::<Vec<_>>();
+                            let error_lines = findings
+                                .iter()
+                                .map(|(name, line_match)| {
+                                    let line = fingerprint_line(&log, line_match.line_number);
+                                    (name.clone(), redactor.redact(&line))
+                                })
+                                .collect::<Vec<_>>();
+                            // only needed to group the unmatched runs
+                            let error_line = if findings.is_empty() {
+                                last_error_line(&log).map(|line| redactor.redact(&line))
+                            } else {
+                                None
+                            };
+                            (findings, excerpts, chains, error_lines, error_line)
+                        }
+                        RunKind::Warnings => (
+                            process_warnings(config.warnings.as_ref().unwrap(), &log),
+                            Vec::new(),
+                            Vec::new(),
+                            Vec::new(),
+                            None,
+                        ),
+                        RunKind::Section(_) => (
+                            process_log(&config, &log),
+                            Vec::new(),
+                            Vec::new(),
+                            Vec::new(),
+                            None,
+                        ),
+                    };
+                    let relaxed = if kind == RunKind::Interesting && config.relaxed_diagnostics {
+                        relaxed_matches(&config.targets, &log, &classified.0)
+                            .into_iter()
+                            .map(|(name, relaxed)| (name, relaxed.relaxation))
+                            .collect()
+                    } else {
+                        Vec::new()
+                    };
+                    (
+                        duration,
+                        linker,
+                        resolver,
+                        unexpected_rustc,
+                        relaxed,
+                        classified,
+                    )
+                })
+                .await
+                .unwrap();
+                let analyzed = AnalyzedRun {
+                    log_bytes,
+                    findings: run_findings,
+                    excerpts,
+                    chains,
+                    error_lines,
+                    error_line,
+                    duration,
+                    linker,
+                    resolver,
+                    relaxed,
+                    external,
+                    unexpected_rustc,
+                };
+                (kind, selected, analyzed)
+            }
+        })
+        .buffer_unordered(parallelism);
+
+    let mut findings = BTreeMap::new();
+    let mut warning_findings = BTreeMap::new();
+    let mut samples = BTreeMap::<String, Sample>::new();
+    let mut category_crates = BTreeMap::<String, BTreeSet<String>>::new();
+    // category -> crate -> (log, chain), keeping the chain of the first log for reproducible reports
+    let mut fingerprints = BTreeMap::<String, Fingerprint>::new();
+    let mut finding_matches = BTreeMap::<String, Vec<FindingMatch>>::new();
+    let mut dependency_chains = BTreeMap::<String, BTreeMap<String, (String, Vec<String>)>>::new();
+
+    let mut log_sizes = LogSizes::default();
+    let mut all_log_sizes = Vec::new();
+    let mut relaxed_crates = BTreeMap::<String, BTreeMap<String, Relaxation>>::new();
+    // crate -> run index -> (log, seconds)
+    let mut run_durations = BTreeMap::<String, BTreeMap<usize, (String, u64)>>::new();
+    let mut linker_crates = BTreeMap::<LinkerItem, BTreeSet<String>>::new();
+    let mut resolver_crates = BTreeMap::<ResolverFailure, BTreeSet<String>>::new();
+    let mut toolchain_mismatches = Vec::new();
+
+    let mut other_groups = BTreeMap::<Option<String>, BTreeSet<String>>::new();
+    let mut analyzed_runs = 0;
+    let mut live_written_at = Instant::now();
+    let mut findings_stream = if config.findings_stream {
+        match tokio::fs::File::create(findings_stream_path(experiment)).await {
+            Ok(file) => Some(file),
+            Err(err) => {
+                log::warn!("Failed to create the findings stream of {experiment}: {err}");
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    while let Some((kind, selected, analyzed)) = stream.next().await {
+        let AnalyzedRun {
+            log_bytes,
+            findings: log_findings,
+            excerpts,
+            chains,
+            error_lines,
+            error_line,
+            duration,
+            linker,
+            resolver,
+            relaxed,
+            external,
+            unexpected_rustc,
+        } = analyzed;
+        let categories = log_findings
+            .into_keys()
+            .chain(external)
+            .collect::<BTreeSet<_>>();
+        let run_id = run_id(experiment, &selected.krate.name, selected.index);
+        log_sizes.total_bytes += log_bytes;
+        all_log_sizes.push(LogSize {
+            krate: selected.krate.name.clone(),
+            run_id: run_id.clone(),
+            log: selected.run.log.clone(),
+            bytes: log_bytes,
+        });
+
+        match kind {
+            RunKind::Interesting => {}
+            RunKind::Warnings => {
+                for finding in categories {
+                    *warning_findings.entry(finding).or_default() += 1;
+                }
+                run_pb.inc(1);
+                continue;
+            }
+            RunKind::Section(index) => {
+                let section = &mut sections[index];
+                if categories.is_empty()
+                    && let Err(position) = section.other.binary_search(&selected.krate.name)
+                {
+                    section.other.insert(position, selected.krate.name.clone());
+                }
+                for finding in categories {
+                    *section.findings.entry(finding).or_default() += 1;
+                }
+                run_pb.inc(1);
+                continue;
+            }
+        }
+
+        for (name, relaxation) in relaxed {
+            // the external classifiers may have reported the category
+            if !categories.contains(&name) {
+                relaxed_crates
+                    .entry(name)
+                    .or_default()
+                    .insert(selected.krate.name.clone(), relaxation);
+            }
+        }
+
+        for item in linker {
+            linker_crates
+                .entry(item)
+                .or_default()
+                .insert(selected.krate.name.clone());
+        }
+        if let Some(found) = unexpected_rustc {
+            toolchain_mismatches.push(ToolchainMismatch {
+                krate: selected.krate.name.clone(),
+                run_id: run_id.clone(),
+                log: selected.run.log.clone(),
+                expected: selected
+                    .run
+                    .log
+                    .split('/')
+                    .next()
+                    .unwrap_or_default()
+                    .to_string(),
+                found,
+            });
+        }
+        for failure in resolver {
+            resolver_crates
+                .entry(failure)
+                .or_default()
+                .insert(selected.krate.name.clone());
+        }
+
+        if let Some(seconds) = duration {
+            run_durations
+                .entry(selected.krate.name.clone())
+                .or_default()
+                .insert(selected.index, (selected.run.log.clone(), seconds));
+        }
+
+        if let Some(file) = &mut findings_stream {
+            let line = serde_json::json!({
+                "crate": selected.krate.name,
+                "run_id": run_id,
+                "log": selected.run.log,
+                "log_url": log_url(experiment, &selected.run.log),
+                "categories": categories,
+            });
+            if let Err(err) = file.write_all(format!("{line}\n").as_bytes()).await {
+                log::warn!("Failed to write to the findings stream of {experiment}: {err}");
+                findings_stream = None;
+            }
+        }
+
+        if categories.is_empty() {
+            other_groups
+                .entry(error_line)
+                .or_default()
+                .insert(selected.krate.name.clone());
+            let other_crate =
+                other
+                    .entry(selected.krate.name.clone())
+                    .or_insert_with(|| OtherCrate {
+                        id: crate_id(experiment, &selected.krate.name),
+                        res: selected.krate.res.clone(),
+                        runs: Vec::new(),
+                    });
+            // crates may list the same log for multiple runs
+            if !other_crate
+                .runs
+                .iter()
+                .any(|run| run.log == selected.run.log)
+            {
+                other_crate.runs.push(OtherRun {
+                    id: run_id.clone(),
+                    index: selected.index,
+                    role: selected.role(),
+                    log: selected.run.log.clone(),
+                });
+                other_crate.runs.sort_by_key(|run| run.index);
+            }
+        }
+
+        for finding in categories {
+            *log_sizes.category_bytes.entry(finding.clone()).or_default() += log_bytes;
+            category_crates
+                .entry(finding.clone())
+                .or_default()
+                .insert(selected.krate.name.clone());
+            *findings.entry(finding).or_default() += 1;
+        }
+
+        for (finding, line) in error_lines {
+            let failure = fingerprints
+                .entry(failure_fingerprint(&finding, &line))
+                .or_insert_with(|| Fingerprint {
+                    category: finding,
+                    error_line: line,
+                    crates: Vec::new(),
+                    run_ids: Vec::new(),
+                });
+            if let Err(index) = failure.crates.binary_search(&selected.krate.name) {
+                failure.crates.insert(index, selected.krate.name.clone());
+            }
+            if let Err(index) = failure.run_ids.binary_search(&run_id) {
+                failure.run_ids.insert(index, run_id.clone());
+            }
+        }
+
+        for (finding, chain) in chains {
+            let chain = (selected.run.log.clone(), chain);
+            match dependency_chains
+                .entry(finding)
+                .or_default()
+                .entry(selected.krate.name.clone())
+            {
+                Entry::Vacant(entry) => {
+                    entry.insert(chain);
+                }
+                Entry::Occupied(mut entry) => {
+                    if chain.0 < entry.get().0 {
+                        entry.insert(chain);
+                    }
+                }
+            }
+        }
+
+        for (finding, excerpt) in &excerpts {
+            let Some(line) = excerpt.matched_line() else {
+                continue;
+            };
+            finding_matches
+                .entry(finding.clone())
+                .or_default()
+                .push(FindingMatch {
+                    run_id: run_id.clone(),
+                    krate: selected.krate.name.clone(),
+                    log: selected.run.log.clone(),
+                    line_number: excerpt.matched_line_number,
+                    line: line.to_string(),
+                });
+        }
+
+        // keep the sample of the first crate by name, so that reports are reproducible
+        for (finding, excerpt) in excerpts {
+            let sample = Sample {
+                krate: selected.krate.name.clone(),
+                run_id: run_id.clone(),
+                log: selected.run.log.clone(),
+                excerpt,
+            };
+            match samples.entry(finding) {
+                Entry::Vacant(entry) => {
+                    entry.insert(sample);
+                }
+                Entry::Occupied(mut entry) => {
+                    if (&sample.krate, &sample.log) < (&entry.get().krate, &entry.get().log) {
+                        entry.insert(sample);
+                    }
+                }
+            }
+        }
+
+        analyzed_runs += 1;
+        metrics::LOGS_CLASSIFIED.inc();
+        if let Some(interval) = live_report
+            && live_written_at.elapsed() >= interval
+        {
+            live_written_at = Instant::now();
+            let unclassified = other.values().map(|other| other.runs.len()).sum();
+            let partial = render_live_report(
+                experiment,
+                analyzed_runs,
+                interesting_results_count,
+                &findings,
+                unclassified,
+            );
+            if let Err(err) = tokio::fs::write(live_report_path(experiment), partial).await {
+                log::warn!("Failed to write the live report of {experiment}: {err}");
+            }
+        }
+
+        run_pb.inc(1);
+    }
+    // tokio's files write in the background, the last lines would be lost without flushing
+    if let Some(mut file) = findings_stream
+        && let Err(err) = file.flush().await
+    {
+        log::warn!("Failed to write to the findings stream of {experiment}: {err}");
+    }
+    connectivity.check()?;
+    if live_report.is_some() {
+        // the complete report is written by the caller
+        let _ = tokio::fs::remove_file(live_report_path(experiment)).await;
+    }
+
+    save_cache_metadata(
+        experiment,
+        &missing_logs,
+        run_pb.position() as usize,
+        Some(ruleset_hash.clone()),
+    );
+
+    // ties are ordered by log, so that reports are reproducible
+    all_log_sizes.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.log.cmp(&b.log)));
+    all_log_sizes.truncate(LARGEST_LOGS);
+    log_sizes.largest = all_log_sizes;
+    log_sizes.reuse = store.reuse();
+
+    let durations = build_durations(experiment, &report, run_durations).await;
+
+    let mut other_groups = other_groups
+        .into_iter()
+        .map(|(error_line, crates)| OtherGroup {
+            error_line,
+            crates: crates.into_iter().collect(),
+        })
+        .collect::<Vec<_>>();
+    // stable, so groups of the same size stay ordered by their line
+    other_groups.sort_by_key(|group| std::cmp::Reverse(group.crates.len()));
+
+    report_ps.set_message(format!("Processed Crated Report for {experiment}"));
+
+    let mut analysis = AnalysisReport {
+        schema_version: REPORT_SCHEMA_VERSION,
+        experiment: experiment.to_string(),
+        mode,
+        status,
+        regressed_count,
+        interesting_results_count,
+        findings,
+        ignored_findings: BTreeMap::new(),
+        refined_categories: BTreeMap::new(),
+        other,
+        other_groups,
+        missing_runs,
+        reconstructed_logs,
+        samples,
+        descriptions: BTreeMap::new(),
+        annotations: BTreeMap::new(),
+        category_crates: category_crates
+            .into_iter()
+            .map(|(category, crates)| (category, crates.into_iter().collect()))
+            .collect(),
+        dependency_chains: dependency_chains
+            .into_iter()
+            .map(|(category, crates)| {
+                let crates = crates
+                    .into_iter()
+                    .map(|(krate, (_log, chain))| (krate, chain))
+                    .collect();
+                (category, crates)
+            })
+            .collect(),
+        yanked: if config.yanked == YankedHandling::Annotate {
+            yanked.iter().cloned().collect()
+        } else {
+            Vec::new()
+        },
+        local_crates: Vec::new(),
+        excluded_yanked: if config.yanked == YankedHandling::Exclude {
+            yanked.into_iter().collect()
+        } else {
+            Vec::new()
+        },
+        finding_matches: finding_matches
+            .into_iter()
+            .map(|(category, mut matches)| {
+                // runs are analyzed in any order, sort them so that reports are reproducible
+                matches.sort_by(|a, b| a.run_id.cmp(&b.run_id));
+                (category, matches)
+            })
+            .collect(),
+        fingerprints,
+        known_failures: BTreeMap::new(),
+        relaxed_matches: BTreeMap::new(),
+        thousands_separator: config.thousands_separator.clone(),
+        crater_results: BTreeMap::new(),
+        run_results: BTreeMap::new(),
+        crate_urls: BTreeMap::new(),
+        expected_krate_result: config.expected_krate_result(),
+        expected_run_result: config.expected_run_result(),
+        selection: config.select.as_ref().map(|select| select.to_string()),
+        reproducibility_hash: Some(reproducibility_hash),
+        config_hash: Some(config.config_hash()),
+        owners: BTreeMap::new(),
+        sections: config.sections.keys().cloned().zip(sections).collect(),
+        warnings: config.warnings.as_ref().map(|warnings| WarningsReport {
+            expected_krate_result: warnings.crate_result.to_string(),
+            expected_run_result: warnings.run_result.to_string(),
+            krate_count: warning_krate_count,
+            run_count: warning_run_count,
+            findings: warning_findings,
+        }),
+        comparison: None,
+        popularity: None,
+        log_sizes: Some(log_sizes),
+        durations,
+        linker_issues: LinkerIssue::aggregate(linker_crates),
+        resolver_culprits: ResolverCulprit::aggregate(resolver_crates),
+        toolchain_mismatches: {
+            // runs are analyzed in any order, sort them so that reports are reproducible
+            toolchain_mismatches
+                .sort_by(|a: &ToolchainMismatch, b| (&a.krate, &a.log).cmp(&(&b.krate, &b.log)));
+            toolchain_mismatches
+        },
+        tagged_crates: BTreeMap::new(),
+    };
+
+    analysis.descriptions = config
+        .category_descriptions()
+        .into_iter()
+        .filter(|(name, _)| {
+            analysis.findings.contains_key(name)
+                || analysis
+                    .warnings
+                    .as_ref()
+                    .is_some_and(|warnings| warnings.findings.contains_key(name))
+        })
+        .collect();
+    for name in analysis.findings.keys() {
+        if let Some(description) = config
+            .extractors
+            .iter()
+            .filter(|extractor| extractor.may_produce(name))
+            .find_map(|extractor| extractor.description.as_ref())
+        {
+            analysis
+                .descriptions
+                .entry(name.clone())
+                .or_insert_with(|| description.clone());
+        }
+    }
+
+    analysis.owners = config.category_owners(analysis.findings.keys());
+
+    // a category matched in one run of a crate and relaxed in another only hints at an unrelated failure
+    for (name, crates) in &mut relaxed_crates {
+        if let Some(matched) = analysis.category_crates.get(name) {
+            crates.retain(|krate, _| matched.binary_search(krate).is_err());
+        }
+    }
+    relaxed_crates.retain(|_, crates| !crates.is_empty());
+    analysis.relaxed_matches = relaxed_crates;
+
+    let selected_crates = analysis.crate_categories();
+    analysis.crater_results = report
+        .crates
+        .iter()
+        .filter(|krate| selected_crates.contains_key(&krate.name))
+        .map(|krate| (krate.name.clone(), krate.res.clone()))
+        .collect();
+    analysis.run_results = report
+        .crates
+        .iter()
+        .filter(|krate| selected_crates.contains_key(&krate.name))
+        .map(|krate| {
+            let results = krate
+                .runs
+                .iter()
+                .map(|run| run.as_ref().map(|run| run.res.clone()))
+                .collect();
+            (krate.name.clone(), results)
+        })
+        .collect();
+
+    let all_crate_urls = report
+        .crates
+        .iter()
+        .filter_map(|krate| Some((krate.name.as_str(), krate.url.as_deref()?)))
+        .collect();
+    analysis.tag_crates(&crate_tags, &all_crate_urls);
+
+    let mentioned_crates = analysis.mentioned_crates();
+    let (local_crates, crates): (Vec<_>, Vec<_>) = report
+        .crates
+        .iter()
+        .chain(&excluded_crates)
+        .filter(|krate| mentioned_crates.contains(krate.name.as_str()))
+        .partition(|krate| krate.is_local());
+    analysis.crate_urls = crates
+        .into_iter()
+        .filter_map(|krate| Some((krate.name.clone(), krate.url.clone()?)))
+        .collect();
+    let mut local_crates = local_crates
+        .into_iter()
+        .map(|krate| krate.name.clone())
+        .collect::<Vec<_>>();
+    local_crates.sort();
+    analysis.local_crates = local_crates;
+
+    metrics::EXPERIMENTS_ANALYZED.inc();
+    Ok(analysis)
+}
+
+/// The crate names of each tag in the [crate tags file](Config::crate_tags_file), if one is configured.
+fn load_crate_tags(config: &Config) -> Result<BTreeMap<String, BTreeSet<String>>, AnalysisError> {
+    if config.crate_tags_file.is_empty() {
+        return Ok(BTreeMap::new());
+    }
+    let content = std::fs::read_to_string(&config.crate_tags_file).map_err(|err| {
+        AnalysisError::InvalidConfig(format!(
+            "failed to read the crate tags '{}': {err}",
+            config.crate_tags_file
+        ))
+    })?;
+    toml::from_str(&content).map_err(|err| {
+        AnalysisError::InvalidConfig(format!(
+            "invalid crate tags '{}': {err}",
+            config.crate_tags_file
+        ))
+    })
+}
+
+/// The classified runs streamed as JSON lines while an experiment is analyzed, see [`Config::findings_stream`].
+pub fn findings_stream_path(experiment: &str) -> String {
+    format!("results/{experiment}/findings.jsonl")
+}
+
+/// The partial report written while an experiment is analyzed, see [`run_analysis`].
+pub fn live_report_path(experiment: &str) -> String {
+    format!("results/{experiment}/live.report")
+}
+
+/// The findings of the first `analyzed` of `total` selected runs, most common categories first.
+fn render_live_report(
+    experiment: &str,
+    analyzed: usize,
+    total: usize,
+    findings: &BTreeMap<String, usize>,
+    unclassified: usize,
+) -> String {
+    let mut findings = findings.iter().collect::<Vec<_>>();
+    findings.sort_by(|(name_a, count_a), (name_b, count_b)| {
+        count_b.cmp(count_a).then(name_a.cmp(name_b))
+    });
+
+    let mut out = format!(
+        "Partial report for {experiment}: {analyzed} of {total} runs analyzed ({:.1}%)\n",
+        percent(analyzed, total)
+    );
+    for (name, count) in findings {
+        let _ = writeln!(
+            out,
+            "{name}: {count} ({:.1}% so far)",
+            percent(*count, analyzed)
+        );
+    }
+    let _ = writeln!(
+        out,
+        "unclassified: {unclassified} ({:.1}% so far)",
+        percent(unclassified, analyzed)
+    );
+    out
+}
+
+/// Persists the skip list and manifest of the experiment's cache,
+/// failing to do so only makes the next run slower, so it doesn't fail the analysis.
+fn save_cache_metadata(
+    experiment: &str,
+    missing_logs: &MissingLogs,
+    log_count: usize,
+    ruleset_hash: Option<String>,
+) {
+    if let Err(err) = missing_logs.save() {
+        log::warn!("Failed to save the missing logs of {experiment}: {err}");
+    }
+    if let Err(err) = CacheManifest::record_use(experiment, log_count, ruleset_hash) {
+        log::warn!("Failed to save the cache manifest of {experiment}: {err}");
+    }
+}
+
+/// Lists the slowest of the timed runs and compares the durations of the baseline and candidate runs,
+/// looking up the logs of runs that weren't selected in the cache.
+async fn build_durations(
+    experiment: &str,
+    report: &Results,
+    run_durations: BTreeMap<String, BTreeMap<usize, (String, u64)>>,
+) -> Option<BuildDurations> {
+    if run_durations.is_empty() {
+        return None;
+    }
+
+    let mut regressions = Vec::new();
+    for krate in &report.crates {
+        let Some(timed) = run_durations.get(&krate.name) else {
+            continue;
+        };
+        let mut seconds = [None, None];
+        for (index, seconds) in seconds.iter_mut().enumerate() {
+            let Some(Some(run)) = krate.runs.get(index) else {
+                continue;
+            };
+            *seconds = match timed.get(&index) {
+                Some((_, timed)) => Some(*timed),
+                None => get_cached_log(experiment, &run.log)
+                    .await
+                    .as_deref()
+                    .and_then(log_duration),
+            };
+        }
+        if let [Some(baseline_seconds), Some(candidate_seconds)] = seconds
+            && candidate_seconds > baseline_seconds
+        {
+            regressions.push(DurationRegression {
+                krate: krate.name.clone(),
+                baseline_seconds,
+                candidate_seconds,
+            });
+        }
+    }
+    // ties are ordered by crate, so that reports are reproducible
+    regressions.sort_by(|a, b| {
+        (b.candidate_seconds - b.baseline_seconds)
+            .cmp(&(a.candidate_seconds - a.baseline_seconds))
+            .then_with(|| a.krate.cmp(&b.krate))
+    });
+    regressions.truncate(SLOWEST_BUILDS);
+
+    let mut slowest = run_durations
+        .into_iter()
+        .flat_map(|(krate, runs)| {
+            runs.into_iter()
+                .map(move |(index, (log, seconds))| CrateDuration {
+                    run_id: run_id(experiment, &krate, index),
+                    krate: krate.clone(),
+                    log,
+                    seconds,
+                })
+        })
+        .collect::<Vec<_>>();
+    slowest.sort_by(|a, b| b.seconds.cmp(&a.seconds).then_with(|| a.log.cmp(&b.log)));
+    slowest.truncate(SLOWEST_BUILDS);
+
+    Some(BuildDurations {
+        slowest,
+        regressions,
+    })
+}
+
+/// The outcome of classifying a single log.
+struct AnalyzedRun {
+    log_bytes: u64,
+    findings: LogFindings,
+    excerpts: Vec<(String, Excerpt)>,
+    /// The dependency chain printed after the matched line of each category, if any
+    chains: Vec<(String, Vec<String>)>,
+    /// The normalized matched line of each category, to fingerprint the failure
+    error_lines: Vec<(String, String)>,
+    /// The normalized last error line, only determined for logs without findings
+    error_line: Option<String>,
+    /// Seconds between the first and last timestamp of the log, only determined for selected runs
+    duration: Option<u64>,
+    /// Libraries and symbols the linker failed to find, only determined for selected runs
+    linker: BTreeSet<LinkerItem>,
+    /// Dependencies cargo failed to resolve, only determined for selected runs
+    resolver: BTreeSet<ResolverFailure>,
+    /// Categories only matching with relaxed matching, only determined with [`Config::relaxed_diagnostics`]
+    relaxed: Vec<(String, Relaxation)>,
+    /// Categories reported by the external classifiers
+    external: BTreeSet<String>,
+    /// The `rustc --version` banner if it doesn't fit the run's toolchain, only determined for selected runs
+    unexpected_rustc: Option<String>,
+}
+
+/// Whether a log is classified as a failure, checked for warnings or classified for a further section
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RunKind {
+    Interesting,
+    Warnings,
+    /// Index into the [sections](Config::sections) in order of their names
+    Section(usize),
+}
+
+/// Downloads the report and all interesting logs of an experiment into the cache without classifying them.
+///
+/// Already cached files are skipped and downloads are only persisted once complete,
+/// so an interrupted prefetch can simply be restarted and picks up where it left off.
+pub async fn prefetch(
+    config: &Config,
+    client: &Client,
+    experiment: &str,
+    report_ps: &ProgressBar,
+    multi: &MultiProgress,
+    parallelism: usize,
+) -> Result<(), AnalysisError> {
+    prepare_experiment_dir(experiment)?;
+
+    report_ps.set_message(format!("Getting Crater Report for {experiment}"));
+    report_ps.enable_steady_tick(Duration::from_millis(100));
+    let mode = get_experiment_mode(client, multi, experiment).await;
+    let config = &match &mode {
+        Some(mode) => Cow::Owned(config.for_mode(mode)),
+        None => Cow::Borrowed(config),
+    };
+    let mut report = match get_report(
+        client,
+        multi,
+        experiment,
+        config.regenerated_reports,
+        |krate| is_relevant_crate(config, krate),
+    )
+    .await
+    {
+        Err(AnalysisError::NotFound(_)) => {
+            return Err(unknown_experiment(client, &config.crater_api_url, experiment).await);
+        }
+        report => report?,
+    };
+    report.reconstruct_missing_logs();
+
+    let missing_logs = MissingLogs::load(experiment, config.missing_log_expiry());
+    let ruleset_hash = &config.ruleset_hash();
+
+    let (_, mut interesting_runs) = select_interesting_runs(config, &report);
+    if let Some(warnings) = &config.warnings {
+        interesting_runs.extend(select_warning_runs(warnings, &report).1);
+    }
+    for section in config.sections.values() {
+        interesting_runs.extend(select_section_runs(section, &report).1);
+    }
+
+    let log_pb = multi.add(
+        ProgressBar::new(interesting_runs.len() as u64)
+            .with_message(format!("Prefetching logs for {experiment}")),
+    );
+    log_pb.set_style(
+        ProgressStyle::with_template("{msg} {wide_bar} {human_pos}/{human_len} ETA {eta_precise}")
+            .unwrap(),
+    );
+    report_ps.set_message(format!("Prefetching logs for {experiment}"));
+
+    let connectivity = Connectivity::default();
+    let store = LogStore::new(config.share_logs).with_compressions(&config.compressed_logs);
+    let failed = futures::stream::iter(interesting_runs)
+        .map(|SelectedRun { run, .. }| {
+            let log_pb = &log_pb;
+            let missing_logs = &missing_logs;
+            let store = &store;
+            let connectivity = &connectivity;
+            async move {
+                if !connectivity.ready().await {
+                    return true;
+                }
+                let log = get_log(
+                    client,
+                    multi,
+                    store,
+                    missing_logs,
+                    experiment,
+                    &run.log,
+                    ruleset_hash,
+                )
+                .await;
+                log_pb.inc(1);
+                if connectivity.record(&log, multi).await
+                    && let Err(err) = &log
+                {
+                    log::warn!("Failed to get log '{}': {err}", run.log);
+                }
+                log.is_err()
+            }
+        })
+        .buffer_unordered(parallelism)
+        .filter(|failed| std::future::ready(*failed))
+        .count()
+        .await;
+    connectivity.check()?;
+
+    log_pb.finish();
+    save_cache_metadata(
+        experiment,
+        &missing_logs,
+        log_pb.position() as usize - failed,
+        None,
+    );
+    let reused = match store.reuse() {
+        Some(reuse) if reuse.reused > 0 => {
+            format!(", {} reused from other experiments", reuse.reused)
+        }
+        _ => String::new(),
+    };
+    if failed == 0 {
+        report_ps.finish_with_message(format!("Prefetched all logs for {experiment}{reused}"));
+    } else {
+        report_ps.finish_with_message(format!(
+            "Prefetched logs for {experiment}{reused}, {failed} failed (rerun prefetch to retry them)"
+        ));
+    }
+
+    Ok(())
+}
+
+/// Classifies the runs previously attributed to `category` in the analysis of `experiment` with the category's
+/// [refinement](Config::refinements) and moves them to the more specific categories they match,
+/// see [`AnalysisReport::refine_category`].
+///
+/// Only the logs of those runs are read, all other runs keep their categories without being classified again.
+pub async fn refine_analysis(
+    config: &Config,
+    client: &Client,
+    experiment: &str,
+    category: &str,
+    report_ps: &ProgressBar,
+    multi: &MultiProgress,
+    parallelism: usize,
+) -> Result<AnalysisReport, AnalysisError> {
+    let mut report = match load_analysis(experiment).await {
+        Ok(report) => report,
+        Err(AnalysisError::Io(err)) if err.kind() == std::io::ErrorKind::NotFound => {
+            return Err(AnalysisError::InvalidArguments(format!(
+                "{experiment} has not been analyzed yet, analyze it before refining its categories"
+            )));
+        }
+        Err(err) => return Err(err),
+    };
+    let config = &match &report.mode {
+        Some(mode) => Cow::Owned(config.for_mode(mode)),
+        None => Cow::Borrowed(config),
+    };
+    let refinement = config.refinements.get(category).ok_or_else(|| {
+        AnalysisError::InvalidConfig(format!(
+            "no refinement configured for '{category}', add targets to [refinements.\"{category}\"]"
+        ))
+    })?;
+    let Some(runs) = report.finding_matches.get(category).cloned() else {
+        return Err(AnalysisError::InvalidArguments(format!(
+            "no run of {experiment} was attributed to '{category}'"
+        )));
+    };
+    let redactor = &config
+        .redactor()
+        .map_err(|err| AnalysisError::InvalidConfig(format!("invalid redact pattern: {err}")))?;
+    let missing_logs = MissingLogs::load(experiment, config.missing_log_expiry());
+
+    let refine_pb = multi.add(
+        ProgressBar::new(runs.len() as u64)
+            .with_message(format!("Refining '{category}' of {experiment}")),
+    );
+    refine_pb.set_style(
+        ProgressStyle::with_template("{msg} {wide_bar} {human_pos}/{human_len} ETA {eta_precise}")
+            .unwrap(),
+    );
+    report_ps.set_message(format!("Refining '{category}' of {experiment}"));
+    report_ps.enable_steady_tick(Duration::from_millis(100));
+
+    let connectivity = Connectivity::default();
+    let store = LogStore::new(config.share_logs)
+        .with_compressions(&config.compressed_logs)
+        .with_memory_budget(config.log_memory_budget_mib);
+    let refined_runs = futures::stream::iter(runs)
+        .map(|run| {
+            let refine_pb = &refine_pb;
+            let missing_logs = &missing_logs;
+            let store = &store;
+            let connectivity = &connectivity;
+            async move {
+                if !connectivity.ready().await {
+                    return None;
+                }
+                // no rule set pruned logs with, as they may lack the lines the refinement looks for
+                let log =
+                    get_log(client, multi, store, missing_logs, experiment, &run.log, "").await;
+                refine_pb.inc(1);
+                if connectivity.record(&log, multi).await
+                    && let Err(err) = &log
+                {
+                    log::warn!("Failed to get log '{}': {err}", run.log);
+                }
+                let (log, _permit) = log.ok()?;
+                let log = truncate_long_lines(&log, config.max_line_length);
+                metrics::LOGS_CLASSIFIED.inc();
+                let samples = process_refinement(refinement, &log)
+                    .into_iter()
+                    .map(|(name, line_match)| {
+                        let mut excerpt = Excerpt::extract(
+                            &log,
+                            line_match.line_number,
+                            config.excerpt_context(&name),
+                        );
+                        excerpt.redact(redactor);
+                        let sample = Sample {
+                            krate: run.krate.clone(),
+                            run_id: run.run_id.clone(),
+                            log: run.log.clone(),
+                            excerpt,
+                        };
+                        (name, sample)
+                    })
+                    .collect::<Vec<_>>();
+                Some(samples)
+            }
+        })
+        .buffer_unordered(parallelism)
+        .filter_map(std::future::ready)
+        .collect::<Vec<_>>()
+        .await;
+    connectivity.check()?;
+    refine_pb.finish();
+    if let Err(err) = missing_logs.save() {
+        log::warn!("Failed to save the missing logs of {experiment}: {err}");
+    }
+
+    let mut refined = BTreeMap::<String, Vec<Sample>>::new();
+    for (name, sample) in refined_runs.into_iter().flatten() {
+        refined.entry(name).or_default().push(sample);
+    }
+    report.refine_category(category, refined);
+    report.reproducibility_hash = report
+        .reproducibility_hash
+        .map(|hash| config.refined_reproducibility_hash(&hash, category));
+
+    let descriptions = config.category_descriptions();
+    for name in report
+        .refined_categories
+        .get(category)
+        .into_iter()
+        .flatten()
+    {
+        if let Some(description) = descriptions.get(name) {
+            report
+                .descriptions
+                .insert(name.clone(), description.clone());
+        }
+    }
+    report.owners = config.category_owners(report.findings.keys());
+    report.thousands_separator = config.thousands_separator.clone();
+    Ok(report)
+}
@@ -0,0 +1,120 @@
+//! Synchronous wrappers around the analysis pipeline, for tools that aren't async.
+//!
+//! An [`Analyzer`] owns the tokio runtime and HTTP client driving the async pipeline,
+//! so embedding the analysis doesn't require a runtime of the caller's own.
+//! Like the command line tool, it works on the `results/` cache below the current directory.
+
+use std::sync::Arc;
+
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget};
+use reqwest::Client;
+use tokio::runtime::Runtime;
+
+use crate::{
+    APP_USER_AGENT, AnalysisError, analysis,
+    engine::{AnalysisReport, Config, ExampleLimits, ReportFormat},
+    output,
+};
+
+/// Analyzes experiments synchronously, blocking the calling thread until each call completed.
+///
+/// Must not be used from within an async context, as dropping or blocking on a runtime there panics.
+pub struct Analyzer {
+    runtime: Runtime,
+    client: Client,
+    multi: MultiProgress,
+    parallelism: usize,
+}
+
+impl Analyzer {
+    /// Starts the runtime, the analysis runs without progress bars.
+    pub fn new() -> Result<Self, AnalysisError> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()?;
+        let client = Client::builder().user_agent(APP_USER_AGENT).build()?;
+        let parallelism =
+            std::thread::available_parallelism().map_or(20, |available| available.get());
+        Ok(Self {
+            runtime,
+            client,
+            multi: MultiProgress::with_draw_target(ProgressDrawTarget::hidden()),
+            parallelism,
+        })
+    }
+
+    /// Limits the number of logs downloaded and classified at the same time, defaults to the available parallelism.
+    pub fn with_parallelism(mut self, parallelism: usize) -> Self {
+        self.parallelism = parallelism.max(1);
+        self
+    }
+
+    /// Downloads and classifies an experiment, see [`analysis::run_analysis`].
+    pub fn analyze(
+        &self,
+        config: &Arc<Config>,
+        experiment: &str,
+    ) -> Result<AnalysisReport, AnalysisError> {
+        self.runtime.block_on(analysis::run_analysis(
+            config,
+            &self.client,
+            experiment,
+            &ProgressBar::hidden(),
+            &self.multi,
+            self.parallelism,
+            None,
+        ))
+    }
+
+    /// Reclassifies the runs of a category of an already analyzed experiment, see [`analysis::refine_analysis`].
+    pub fn refine(
+        &self,
+        config: &Config,
+        experiment: &str,
+        category: &str,
+    ) -> Result<AnalysisReport, AnalysisError> {
+        self.runtime.block_on(analysis::refine_analysis(
+            config,
+            &self.client,
+            experiment,
+            category,
+            &ProgressBar::hidden(),
+            &self.multi,
+            self.parallelism,
+        ))
+    }
+
+    /// Downloads an experiment into the cache without classifying it, see [`analysis::prefetch`].
+    pub fn prefetch(&self, config: &Config, experiment: &str) -> Result<(), AnalysisError> {
+        self.runtime.block_on(analysis::prefetch(
+            config,
+            &self.client,
+            experiment,
+            &ProgressBar::hidden(),
+            &self.multi,
+            self.parallelism,
+        ))
+    }
+
+    /// Writes the JSON analysis of an experiment, see [`output::write_analysis`].
+    pub fn write_analysis(&self, report: &AnalysisReport) -> Result<String, AnalysisError> {
+        self.runtime.block_on(output::write_analysis(report))
+    }
+
+    /// Loads the JSON analysis of an already analyzed experiment, see [`output::load_analysis`].
+    pub fn load_analysis(&self, experiment: &str) -> Result<AnalysisReport, AnalysisError> {
+        self.runtime.block_on(output::load_analysis(experiment))
+    }
+
+    /// Renders and writes a report to the [configured path](Config::report_path), see [`output::write_report`].
+    pub fn write_report(
+        &self,
+        report: &AnalysisReport,
+        format: ReportFormat,
+        limits: &ExampleLimits,
+        config: &Config,
+    ) -> Result<String, AnalysisError> {
+        self.runtime
+            .block_on(output::write_report(report, format, limits, config))
+    }
+}
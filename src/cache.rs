@@ -0,0 +1,1355 @@
+//! The on-disk cache of crater reports and logs below `results/`.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+    sync::{
+        Mutex, OnceLock,
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+    },
+    time::{Duration, SystemTime},
+};
+
+use futures::StreamExt as _;
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use memmap2::Mmap;
+use reqwest::Client;
+use tempfile::NamedTempFile;
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+use crate::{
+    AnalysisError,
+    engine::{
+        CrateResult, ExperimentStatus, LogCompression, LogReuse, RegeneratedReportHandling,
+        Results, crates_io_version, parse_http_date, similar_names,
+    },
+    metrics,
+};
+
+pub fn prepare_experiment_dir(experiment: &str) -> Result<(), AnalysisError> {
+    if !std::fs::exists(format!("results/{experiment}"))? {
+        std::fs::create_dir_all(format!("results/{experiment}"))?;
+        std::fs::write(
+            format!("results/{experiment}/CACHEDIR.TAG"),
+            CACHEDIR_TAG_CONTENT,
+        )?;
+    }
+    Ok(())
+}
+
+const CACHEDIR_TAG_CONTENT: &str = "\
+Signature: 8a477f597d28d172789f06886806bc55
+# This file is a cache directory tag created by https://github.com/Skgland/Crater-Analysis
+# For information about cache directory tags, see:
+#	http://www.brynosaurus.com/cachedir/
+";
+
+/// Gets the crater results of an experiment, only keeping the crates for which `keep` returns true.
+pub async fn get_report(
+    client: &Client,
+    multi: &MultiProgress,
+    experiment: &str,
+    regenerated: RegeneratedReportHandling,
+    keep: impl Fn(&CrateResult) -> bool,
+) -> Result<Results, AnalysisError> {
+    let results = get_report_json(client, multi, experiment, regenerated).await?;
+    Ok(Results::from_slice_filtered(&results, keep)?)
+}
+
+/// Gets crater's `results.json` of an experiment as is.
+///
+/// Unless `regenerated` is [`Ignore`](RegeneratedReportHandling::Ignore), its `Last-Modified` date is
+/// looked up and the cache is refreshed if crater regenerated the report after the cached one was downloaded.
+pub async fn get_report_json(
+    client: &Client,
+    multi: &MultiProgress,
+    experiment: &str,
+    regenerated: RegeneratedReportHandling,
+) -> Result<Mmap, AnalysisError> {
+    let result_json_path = format!("results/{experiment}/results.json");
+    let result_json_url =
+        format!("https://crater-reports.s3.amazonaws.com/{experiment}/results.json");
+    let generated_at = match regenerated {
+        RegeneratedReportHandling::Ignore => None,
+        RegeneratedReportHandling::Results | RegeneratedReportHandling::Logs => {
+            report_generated_at(client, &result_json_url).await
+        }
+    };
+    let Some(generated_at) = generated_at else {
+        return get_or_download_file(client, multi, result_json_path.as_ref(), &result_json_url)
+            .await;
+    };
+
+    let cached_at = CacheManifest::load(experiment)
+        .and_then(|manifest| manifest.report_generated_at)
+        .or_else(|| {
+            // caches from before the generation was recorded, by when the report was downloaded
+            let modified = std::fs::metadata(&result_json_path).ok()?.modified().ok()?;
+            Some(
+                modified
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .ok()?
+                    .as_secs(),
+            )
+        });
+    let results = if cached_at.is_some_and(|cached_at| cached_at < generated_at) {
+        log::warn!(
+            "The crater report of {experiment} was regenerated since it was cached, refreshing the cache"
+        );
+        invalidate_report(experiment, regenerated)?;
+        // not from the fallback cache, which may hold the outdated report as well
+        metrics::CACHE_MISSES.inc();
+        let file =
+            download_file(client, multi, result_json_path.as_ref(), &result_json_url).await?;
+        unsafe { Mmap::map(&file)? }
+    } else {
+        get_or_download_file(client, multi, result_json_path.as_ref(), &result_json_url).await?
+    };
+    if let Err(err) = CacheManifest::record_report_generation(experiment, generated_at) {
+        log::warn!("Failed to record when the report of {experiment} was generated: {err}");
+    }
+    Ok(results)
+}
+
+/// The `Last-Modified` date of a crater report as a unix timestamp,
+/// `None` if it can't be looked up, in which case the cached report is used as is.
+async fn report_generated_at(client: &Client, url: &str) -> Option<u64> {
+    let response = client
+        .head(url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .inspect_err(|err| log::debug!("Failed to check whether '{url}' was regenerated: {err}"))
+        .ok()?;
+    let last_modified = response
+        .headers()
+        .get(reqwest::header::LAST_MODIFIED)?
+        .to_str()
+        .ok()?;
+    parse_http_date(last_modified)
+}
+
+/// Drops the cached `results.json` and `config.json` of an experiment and the logs that were missing, and with
+/// [`Logs`](RegeneratedReportHandling::Logs) also the cached logs.
+fn invalidate_report(
+    experiment: &str,
+    regenerated: RegeneratedReportHandling,
+) -> Result<(), AnalysisError> {
+    let dir = Path::new("results").join(experiment);
+    let files = [
+        dir.join("results.json"),
+        dir.join("config.json"),
+        dir.join("missing-logs.json"),
+    ];
+    if regenerated == RegeneratedReportHandling::Logs {
+        match std::fs::remove_dir_all(dir.join("logs")) {
+            Err(err) if err.kind() != ErrorKind::NotFound => return Err(err.into()),
+            _ => {}
+        }
+    }
+    for file in files {
+        match std::fs::remove_file(&file) {
+            Err(err) if err.kind() != ErrorKind::NotFound => return Err(err.into()),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// The mode of the experiment (e.g. `build-and-test` or `clippy`) from the `config.json` crater publishes
+/// with its report, or `None` if the experiment has none or it can't be fetched.
+pub async fn get_experiment_mode(
+    client: &Client,
+    multi: &MultiProgress,
+    experiment: &str,
+) -> Option<String> {
+    #[derive(serde::Deserialize)]
+    struct ExperimentConfig {
+        mode: Option<String>,
+    }
+
+    let config_path = format!("results/{experiment}/config.json");
+    let config_url = format!("https://crater-reports.s3.amazonaws.com/{experiment}/config.json");
+    let config = match get_or_download_file(client, multi, config_path.as_ref(), &config_url).await
+    {
+        Ok(config) => config,
+        Err(AnalysisError::NotFound(_)) => return None,
+        Err(err) => {
+            // the mode only refines the analysis, so don't fail it
+            log::warn!("Failed to get the config of {experiment}: {err}");
+            return None;
+        }
+    };
+    serde_json::from_slice::<ExperimentConfig>(&config)
+        .inspect_err(|err| log::warn!("Ignoring invalid config of {experiment}: {err}"))
+        .ok()?
+        .mode
+}
+
+/// The status and dates of the experiment from crater's API at `api_url` (see [`Config::crater_api_url`](crate::engine::Config::crater_api_url)),
+/// or `None` if they can't be fetched.
+///
+/// Unlike the results the status is not cached, as it changes while the experiment runs.
+pub async fn get_experiment_status(
+    client: &Client,
+    api_url: &str,
+    experiment: &str,
+) -> Option<ExperimentStatus> {
+    let url = api_url.replace("{experiment}", experiment);
+    let status = async {
+        let response = client.get(&url).send().await?.error_for_status()?;
+        let status = serde_json::from_slice::<ExperimentStatus>(&response.bytes().await?)?;
+        Ok::<_, AnalysisError>(status)
+    };
+    // the status is only informational, so don't fail the analysis
+    status
+        .await
+        .inspect_err(|err| log::warn!("Failed to get the status of {experiment}: {err}"))
+        .ok()
+}
+
+/// The error for an experiment crater has no `results.json` for, suggesting similarly named experiments
+/// from crater's experiment list (`api_url` without `/{experiment}`) and the cache below `results/`.
+///
+/// What was already written for the experiment is removed again, so that the typo doesn't show up as a cache.
+pub async fn unknown_experiment(client: &Client, api_url: &str, experiment: &str) -> AnalysisError {
+    let dir = Path::new("results").join(experiment);
+    if !dir.join("results.json").exists() {
+        for file in ["CACHEDIR.TAG", "config-used.toml"] {
+            let _ = std::fs::remove_file(dir.join(file));
+        }
+        // only succeeds if nothing else was cached for it
+        let _ = std::fs::remove_dir(&dir);
+    }
+
+    let mut known = match api_url.strip_suffix("/{experiment}") {
+        Some(list_url) if !list_url.is_empty() => get_experiment_names(client, list_url)
+            .await
+            .inspect_err(|err| log::warn!("Failed to get crater's experiment list: {err}"))
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    };
+    known.extend(
+        list_caches()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(name, _)| name)
+            .filter(|name| {
+                Path::new("results")
+                    .join(name)
+                    .join("results.json")
+                    .exists()
+            }),
+    );
+    let suggestions = similar_names(experiment, known.iter().map(String::as_str));
+    match suggestions.as_slice() {
+        [] => AnalysisError::NotFound(format!("experiment {experiment}")),
+        [suggestion, ..] => AnalysisError::InvalidArguments(format!(
+            "experiment '{experiment}' does not exist upstream, did you mean {suggestion}?"
+        )),
+    }
+}
+
+/// The names of the experiments listed by crater's API at `list_url`,
+/// given either as names or as objects with a `name`.
+async fn get_experiment_names(
+    client: &Client,
+    list_url: &str,
+) -> Result<Vec<String>, AnalysisError> {
+    let response = client.get(list_url).send().await?.error_for_status()?;
+    let list = serde_json::from_slice::<Vec<serde_json::Value>>(&response.bytes().await?)?;
+    Ok(list
+        .into_iter()
+        .filter_map(|entry| match entry {
+            serde_json::Value::String(name) => Some(name),
+            entry => Some(entry.get("name")?.as_str()?.to_string()),
+        })
+        .collect())
+}
+
+/// Logs that don't exist upstream, persisted per experiment
+/// so that they are not requested again on every run until their entry expires.
+pub struct MissingLogs {
+    path: PathBuf,
+    /// log to the unix timestamp at which it was found missing
+    entries: Mutex<BTreeMap<String, u64>>,
+}
+
+impl MissingLogs {
+    /// Loads the skip list of an experiment, dropping entries older than `expiry`.
+    pub fn load(experiment: &str, expiry: Duration) -> Self {
+        let path = PathBuf::from(format!("results/{experiment}/missing-logs.json"));
+        let mut entries: BTreeMap<String, u64> = match std::fs::read(&path) {
+            Ok(content) => serde_json::from_slice(&content).unwrap_or_else(|err| {
+                log::warn!("Ignoring invalid skip list '{}': {err}", path.display());
+                BTreeMap::new()
+            }),
+            Err(err) => {
+                if err.kind() != ErrorKind::NotFound {
+                    log::warn!("Failed to read skip list '{}': {err}", path.display());
+                }
+                BTreeMap::new()
+            }
+        };
+
+        let now = unix_timestamp();
+        entries.retain(|_, &mut since| now.saturating_sub(since) < expiry.as_secs());
+
+        Self {
+            path,
+            entries: Mutex::new(entries),
+        }
+    }
+
+    pub fn contains(&self, log: &str) -> bool {
+        self.entries.lock().unwrap().contains_key(log)
+    }
+
+    pub fn insert(&self, log: &str) {
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(log.to_string(), unix_timestamp());
+    }
+
+    pub fn save(&self) -> Result<(), AnalysisError> {
+        let content = serde_json::to_vec_pretty(&*self.entries.lock().unwrap())?;
+        std::fs::write(&self.path, content)?;
+        Ok(())
+    }
+}
+
+/// Provenance of an experiment's cache, stored as `results/<experiment>/cache-manifest.json`.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct CacheManifest {
+    /// Version of this tool that last used the cache
+    pub tool_version: String,
+    /// Unix timestamp of the first use of the cache
+    pub created_at: u64,
+    /// Unix timestamp of the last analysis or prefetch
+    pub last_used_at: u64,
+    /// Unix timestamp of the last analysis
+    #[serde(default)]
+    pub last_analyzed_at: Option<u64>,
+    /// Number of logs available during the last use
+    pub log_count: usize,
+    /// [Rule set hash](crate::engine::Config::ruleset_hash) of the last analysis
+    #[serde(default)]
+    pub ruleset_hash: Option<String>,
+    /// Unix timestamp of when crater generated the cached report, its `Last-Modified` date
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub report_generated_at: Option<u64>,
+}
+
+impl CacheManifest {
+    fn path(experiment: &str) -> String {
+        format!("results/{experiment}/cache-manifest.json")
+    }
+
+    pub fn load(experiment: &str) -> Option<Self> {
+        let content = std::fs::read(Self::path(experiment)).ok()?;
+        serde_json::from_slice(&content)
+            .inspect_err(|err| log::warn!("Ignoring invalid cache manifest of {experiment}: {err}"))
+            .ok()
+    }
+
+    /// Records a use of the experiment's cache, `ruleset_hash` is only given for analyses.
+    pub fn record_use(
+        experiment: &str,
+        log_count: usize,
+        ruleset_hash: Option<String>,
+    ) -> Result<(), AnalysisError> {
+        let now = unix_timestamp();
+        let previous = Self::load(experiment);
+        let manifest = Self {
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            created_at: previous
+                .as_ref()
+                .map_or(now, |previous| previous.created_at),
+            last_used_at: now,
+            last_analyzed_at: if ruleset_hash.is_some() {
+                Some(now)
+            } else {
+                previous
+                    .as_ref()
+                    .and_then(|previous| previous.last_analyzed_at)
+            },
+            log_count,
+            report_generated_at: previous
+                .as_ref()
+                .and_then(|previous| previous.report_generated_at),
+            ruleset_hash: ruleset_hash
+                .or_else(|| previous.and_then(|previous| previous.ruleset_hash)),
+        };
+        manifest.save(experiment)
+    }
+
+    /// Records when crater generated the cached report of the experiment, see [`get_report_json`].
+    pub fn record_report_generation(
+        experiment: &str,
+        generated_at: u64,
+    ) -> Result<(), AnalysisError> {
+        let now = unix_timestamp();
+        let manifest = match Self::load(experiment) {
+            Some(manifest) if manifest.report_generated_at == Some(generated_at) => return Ok(()),
+            Some(manifest) => Self {
+                report_generated_at: Some(generated_at),
+                ..manifest
+            },
+            None => Self {
+                tool_version: env!("CARGO_PKG_VERSION").to_string(),
+                created_at: now,
+                last_used_at: now,
+                last_analyzed_at: None,
+                log_count: 0,
+                ruleset_hash: None,
+                report_generated_at: Some(generated_at),
+            },
+        };
+        manifest.save(experiment)
+    }
+
+    fn save(&self, experiment: &str) -> Result<(), AnalysisError> {
+        std::fs::write(Self::path(experiment), serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// All experiments with a cache below `results/` and their manifest, if any.
+pub fn list_caches() -> Result<Vec<(String, Option<CacheManifest>)>, AnalysisError> {
+    let entries = match std::fs::read_dir("results") {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut caches = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+        let experiment = entry.file_name().to_string_lossy().into_owned();
+        let manifest = CacheManifest::load(&experiment);
+        caches.push((experiment, manifest));
+    }
+    caches.sort_by(|(a, _), (b, _)| a.cmp(b));
+    Ok(caches)
+}
+
+/// Folders below `results/` shared by all experiments rather than caching one.
+const SHARED_CACHE_DIRS: [&str; 3] = ["log-store", "top-crates", "crates-io"];
+
+/// Removes the cache of `experiment`, or only its logs with `logs_only`, returning the removed paths.
+///
+/// Unlike removing `results/<experiment>` by hand, this leaves the other experiments' caches alone: the `dot` folder
+/// in it holds the [legacy logs](legacy_log_path) of the experiment named the same with a trailing dot, whose
+/// legacy logs are in turn purged from there. Logs shared through the log store stay in the store.
+pub fn purge_cache(experiment: &str, logs_only: bool) -> Result<Vec<PathBuf>, AnalysisError> {
+    let mut components = Path::new(experiment).components();
+    if !matches!(
+        (components.next(), components.next()),
+        (Some(std::path::Component::Normal(_)), None)
+    ) || SHARED_CACHE_DIRS.contains(&experiment)
+    {
+        return Err(AnalysisError::InvalidArguments(format!(
+            "'{experiment}' is not the name of an experiment"
+        )));
+    }
+
+    let dir = PathBuf::from(format!("results/{experiment}"));
+    let logs = dir.join("logs");
+    let legacy_logs = PathBuf::from(format!("results/{experiment}/logs").replace("./", "/dot/"));
+    if !dir.exists() && !legacy_logs.exists() {
+        return Err(AnalysisError::InvalidArguments(format!(
+            "there is no cache of {experiment}"
+        )));
+    }
+
+    let mut removed = Vec::new();
+    let mut remove = |path: PathBuf| -> Result<(), AnalysisError> {
+        let result = if path.is_dir() {
+            std::fs::remove_dir_all(&path)
+        } else {
+            std::fs::remove_file(&path)
+        };
+        match result {
+            Ok(()) => removed.push(path),
+            Err(err) if err.kind() == ErrorKind::NotFound => {}
+            Err(err) => return Err(err.into()),
+        }
+        Ok(())
+    };
+
+    if legacy_logs != logs {
+        remove(legacy_logs.clone())?;
+        // the folder of the legacy logs is shared with the experiment without the trailing dot
+        if let Some(legacy_dir) = legacy_logs.parent() {
+            let _ = std::fs::remove_dir(legacy_dir);
+        }
+    }
+    if logs_only {
+        remove(logs)?;
+        remove(dir.join("missing-logs.json"))?;
+        if let Some(manifest) = CacheManifest::load(experiment) {
+            CacheManifest {
+                log_count: 0,
+                ..manifest
+            }
+            .save(experiment)?;
+        }
+        return Ok(removed);
+    }
+
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(removed),
+        Err(err) => return Err(err.into()),
+    };
+    for entry in entries {
+        let entry = entry?;
+        // the legacy logs of `<experiment>.`
+        if entry.file_name() == "dot" {
+            continue;
+        }
+        remove(entry.path())?;
+    }
+    // kept if it holds the legacy logs of `<experiment>.`
+    if std::fs::remove_dir(&dir).is_ok() {
+        removed.push(dir);
+    }
+    Ok(removed)
+}
+
+pub fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs())
+}
+
+/// The logs of all experiments by their S3 ETag, the MD5 of their content, hard linked into the experiment caches,
+/// so that logs identical across experiments (e.g. of retried crates) are downloaded and stored once.
+const LOG_STORE_DIR: &str = "results/log-store";
+
+/// The shared log store and how the logs fetched through it were obtained, see [`Config::share_logs`](crate::engine::Config::share_logs).
+///
+/// The logs handed out are accounted for in its [memory budget](LogStore::with_memory_budget).
+#[derive(Debug)]
+pub struct LogStore {
+    enabled: bool,
+    /// The compressed variants tried before the plain log, with whether one was missing for a log of the experiment,
+    /// see [`Config::compressed_logs`](crate::engine::Config::compressed_logs)
+    compressions: Vec<(LogCompression, AtomicBool)>,
+    /// KiB of logs that may be held at the same time, 0 for no limit
+    budget_kib: u32,
+    /// One permit per KiB of the budget, as `acquire_many` is limited to u32 permits
+    budget: Semaphore,
+    downloaded: AtomicUsize,
+    reused: AtomicUsize,
+    reused_bytes: AtomicU64,
+}
+
+impl LogStore {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            compressions: Vec::new(),
+            budget_kib: 0,
+            budget: Semaphore::new(0),
+            downloaded: AtomicUsize::new(0),
+            reused: AtomicUsize::new(0),
+            reused_bytes: AtomicU64::new(0),
+        }
+    }
+
+    /// Tries the compressed variants of the logs in order before the plain logs.
+    pub fn with_compressions(mut self, compressions: &[LogCompression]) -> Self {
+        self.compressions = compressions
+            .iter()
+            .map(|&compression| (compression, AtomicBool::new(false)))
+            .collect();
+        self
+    }
+
+    /// Limits the total size of the logs handed out by [`get_log`] and not dropped yet, 0 disables the limit,
+    /// see [`Config::log_memory_budget_mib`](crate::engine::Config::log_memory_budget_mib).
+    pub fn with_memory_budget(mut self, mib: u32) -> Self {
+        self.budget_kib = mib.saturating_mul(1024);
+        self.budget = Semaphore::new(self.budget_kib as usize);
+        self
+    }
+
+    /// How the fetched logs were obtained, `None` if no log had to be fetched.
+    pub fn reuse(&self) -> Option<LogReuse> {
+        let reuse = LogReuse {
+            downloaded: self.downloaded.load(Ordering::Relaxed),
+            reused: self.reused.load(Ordering::Relaxed),
+            reused_bytes: self.reused_bytes.load(Ordering::Relaxed),
+        };
+        (reuse.downloaded + reuse.reused > 0).then_some(reuse)
+    }
+
+    /// Waits until a log of `bytes` fits into the memory budget and reserves it,
+    /// a single log exceeding the whole budget may still be held on its own.
+    async fn reserve(&self, bytes: u64) -> SemaphorePermit<'_> {
+        self.budget
+            .acquire_many(self.budget_permits(bytes))
+            .await
+            .unwrap()
+    }
+
+    /// Grows `permit` to cover a log of `bytes`, if it doesn't already.
+    async fn grow<'a>(&'a self, permit: SemaphorePermit<'a>, bytes: u64) -> SemaphorePermit<'a> {
+        if self.budget_permits(bytes) as usize <= permit.num_permits() {
+            return permit;
+        }
+        // released first, as topping up while holding permits could deadlock
+        drop(permit);
+        self.reserve(bytes).await
+    }
+
+    fn budget_permits(&self, bytes: u64) -> u32 {
+        u32::try_from(bytes.div_ceil(1024)).map_or(self.budget_kib, |kib| kib.min(self.budget_kib))
+    }
+
+    /// Fetches a log that isn't cached yet into `log_path`, linking it from the store if an identical log is stored
+    /// and adding it to the store otherwise.
+    ///
+    /// Logs downloaded compressed bypass the store, as it is keyed by the ETags of the plain logs.
+    async fn fetch(
+        &self,
+        client: &Client,
+        multi: &MultiProgress,
+        log_path: &Path,
+        log_url: &str,
+    ) -> Result<(Mmap, SemaphorePermit<'_>), AnalysisError> {
+        if let Some(log) = self.fetch_compressed(client, log_path, log_url).await? {
+            self.downloaded.fetch_add(1, Ordering::Relaxed);
+            return Ok(log);
+        }
+        metrics::DOWNLOADS_IN_FLIGHT.inc();
+        let log = self.fetch_plain(client, multi, log_path, log_url).await;
+        metrics::DOWNLOADS_IN_FLIGHT.dec();
+        if log.is_err() {
+            metrics::DOWNLOAD_ERRORS.inc();
+        }
+        log
+    }
+
+    /// Fetches the plain log with a single `GET` request, whose ETag locates the log in the store
+    /// and whose `Content-Length` is reserved in the memory budget before the log is downloaded.
+    async fn fetch_plain(
+        &self,
+        client: &Client,
+        multi: &MultiProgress,
+        log_path: &Path,
+        log_url: &str,
+    ) -> Result<(Mmap, SemaphorePermit<'_>), AnalysisError> {
+        let response = client.get(log_url).send().await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(AnalysisError::NotFound(log_url.to_string()));
+        }
+        // never cache error pages
+        let response = response.error_for_status()?;
+
+        let stored = if self.enabled {
+            stored_log_path(response.headers())
+        } else {
+            None
+        };
+        if let Some(stored) = &stored
+            && let Ok(metadata) = tokio::fs::metadata(stored).await
+            && link_or_copy(stored, log_path).is_ok()
+        {
+            // dropping the response without reading the body closes the connection
+            drop(response);
+            let permit = self.reserve(metadata.len()).await;
+            metrics::CACHE_HITS.inc();
+            self.reused.fetch_add(1, Ordering::Relaxed);
+            self.reused_bytes
+                .fetch_add(metadata.len(), Ordering::Relaxed);
+            let file = std::fs::File::open(log_path)?;
+            return Ok((unsafe { Mmap::map(&file)? }, permit));
+        }
+
+        metrics::CACHE_MISSES.inc();
+        // bodies decoded from a transfer encoding have no known length, they are reserved once downloaded
+        let permit = self.reserve(response.content_length().unwrap_or(0)).await;
+        let file = save_download(multi, log_path, log_url, response).await?;
+        let permit = self.grow(permit, file.metadata()?.len()).await;
+        self.downloaded.fetch_add(1, Ordering::Relaxed);
+        if let Some(stored) = &stored
+            && let Err(err) =
+                std::fs::create_dir_all(LOG_STORE_DIR).and_then(|_| link_or_copy(log_path, stored))
+            && err.kind() != ErrorKind::AlreadyExists
+        {
+            log::debug!("Failed to add '{log_url}' to the log store: {err}");
+        }
+        Ok((unsafe { Mmap::map(&file)? }, permit))
+    }
+
+    /// Downloads the first compressed variant of the log that exists and decompresses it into `log_path` while
+    /// it is downloaded, falling back to the next variant if it is missing or can't be decompressed.
+    ///
+    /// A variant missing for one log isn't tried for the other logs of the experiment anymore.
+    async fn fetch_compressed(
+        &self,
+        client: &Client,
+        log_path: &Path,
+        log_url: &str,
+    ) -> Result<Option<(Mmap, SemaphorePermit<'_>)>, AnalysisError> {
+        for (compression, missing) in &self.compressions {
+            if missing.load(Ordering::Relaxed) {
+                continue;
+            }
+            let url = format!("{log_url}.{}", compression.extension());
+            metrics::DOWNLOADS_IN_FLIGHT.inc();
+            let log = fetch_decompressed(client, *compression, log_path, &url).await;
+            metrics::DOWNLOADS_IN_FLIGHT.dec();
+            let file = match log {
+                Ok(Some(file)) => file,
+                Ok(None) => {
+                    log::debug!("'{url}' is missing, not trying this variant for the other logs");
+                    missing.store(true, Ordering::Relaxed);
+                    continue;
+                }
+                Err(AnalysisError::Io(err)) => {
+                    log::warn!("Failed to decompress '{url}', trying the next variant: {err}");
+                    continue;
+                }
+                Err(err) => {
+                    metrics::DOWNLOAD_ERRORS.inc();
+                    return Err(err);
+                }
+            };
+            metrics::CACHE_MISSES.inc();
+            // the decompressed log is only on disk so far, it is reserved before being mapped
+            let permit = self.reserve(file.metadata()?.len()).await;
+            return Ok(Some((unsafe { Mmap::map(&file)? }, permit)));
+        }
+        Ok(None)
+    }
+}
+
+/// The path of a log in the store, whether it is stored or not, from the ETag of its response.
+fn stored_log_path(headers: &reqwest::header::HeaderMap) -> Option<PathBuf> {
+    let etag = headers.get(reqwest::header::ETAG)?.to_str().ok()?;
+    // multipart uploads have ETags like `"<md5>-<parts>"`, all others are plain MD5 hashes
+    let etag = etag.trim_start_matches("W/").trim_matches('"');
+    if etag.is_empty() || !etag.chars().all(|c| c.is_ascii_hexdigit() || c == '-') {
+        return None;
+    }
+    Some(Path::new(LOG_STORE_DIR).join(format!("{etag}.txt")))
+}
+
+/// Downloads the log compressed with `compression` at `url` into `log_path`, decompressing each chunk as it arrives,
+/// `None` if the variant isn't published for this log.
+///
+/// Failing to decompress is reported as [`AnalysisError::Io`], leaving no partial log behind.
+async fn fetch_decompressed(
+    client: &Client,
+    compression: LogCompression,
+    log_path: &Path,
+    url: &str,
+) -> Result<Option<std::fs::File>, AnalysisError> {
+    let response = client.get(url).send().await?;
+    // S3 answers 403 rather than 404 for missing objects in unlistable buckets
+    if matches!(
+        response.status(),
+        reqwest::StatusCode::NOT_FOUND | reqwest::StatusCode::FORBIDDEN
+    ) {
+        return Ok(None);
+    }
+    let mut response = response.error_for_status()?;
+
+    let (tempfile, log_path) = create_download_file(log_path)?;
+    let mut decoder = LogDecoder::new(compression, tempfile)?;
+    while let Some(chunk) = response.chunk().await? {
+        decoder = tokio::task::spawn_blocking(move || decoder.write_all(&chunk).map(|_| decoder))
+            .await
+            .unwrap()?;
+    }
+    let tempfile = tokio::task::spawn_blocking(move || decoder.finish())
+        .await
+        .unwrap()?;
+    Ok(Some(
+        tempfile.persist(log_path).map_err(std::io::Error::from)?,
+    ))
+}
+
+/// Decompresses what is written to it into a file.
+enum LogDecoder {
+    Zstd(zstd::stream::zio::Writer<NamedTempFile, zstd::stream::raw::Decoder<'static>>),
+    Brotli(Box<brotli::DecompressorWriter<NamedTempFile>>),
+    Gzip(flate2::write::GzDecoder<NamedTempFile>),
+}
+
+impl LogDecoder {
+    fn new(compression: LogCompression, file: NamedTempFile) -> std::io::Result<Self> {
+        Ok(match compression {
+            LogCompression::Zstd => LogDecoder::Zstd(zstd::stream::zio::Writer::new(
+                file,
+                zstd::stream::raw::Decoder::new()?,
+            )),
+            LogCompression::Brotli => {
+                LogDecoder::Brotli(Box::new(brotli::DecompressorWriter::new(file, 64 * 1024)))
+            }
+            LogCompression::Gzip => LogDecoder::Gzip(flate2::write::GzDecoder::new(file)),
+        })
+    }
+
+    fn write_all(&mut self, compressed: &[u8]) -> std::io::Result<()> {
+        match self {
+            LogDecoder::Zstd(decoder) => decoder.write_all(compressed),
+            LogDecoder::Brotli(decoder) => decoder.write_all(compressed),
+            LogDecoder::Gzip(decoder) => decoder.write_all(compressed),
+        }
+    }
+
+    /// Checks that the compressed stream is complete, returning the file it was decompressed into.
+    fn finish(self) -> std::io::Result<NamedTempFile> {
+        match self {
+            LogDecoder::Zstd(mut decoder) => {
+                decoder.finish()?;
+                Ok(decoder.into_inner().0)
+            }
+            LogDecoder::Brotli(decoder) => decoder.into_inner().map_err(|_| {
+                std::io::Error::new(ErrorKind::UnexpectedEof, "truncated brotli stream")
+            }),
+            LogDecoder::Gzip(decoder) => decoder.finish(),
+        }
+    }
+}
+
+/// Hard links `from` to `to`, falling back to copying where hard links aren't supported.
+fn link_or_copy(from: &Path, to: &Path) -> std::io::Result<()> {
+    match std::fs::hard_link(from, to) {
+        Err(err) if err.kind() != ErrorKind::AlreadyExists => std::fs::copy(from, to).map(|_| ()),
+        result => result,
+    }
+}
+
+/// Gets a log from the cache or downloads it, unless it is known to be missing,
+/// with its reservation in the [memory budget](LogStore::with_memory_budget) of `store`.
+///
+/// Cached logs [pruned](prune_cached_log) with another rule set are downloaded again,
+/// as the new rules may match lines that were pruned. Logs that aren't cached yet are fetched through `store`.
+pub async fn get_log<'a>(
+    client: &Client,
+    multi: &MultiProgress,
+    store: &'a LogStore,
+    missing_logs: &MissingLogs,
+    experiment: &str,
+    log: &str,
+    ruleset_hash: &str,
+) -> Result<(Mmap, SemaphorePermit<'a>), AnalysisError> {
+    if missing_logs.contains(log) {
+        return Err(AnalysisError::NotFound(log.to_string()));
+    }
+
+    let log_folder = log_cache_dir(experiment, log);
+
+    if let Err(err) = tokio::fs::create_dir_all(&log_folder).await {
+        // reported once when falling back to the temporary cache
+        log::debug!("Failed to create cache folder: {err}");
+    }
+    let log_path = log_folder.join("log.txt");
+    migrate_legacy_log(experiment, log, &log_path).await;
+    let marker_path = log_folder.join(PRUNED_MARKER);
+    if let Ok(pruned_with) = tokio::fs::read_to_string(&marker_path).await
+        && pruned_with != ruleset_hash
+    {
+        log::debug!("Discarding log '{log}' pruned with rule set {pruned_with}");
+        let _ = tokio::fs::remove_file(&log_path).await;
+        let _ = tokio::fs::remove_file(&marker_path).await;
+    }
+    let log_url = log_url(experiment, log);
+
+    let cached_size = match tokio::fs::metadata(&log_path).await {
+        Ok(metadata) => Some(metadata.len()),
+        Err(_) => match fallback_cache_dir() {
+            Some(dir) => tokio::fs::metadata(dir.join(&log_path))
+                .await
+                .ok()
+                .map(|metadata| metadata.len()),
+            None => None,
+        },
+    };
+    let result = match cached_size {
+        Some(size) => {
+            let permit = store.reserve(size).await;
+            get_or_download_file(client, multi, log_path.as_ref(), &log_url)
+                .await
+                .map(|log| (log, permit))
+        }
+        None => store.fetch(client, multi, &log_path, &log_url).await,
+    };
+    if let Err(AnalysisError::NotFound(_)) = &result {
+        missing_logs.insert(log);
+    }
+    result
+}
+
+/// Reads a log if it is already cached, without downloading it.
+pub async fn get_cached_log(experiment: &str, log: &str) -> Option<Vec<u8>> {
+    let log_path = log_cache_dir(experiment, log).join("log.txt");
+    if let Ok(log) = tokio::fs::read(&log_path).await {
+        return Some(log);
+    }
+    tokio::fs::read(fallback_cache_dir()?.join(log_path))
+        .await
+        .ok()
+}
+
+/// Name of the file next to a pruned log, containing the hash of the rule set the log was pruned with.
+const PRUNED_MARKER: &str = "log.pruned";
+
+/// Replaces a cached log with its pruned version, only keeping the lines needed for the report.
+///
+/// The log is marked as pruned, so that it is downloaded again once the rule set changes.
+/// Logs cached in the [fallback cache](fallback_cache_dir) are left as is.
+pub fn prune_cached_log(
+    experiment: &str,
+    log: &str,
+    pruned: &[u8],
+    ruleset_hash: &str,
+) -> Result<(), AnalysisError> {
+    let log_folder = log_cache_dir(experiment, log);
+    let log_path = log_folder.join("log.txt");
+    if !log_path.exists() {
+        return Ok(());
+    }
+
+    // mark first, a marked but unpruned log is merely downloaded again unnecessarily
+    std::fs::write(log_folder.join(PRUNED_MARKER), ruleset_hash)?;
+    let mut tempfile = NamedTempFile::new_in(&log_folder)?;
+    tempfile.write_all(pruned)?;
+    tempfile.persist(log_path).map_err(std::io::Error::from)?;
+    Ok(())
+}
+
+/// The URL crater publishes the log of a run at.
+pub fn log_url(experiment: &str, log: &str) -> String {
+    format!("https://crater-reports.s3.amazonaws.com/{experiment}/{log}/log.txt")
+}
+
+/// The cache folder of a log, see [`encode_log_key`].
+pub fn log_cache_dir(experiment: &str, log: &str) -> PathBuf {
+    PathBuf::from(format!("results/{experiment}/logs/{}", encode_log_key(log)))
+}
+
+/// Encodes a log key (e.g. `beta/reg/foo-1.0.0`) into a relative path, reversible via [`decode_log_key`].
+///
+/// Each `/` separated segment is kept as is, except that `%` is escaped as `%25`,
+/// dots at the end of a segment as `%2E` (as `.`, `..` and trailing dots don't survive as folder names),
+/// characters not allowed in Windows file names, as in the keys of git and path crates
+/// (e.g. `beta/git/https://github.com/foo/bar`), as `%` followed by their hex code
+/// and an empty segment is written as a lone `%`.
+pub fn encode_log_key(log: &str) -> String {
+    log.split('/')
+        .map(|segment| {
+            if segment.is_empty() {
+                return "%".to_string();
+            }
+            let kept = segment.trim_end_matches('.');
+            let trailing_dots = segment.len() - kept.len();
+            let mut encoded = String::with_capacity(segment.len());
+            for c in kept.chars() {
+                if c == '%' || c.is_ascii_control() || r#"\:*?"<>|"#.contains(c) {
+                    encoded.push_str(&format!("%{:02X}", c as u32));
+                } else {
+                    encoded.push(c);
+                }
+            }
+            encoded.push_str(&"%2E".repeat(trailing_dots));
+            encoded
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Reverses [`encode_log_key`].
+pub fn decode_log_key(encoded: &str) -> String {
+    encoded
+        .split('/')
+        .map(|segment| {
+            let mut decoded = String::with_capacity(segment.len());
+            let mut rest = segment;
+            while let Some((kept, escaped)) = rest.split_once('%') {
+                decoded.push_str(kept);
+                rest = match escaped
+                    .get(..2)
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                {
+                    Some(byte) => {
+                        decoded.push(char::from(byte));
+                        &escaped[2..]
+                    }
+                    // the lone `%` of an empty segment
+                    None => escaped,
+                };
+            }
+            decoded.push_str(rest);
+            decoded
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Where the log was cached before logs keys were encoded reversibly,
+/// which lost trailing dots and collided for keys containing `./`.
+fn legacy_log_path(experiment: &str, log: &str) -> PathBuf {
+    let log_folder = format!("results/{experiment}/logs/{log}/")
+        .replace("./", "/dot/")
+        .trim_end_matches('/')
+        .to_string();
+    PathBuf::from(format!("{log_folder}/log.txt"))
+}
+
+/// Moves a log cached at its legacy location to the current one, so existing caches aren't orphaned.
+async fn migrate_legacy_log(experiment: &str, log: &str, log_path: &Path) {
+    let legacy_path = legacy_log_path(experiment, log);
+    if legacy_path == log_path
+        || tokio::fs::try_exists(log_path).await.unwrap_or(true)
+        || !tokio::fs::try_exists(&legacy_path).await.unwrap_or(false)
+    {
+        return;
+    }
+
+    match tokio::fs::rename(&legacy_path, log_path).await {
+        Ok(()) => log::info!(
+            "Migrated cached log '{}' to '{}'",
+            legacy_path.display(),
+            log_path.display()
+        ),
+        Err(err) => log::warn!(
+            "Failed to migrate cached log '{}': {err}",
+            legacy_path.display()
+        ),
+    }
+}
+
+/// Minimum time between requests to the crates.io API, as its crawler policy allows one request per second.
+const CRATES_IO_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Gets a crates.io API response from the cache or downloads it, one request at a time
+/// and no sooner than [`CRATES_IO_INTERVAL`] after the previous one.
+async fn get_or_download_crates_io(
+    client: &Client,
+    multi: &MultiProgress,
+    cache_path: &Path,
+    url: &str,
+) -> Result<Mmap, AnalysisError> {
+    static LAST_REQUEST: tokio::sync::Mutex<Option<tokio::time::Instant>> =
+        tokio::sync::Mutex::const_new(None);
+
+    let cached = tokio::fs::try_exists(cache_path).await.unwrap_or(false)
+        || fallback_cache_dir().is_some_and(|dir| dir.join(cache_path).exists());
+    if cached {
+        return get_or_download_file(client, multi, cache_path, url).await;
+    }
+    let mut last_request = LAST_REQUEST.lock().await;
+    if let Some(last_request) = *last_request {
+        tokio::time::sleep_until(last_request + CRATES_IO_INTERVAL).await;
+    }
+    let result = get_or_download_file(client, multi, cache_path, url).await;
+    *last_request = Some(tokio::time::Instant::now());
+    result
+}
+
+/// The names of the crates whose crates.io version is yanked, crates from other sources are never yanked.
+///
+/// Each version is cached below `results/crates-io/` and only looked up once,
+/// delete the directory to pick up versions yanked since. Failed lookups count as not yanked.
+/// The versions that aren't cached yet are looked up one per [`CRATES_IO_INTERVAL`].
+pub async fn get_yanked_crates(
+    client: &Client,
+    multi: &MultiProgress,
+    crates: &[CrateResult],
+) -> BTreeSet<String> {
+    #[derive(serde::Deserialize)]
+    struct VersionResponse {
+        version: Version,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct Version {
+        yanked: bool,
+    }
+
+    let versions = crates
+        .iter()
+        .filter_map(|krate| {
+            let (name, version) = crates_io_version(krate.url.as_deref()?)?;
+            let path = PathBuf::from(format!("results/crates-io/{name}/{version}.json"));
+            Some((&krate.name, name, version, path))
+        })
+        .collect::<Vec<_>>();
+    let uncached = versions
+        .iter()
+        .filter(|(_, _, _, path)| !path.exists())
+        .count();
+    if uncached > 0 {
+        log::info!(
+            "Looking up {uncached} crate version(s) on crates.io, one per {}s",
+            CRATES_IO_INTERVAL.as_secs()
+        );
+    }
+    futures::stream::iter(versions)
+        .then(|(krate, name, version, path)| async move {
+            let url = format!("https://crates.io/api/v1/crates/{name}/{version}");
+            if let Err(err) = tokio::fs::create_dir_all(path.parent().unwrap()).await {
+                log::debug!("Failed to create cache folder: {err}");
+            }
+            let yanked = async {
+                let content = get_or_download_crates_io(client, multi, &path, &url).await?;
+                let response: VersionResponse = serde_json::from_slice(&content)?;
+                Ok::<_, AnalysisError>(response.version.yanked)
+            };
+            match yanked.await {
+                Ok(yanked) => yanked.then(|| krate.clone()),
+                Err(err) => {
+                    log::warn!("Failed to look up whether {name} {version} is yanked: {err}");
+                    None
+                }
+            }
+        })
+        .filter_map(std::future::ready)
+        .collect()
+        .await
+}
+
+/// Crates listed per page by the crates.io API, its maximum.
+const CRATES_IO_PAGE_SIZE: usize = 100;
+
+/// The names of the `count` most downloaded crates on crates.io, most downloaded first.
+///
+/// The pages are cached below `results/top-crates/` and only fetched once,
+/// delete the directory to refresh the ranking.
+pub async fn get_top_crates(
+    client: &Client,
+    multi: &MultiProgress,
+    count: usize,
+) -> Result<Vec<String>, AnalysisError> {
+    #[derive(serde::Deserialize)]
+    struct Page {
+        crates: Vec<PageCrate>,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct PageCrate {
+        name: String,
+    }
+
+    std::fs::create_dir_all("results/top-crates")?;
+
+    let mut names = Vec::with_capacity(count);
+    for page in 1..=count.div_ceil(CRATES_IO_PAGE_SIZE) {
+        let path = PathBuf::from(format!("results/top-crates/page-{page}.json"));
+        let url = format!(
+            "https://crates.io/api/v1/crates?sort=downloads&per_page={CRATES_IO_PAGE_SIZE}&page={page}"
+        );
+        let content = get_or_download_crates_io(client, multi, &path, &url).await?;
+        let page: Page = serde_json::from_slice(&content)?;
+        let exhausted = page.crates.len() < CRATES_IO_PAGE_SIZE;
+        names.extend(page.crates.into_iter().map(|krate| krate.name));
+        if exhausted {
+            break;
+        }
+    }
+    names.truncate(count);
+    Ok(names)
+}
+
+/// Where downloads are cached once the cache below `results/` turned out not to be writable.
+static FALLBACK_CACHE: OnceLock<PathBuf> = OnceLock::new();
+
+/// The temporary directory downloads were cached in instead of `results/` during this run, if any.
+///
+/// It is kept after the run, so that the downloads can be reused or moved into the cache.
+pub fn fallback_cache_dir() -> Option<&'static Path> {
+    FALLBACK_CACHE.get().map(PathBuf::as_path)
+}
+
+/// Switches all further downloads to a temporary cache, returning it, or `None` if it can't be created.
+fn activate_fallback_cache(err: &std::io::Error, cache_dir: &Path) -> Option<&'static Path> {
+    if let Some(dir) = fallback_cache_dir() {
+        return Some(dir);
+    }
+
+    let dir = match tempfile::Builder::new()
+        .prefix("crater-analysis-cache-")
+        .tempdir()
+    {
+        Ok(dir) => dir.keep(),
+        Err(temp_err) => {
+            log::warn!(
+                "Cache folder '{}' is not writable ({err}) and no temporary cache could be created: {temp_err}",
+                cache_dir.display()
+            );
+            return None;
+        }
+    };
+
+    // another download may have raced us here, the loser's empty directory is left behind
+    let dir = FALLBACK_CACHE.get_or_init(|| dir);
+    log::warn!(
+        "Cache folder '{}' is not writable ({err}), caching downloads in '{}' for this run",
+        cache_dir.display(),
+        dir.display()
+    );
+    Some(dir)
+}
+
+/// Creates the file a download is written to before being persisted at the returned path.
+fn create_download_file(cache_path: &Path) -> Result<(NamedTempFile, PathBuf), AnalysisError> {
+    let parent = cache_path.parent().unwrap();
+    if fallback_cache_dir().is_none() {
+        match NamedTempFile::new_in(parent) {
+            Ok(tempfile) => return Ok((tempfile, cache_path.to_path_buf())),
+            Err(err) => {
+                if activate_fallback_cache(&err, parent).is_none() {
+                    return Err(err.into());
+                }
+            }
+        }
+    }
+
+    let fallback_path = fallback_cache_dir().unwrap().join(cache_path);
+    let fallback_parent = fallback_path.parent().unwrap();
+    std::fs::create_dir_all(fallback_parent)?;
+    Ok((NamedTempFile::new_in(fallback_parent)?, fallback_path))
+}
+
+pub async fn get_or_download_file(
+    client: &Client,
+    multi: &MultiProgress,
+    cache_path: &Path,
+    download_url: &str,
+) -> Result<Mmap, AnalysisError> {
+    let fallback_path = fallback_cache_dir().map(|dir| dir.join(cache_path));
+    let file = if tokio::fs::try_exists(cache_path).await? {
+        metrics::CACHE_HITS.inc();
+        std::fs::File::open(cache_path)?
+    } else if let Some(fallback_path) = fallback_path
+        && tokio::fs::try_exists(&fallback_path).await?
+    {
+        metrics::CACHE_HITS.inc();
+        std::fs::File::open(fallback_path)?
+    } else {
+        metrics::CACHE_MISSES.inc();
+        metrics::DOWNLOADS_IN_FLIGHT.inc();
+        let file = download_file(client, multi, cache_path, download_url).await;
+        metrics::DOWNLOADS_IN_FLIGHT.dec();
+        if file.is_err() {
+            metrics::DOWNLOAD_ERRORS.inc();
+        }
+        file?
+    };
+
+    Ok(unsafe { Mmap::map(&file)? })
+}
+
+/// Downloads `download_url` into the cache, see [`get_or_download_file`].
+async fn download_file(
+    client: &Client,
+    multi: &MultiProgress,
+    cache_path: &Path,
+    download_url: &str,
+) -> Result<std::fs::File, AnalysisError> {
+    let parent = cache_path.parent().unwrap();
+    let entry = if let Some(name) = parent.file_name() {
+        name.to_string_lossy().into_owned()
+    } else {
+        "parent-has-no-name".to_string()
+    };
+
+    log::debug!("Failed to access cached results for {entry}, falling back to downloading");
+
+    let response = client.get(download_url).send().await?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Err(AnalysisError::NotFound(download_url.to_string()));
+    }
+    // never cache error pages
+    let response = response.error_for_status()?;
+    save_download(multi, cache_path, download_url, response).await
+}
+
+/// Streams the body of `response` into the cache at `cache_path`, showing the progress.
+async fn save_download(
+    multi: &MultiProgress,
+    cache_path: &Path,
+    download_url: &str,
+    mut response: reqwest::Response,
+) -> Result<std::fs::File, AnalysisError> {
+    let (mut tempfile, cache_path) = create_download_file(cache_path)?;
+
+    let download_pb =
+        multi.add(ProgressBar::no_length().with_message(format!("Downloading {download_url}")));
+    download_pb.set_style(
+        ProgressStyle::with_template("{msg} {wide_bar} {binary_bytes}/{binary_total_bytes} {binary_bytes_per_sec} ETA {eta_precise}").unwrap(),
+    );
+
+    if let Some(len) = response.content_length() {
+        download_pb.set_length(len);
+        let _ = tempfile.as_file().set_len(len);
+    }
+
+    while let Some(chunk) = response.chunk().await? {
+        tempfile = match tokio::task::spawn_blocking({
+            let download_pb = download_pb.clone();
+            move || {
+                download_pb.inc(chunk.len() as u64);
+                tempfile.write_all(&chunk).map(|_| tempfile)
+            }
+        })
+        .await
+        .unwrap()
+        {
+            Err(err) => {
+                log::warn!("Failed to cache result to {cache_path:?}: {err}");
+                return Err(err.into());
+            }
+            Ok(tempfile) => tempfile,
+        };
+    }
+
+    Ok(tempfile.persist(cache_path).map_err(std::io::Error::from)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log_keys_round_trip() {
+        for log in [
+            "beta/reg/foo-1.0.0",
+            "beta/gh/rust-lang/rust",
+            "beta/git/https://github.com/foo/bar",
+            "beta/path/C:\\crates\\foo",
+            "beta/local/100%",
+            "beta/reg/foo..",
+            "beta/../..",
+            "beta//empty/",
+            "beta/reg/%2E",
+            "beta/reg/tab\there",
+            "",
+        ] {
+            assert_eq!(decode_log_key(&encode_log_key(log)), log, "{log}");
+        }
+    }
+
+    #[test]
+    fn log_keys_are_encoded_as_valid_folder_names() {
+        assert_eq!(encode_log_key("beta/reg/foo-1.0.0"), "beta/reg/foo-1.0.0");
+        assert_eq!(
+            encode_log_key("beta/git/https://github.com/foo/bar"),
+            "beta/git/https%3A/%/github.com/foo/bar"
+        );
+        assert_eq!(encode_log_key("beta/../a."), "beta/%2E%2E/a%2E");
+        assert_eq!(encode_log_key("100%"), "100%25");
+        assert_eq!(encode_log_key(r#"a*b?"<>|"#), "a%2Ab%3F%22%3C%3E%7C");
+    }
+}
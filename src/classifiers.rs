@@ -0,0 +1,70 @@
+//! External classifiers, see [`Config::external_classifiers`].
+
+use std::collections::BTreeSet;
+
+use tokio::sync::Semaphore;
+
+use crate::{
+    command::run_command,
+    engine::{Config, ExternalClassifierConfig},
+};
+
+/// Runs the configured external classifiers, at most
+/// [`Config::external_classifier_concurrency`] processes at a time.
+pub struct ExternalClassifiers<'a> {
+    classifiers: &'a [ExternalClassifierConfig],
+    permits: Semaphore,
+}
+
+impl<'a> ExternalClassifiers<'a> {
+    pub fn new(config: &'a Config) -> Self {
+        Self {
+            classifiers: &config.external_classifiers,
+            permits: Semaphore::new(config.external_classifier_concurrency.max(1)),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.classifiers.is_empty()
+    }
+
+    /// The categories printed by any of the classifiers for the log, one per line.
+    ///
+    /// Failing classifiers are logged and contribute no categories.
+    pub async fn classify(&self, experiment: &str, log_key: &str, log: &[u8]) -> BTreeSet<String> {
+        let outputs =
+            futures::future::join_all(self.classifiers.iter().map(|classifier| async move {
+                let _permit = self.permits.acquire().await.unwrap();
+                let output = run_command(
+                    &classifier.command,
+                    &[
+                        ("CRATER_ANALYSIS_EXPERIMENT", experiment),
+                        ("CRATER_ANALYSIS_LOG", log_key),
+                    ],
+                    log,
+                    classifier.timeout_secs,
+                )
+                .await;
+                (classifier, output)
+            }))
+            .await;
+
+        let mut categories = BTreeSet::new();
+        for (classifier, output) in outputs {
+            match output {
+                Ok(stdout) => categories.extend(
+                    String::from_utf8_lossy(&stdout)
+                        .lines()
+                        .map(str::trim)
+                        .filter(|line| !line.is_empty())
+                        .map(str::to_string),
+                ),
+                Err(err) => log::warn!(
+                    "External classifier {:?} failed for '{log_key}': {err}",
+                    classifier.command
+                ),
+            }
+        }
+        categories
+    }
+}
@@ -0,0 +1,77 @@
+//! Whether the output is colored, see [`ColorMode`].
+//!
+//! The mode is applied once at startup and then shared by everything writing to the terminal
+//! through [`console`] (the summary and progress bars) and by the text reports written afterwards.
+
+use std::{
+    str::FromStr,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// Whether ANSI escape sequences in log lines are kept in text reports, see [`keep_ansi_in_reports`].
+static KEEP_ANSI: AtomicBool = AtomicBool::new(false);
+
+/// Given by `--color`, defaults to [`ColorMode::from_env`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorMode {
+    /// Colors if the output is a terminal
+    Auto,
+    /// Colors even if the output is redirected, ANSI escape sequences in log lines are kept in text reports
+    Always,
+    Never,
+}
+
+impl ColorMode {
+    /// [`Never`](Self::Never) if the `NO_COLOR` environment variable is set to a non-empty value, see <https://no-color.org>,
+    /// otherwise [`Auto`](Self::Auto).
+    pub fn from_env() -> Self {
+        if std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+            ColorMode::Never
+        } else {
+            ColorMode::Auto
+        }
+    }
+
+    /// Enables or disables colors on stdout and stderr, leaving the detection of [`Auto`](Self::Auto) to [`console`].
+    pub fn apply(self) {
+        match self {
+            ColorMode::Auto => {}
+            ColorMode::Always | ColorMode::Never => {
+                let enabled = self == ColorMode::Always;
+                console::set_colors_enabled(enabled);
+                console::set_colors_enabled_stderr(enabled);
+            }
+        }
+        KEEP_ANSI.store(self == ColorMode::Always, Ordering::Relaxed);
+    }
+
+    /// The corresponding style of log messages.
+    pub fn write_style(self) -> env_logger::WriteStyle {
+        match self {
+            ColorMode::Auto => env_logger::WriteStyle::Auto,
+            ColorMode::Always => env_logger::WriteStyle::Always,
+            ColorMode::Never => env_logger::WriteStyle::Never,
+        }
+    }
+}
+
+impl FromStr for ColorMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(ColorMode::Auto),
+            "always" => Ok(ColorMode::Always),
+            "never" => Ok(ColorMode::Never),
+            _ => Err(format!(
+                "unknown color mode '{s}', expected auto, always or never"
+            )),
+        }
+    }
+}
+
+/// Whether text reports keep ANSI escape sequences of log lines, only with [`ColorMode::Always`];
+/// Markdown and HTML reports never do.
+pub fn keep_ansi_in_reports() -> bool {
+    KEEP_ANSI.load(Ordering::Relaxed)
+}
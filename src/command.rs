@@ -0,0 +1,63 @@
+//! Running external commands that get their input on stdin, shared by hooks and external classifiers.
+
+use std::{process::Stdio, time::Duration};
+
+use tokio::{io::AsyncWriteExt as _, process::Command};
+
+#[derive(thiserror::Error, Debug)]
+pub enum CommandError {
+    #[error("empty command")]
+    EmptyCommand,
+    #[error("failed to run command: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("command timed out after {0} seconds")]
+    Timeout(u64),
+    #[error("command exited with {status}: {stderr}")]
+    Failed {
+        status: std::process::ExitStatus,
+        stderr: String,
+    },
+}
+
+/// Runs the program followed by its arguments with `input` on stdin, returning its stdout.
+///
+/// The command is killed once the timeout expires.
+pub async fn run_command(
+    command: &[String],
+    envs: &[(&str, &str)],
+    input: &[u8],
+    timeout_secs: u64,
+) -> Result<Vec<u8>, CommandError> {
+    let (program, args) = command.split_first().ok_or(CommandError::EmptyCommand)?;
+
+    let mut child = Command::new(program)
+        .args(args)
+        .envs(envs.iter().copied())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+
+    let mut stdin = child.stdin.take().unwrap();
+    let write_stdin = async move {
+        // commands not interested in their input may close stdin early
+        let _ = stdin.write_all(input).await;
+    };
+
+    let output = tokio::time::timeout(Duration::from_secs(timeout_secs), async {
+        let (_, output) = tokio::join!(write_stdin, child.wait_with_output());
+        output
+    })
+    .await
+    .map_err(|_| CommandError::Timeout(timeout_secs))??;
+
+    if output.status.success() {
+        Ok(output.stdout)
+    } else {
+        Err(CommandError::Failed {
+            status: output.status,
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        })
+    }
+}
@@ -0,0 +1,135 @@
+//! Detection of systemic connectivity failures while downloading logs, see [`Connectivity`].
+//!
+//! When crater's S3 bucket is unreachable, every single download fails with the same
+//! error; instead of warning about each of them, the downloads are paused after a few
+//! consecutive failures and the user is asked once whether to retry or abort.
+
+use std::{
+    io::{BufRead, IsTerminal, Write},
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+
+use indicatif::MultiProgress;
+
+use crate::AnalysisError;
+
+/// Consecutive connection failures after which the network is considered down rather than a single log unavailable.
+pub const MAX_CONSECUTIVE_FAILURES: usize = 10;
+
+/// Counts consecutive connection failures across concurrent downloads.
+#[derive(Debug, Default)]
+pub struct Connectivity {
+    consecutive_failures: AtomicUsize,
+    aborted: AtomicBool,
+    /// Held while the user is asked how to continue, pausing all downloads waiting in [`Connectivity::ready`]
+    pause: tokio::sync::Mutex<()>,
+}
+
+impl Connectivity {
+    /// Waits while the downloads are paused, `false` once they were aborted.
+    pub async fn ready(&self) -> bool {
+        let _paused = self.pause.lock().await;
+        !self.aborted.load(Ordering::Relaxed)
+    }
+
+    /// Records the outcome of a download, returns whether a failure should still be reported on its own.
+    ///
+    /// After [`MAX_CONSECUTIVE_FAILURES`] connection failures in a row one diagnostic is printed instead,
+    /// on a terminal the user may then retry the downloads, otherwise they are aborted.
+    pub async fn record<T>(
+        &self,
+        result: &Result<T, AnalysisError>,
+        multi: &MultiProgress,
+    ) -> bool {
+        let err = match result {
+            Err(AnalysisError::Reqwest(err)) if err.is_connect() || err.is_timeout() => err,
+            // anything else, including a 404, means the server was reached
+            _ => {
+                self.consecutive_failures.store(0, Ordering::Relaxed);
+                return result.is_err();
+            }
+        };
+        if self.aborted.load(Ordering::Relaxed) {
+            return false;
+        }
+        let failures = self.consecutive_failures.fetch_add(1, Ordering::Relaxed) + 1;
+        if failures < MAX_CONSECUTIVE_FAILURES {
+            return true;
+        }
+
+        let _paused = self.pause.lock().await;
+        // another download may have paused meanwhile and the user decided already
+        if self.aborted.load(Ordering::Relaxed)
+            || self.consecutive_failures.load(Ordering::Relaxed) < MAX_CONSECUTIVE_FAILURES
+        {
+            return false;
+        }
+        let diagnostic = diagnostic(err, failures);
+        let multi = multi.clone();
+        // unlike `block_in_place`, this also works on a current-thread runtime
+        let retry = tokio::task::spawn_blocking(move || {
+            multi.suspend(|| {
+                eprintln!("{diagnostic}");
+                prompt_retry()
+            })
+        })
+        .await
+        .unwrap_or(false);
+        if retry {
+            self.consecutive_failures.store(0, Ordering::Relaxed);
+        } else {
+            self.aborted.store(true, Ordering::Relaxed);
+        }
+        false
+    }
+
+    /// Fails if the downloads were aborted, after which the analysis would be incomplete.
+    pub fn check(&self) -> Result<(), AnalysisError> {
+        if self.aborted.load(Ordering::Relaxed) {
+            return Err(AnalysisError::Unreachable(
+                "aborted after repeated connection failures".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn diagnostic(err: &reqwest::Error, failures: usize) -> String {
+    let host = err
+        .url()
+        .and_then(|url| url.host_str())
+        .unwrap_or("crater-reports.s3.amazonaws.com");
+    let cause = if err.is_timeout() {
+        "timed out"
+    } else {
+        "failed to connect"
+    };
+    format!(
+        "\n{failures} downloads in a row {cause}, {host} seems to be unreachable.\n\
+         Check your network connection, that {host} resolves (DNS) and the proxy settings \
+         (HTTPS_PROXY, NO_PROXY).\nLast error: {err}"
+    )
+}
+
+/// Asks whether to retry the downloads, never on a non-interactive terminal.
+fn prompt_retry() -> bool {
+    let stdin = std::io::stdin();
+    if !(stdin.is_terminal() && std::io::stderr().is_terminal()) {
+        eprintln!("Aborting, as there is no terminal to ask whether to retry.");
+        return false;
+    }
+    loop {
+        eprint!("Retry the downloads [r] or abort [a]? ");
+        let _ = std::io::stderr().flush();
+        let mut answer = String::new();
+        match stdin.lock().read_line(&mut answer) {
+            Ok(0) | Err(_) => return false,
+            Ok(_) => {}
+        }
+        match answer.trim().to_lowercase().as_str() {
+            "r" | "retry" => return true,
+            "a" | "abort" => return false,
+            _ => {}
+        }
+    }
+}
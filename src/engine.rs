@@ -0,0 +1,101 @@
+//! The classification engine: rules, log matching and report structures.
+//!
+//! Nothing in here performs any IO, so this module is available without the
+//! `runtime` feature and compiles for `wasm32-unknown-unknown`, e.g. to reclassify
+//! pasted logs in a browser with the same rules as the command line tool.
+
+mod ansi;
+mod badge;
+mod blacklist;
+mod blocked;
+mod bot;
+mod builtin;
+mod chain;
+mod classify;
+mod cluster;
+mod config;
+mod crosstab;
+mod diff;
+mod excerpt;
+mod expect;
+mod explain;
+mod expr;
+mod extractor;
+mod fingerprint;
+mod hash;
+mod hits;
+mod ids;
+mod linker;
+mod lint;
+mod popularity;
+mod proc_macro;
+mod redact;
+mod render;
+mod report;
+mod resolver;
+mod results;
+mod selection;
+mod status;
+mod suggest;
+mod timing;
+mod toolchain;
+mod truncate;
+
+pub use ansi::strip_ansi;
+pub use badge::{ShieldsBadge, category_badges, summary_badge};
+pub use blacklist::crater_blacklist;
+pub use blocked::{BLOCKED_ON, blocking_dependency};
+pub use bot::{BotCommand, check_experiment_name, parse_bot_command};
+pub use builtin::{BUILTIN_RULE_GROUPS, UnknownRuleGroup};
+pub use chain::dependency_chain;
+pub use classify::{
+    LineMatch, LogFindings, NearMiss, Relaxation, RelaxedMatch, near_misses, process_log,
+    process_refinement, process_warnings, relaxed_matches,
+};
+pub use cluster::last_error_line;
+pub use config::{
+    CONFIG_PROFILES, Config, ExistingAnalysisHandling, ExpectedResults, ExternalClassifierConfig,
+    HookConfig, LogCompression, MissingRunHandling, ModeConfig, RefinementConfig,
+    RegeneratedReportHandling, SectionConfig, Target, TargetRegex, WarningsConfig, YankedHandling,
+    ZulipConfig,
+};
+pub use crosstab::{CrateResultCrossTab, Disagreement};
+pub use diff::{CategoryShare, ExperimentDiff};
+pub use excerpt::{
+    EXCERPT_CONTEXT_LINES, Excerpt, ExcerptContext, FindingMatch, Sample, prune_log,
+};
+pub use expect::{Bounds, CountRange, ExpectationResult, Expectations, check_expectations};
+pub use explain::{CategoryExplanation, explain_category};
+pub use expr::{ParseError, SelectExpr};
+pub use extractor::{Extractor, ExtractorPattern, extract_windowed};
+pub use fingerprint::{
+    Fingerprint, FingerprintStore, KnownFailure, TriageStatus, failure_fingerprint,
+    fingerprint_line,
+};
+pub use hash::stable_hash;
+pub use hits::{HitSample, RuleHits, rule_hits};
+pub use ids::{crate_id, run_id};
+pub use linker::{LinkerIssue, LinkerItem, LinkerItemKind, linker_items};
+pub use lint::{PatternIssue, lint_target};
+pub use popularity::{Popularity, crates_io_name, crates_io_version};
+pub use proc_macro::{PROC_MACRO_PANICKED, ProcMacroPanic, proc_macro_panics};
+pub use redact::{REDACTED, Redactor};
+pub use render::{ExampleLimits, ReportFormat, anchor, category_anchor, render_summary_markdown};
+pub use report::{
+    AnalysisReport, Comparison, CrateAnnotation, LogReuse, LogSize, LogSizes,
+    MOST_CATEGORIES_LISTED, OtherCrate, OtherGroup, OtherRun, REPORT_SCHEMA_VERSION, SectionReport,
+    UNCLASSIFIED, WarningsReport, format_bytes, format_count, percent, report_schema,
+};
+pub use resolver::{ResolverCulprit, ResolverFailure, resolver_failures};
+pub use results::{CrateResult, Results, RunResult, filter_results_json};
+pub use selection::{
+    SelectedRun, is_relevant_crate, run_role, select_interesting_runs, select_missing_runs,
+    select_section_runs, select_warning_runs,
+};
+pub use status::{ExperimentStatus, format_date, parse_http_date, parse_rfc3339};
+pub use suggest::{edit_distance, similar_names};
+pub use timing::{
+    BuildDurations, CrateDuration, DurationRegression, format_duration, log_duration,
+};
+pub use toolchain::{ToolchainMismatch, toolchain_mismatch};
+pub use truncate::truncate_long_lines;
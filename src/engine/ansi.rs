@@ -0,0 +1,43 @@
+//! Removal of ANSI escape sequences from log lines, see [`strip_ansi`].
+
+use std::borrow::Cow;
+
+const ESC: char = '\x1b';
+const BEL: char = '\x07';
+
+/// `text` without ANSI escape sequences, e.g. the colors of a build run with `--color always`.
+///
+/// Removes control sequences (`ESC [ … final byte`), operating system commands such as hyperlinks
+/// (`ESC ] … BEL` or `ESC ] … ESC \`) and the other two character escapes.
+pub fn strip_ansi(text: &str) -> Cow<'_, str> {
+    if !text.contains(ESC) {
+        return Cow::Borrowed(text);
+    }
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != ESC {
+            stripped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('[') => {
+                // parameter and intermediate bytes up to the final byte
+                for c in chars.by_ref() {
+                    if ('\x40'..='\x7e').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == BEL || (c == ESC && chars.next_if_eq(&'\\').is_some()) {
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Cow::Owned(stripped)
+}
@@ -0,0 +1,75 @@
+//! Badges of an analysis for dashboards and READMEs, as JSON for shields.io's endpoint badges, see [`summary_badge`].
+
+use std::collections::BTreeMap;
+
+use super::{AnalysisReport, percent};
+
+/// The JSON shields.io renders as a badge, see <https://shields.io/badges/endpoint-badge>.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShieldsBadge {
+    /// Always 1
+    pub schema_version: u8,
+    pub label: String,
+    pub message: String,
+    pub color: String,
+}
+
+impl ShieldsBadge {
+    fn new(label: &str, message: String, color: &str) -> Self {
+        Self {
+            schema_version: 1,
+            label: label.to_string(),
+            message,
+            color: color.to_string(),
+        }
+    }
+}
+
+/// The number of selected runs of the experiment and the share none of the categories matched,
+/// green while at most 5% are unclassified, yellow up to 20% and red beyond.
+pub fn summary_badge(report: &AnalysisReport) -> ShieldsBadge {
+    let runs = report.interesting_results_count;
+    if runs == 0 {
+        return ShieldsBadge::new(
+            &report.experiment,
+            "no runs selected".to_string(),
+            "lightgrey",
+        );
+    }
+    let unclassified = percent(report.unclassified_count(), runs);
+    let color = if unclassified <= 5.0 {
+        "brightgreen"
+    } else if unclassified <= 20.0 {
+        "yellow"
+    } else {
+        "red"
+    };
+    ShieldsBadge::new(
+        &report.experiment,
+        format!(
+            "{} runs, {unclassified:.1}% unclassified",
+            report.format_count(runs)
+        ),
+        color,
+    )
+}
+
+/// The runs of each found category with their share of the selected runs.
+pub fn category_badges(report: &AnalysisReport) -> BTreeMap<&str, ShieldsBadge> {
+    report
+        .findings
+        .iter()
+        .map(|(category, &count)| {
+            let message = format!(
+                "{} ({:.1}%)",
+                report.format_count(count),
+                percent(count, report.interesting_results_count)
+            );
+            (
+                category.as_str(),
+                ShieldsBadge::new(category, message, "blue"),
+            )
+        })
+        .collect()
+}
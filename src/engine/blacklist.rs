@@ -0,0 +1,88 @@
+//! Crates to skip in the next run of an experiment, in the syntax of crater's `config.toml`, see [`crater_blacklist`].
+
+use std::{collections::BTreeMap, fmt::Write as _};
+
+use super::{AnalysisReport, config::glob_matches, crates_io_name};
+
+/// The crates of the categories matching `patterns` (`*` matches any text and `?` a single character) as
+/// entries of the `[crates]`, `[github-repos]` and `[local-crates]` tables of crater's `config.toml`,
+/// each skipped and commented with its category; `None` if no crate is in any of the categories.
+///
+/// Registry crates and GitHub repositories are identified by their URL, falling back to names like
+/// `gh/<owner>/<repo>` and `local/<name>`; path crates can't be skipped by crater and are left out.
+pub fn crater_blacklist(report: &AnalysisReport, patterns: &[String]) -> Option<String> {
+    let mut tables = BTreeMap::<&str, BTreeMap<String, &str>>::new();
+    for (category, crates) in &report.category_crates {
+        if !patterns
+            .iter()
+            .any(|pattern| glob_matches(pattern, category))
+        {
+            continue;
+        }
+        for krate in crates {
+            let url = report.crate_urls.get(krate).map(String::as_str);
+            let Some((table, key)) = crater_entry(krate, url) else {
+                continue;
+            };
+            // a crate in several categories is listed with the first of them
+            tables
+                .entry(table)
+                .or_default()
+                .entry(key)
+                .or_insert(category);
+        }
+    }
+    if tables.is_empty() {
+        return None;
+    }
+
+    // writing to a String never fails
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "# Crates of {} to skip, to be merged into crater's config.toml",
+        report.experiment
+    );
+    for (table, entries) in tables {
+        let _ = writeln!(out, "\n[{table}]");
+        for (key, category) in entries {
+            let _ = writeln!(out, "{} = {{ skip = true }} # {category}", toml_key(&key));
+        }
+    }
+    Some(out)
+}
+
+/// The table of crater's `config.toml` listing `krate` and its key there.
+fn crater_entry(krate: &str, url: Option<&str>) -> Option<(&'static str, String)> {
+    if let Some(name) = url.and_then(crates_io_name) {
+        return Some(("crates", name.to_string()));
+    }
+    if let Some(repo) = url.and_then(|url| url.strip_prefix("https://github.com/")) {
+        let mut parts = repo.split('/');
+        if let (Some(owner), Some(name)) = (parts.next(), parts.next()) {
+            return Some(("github-repos", format!("{owner}/{name}")));
+        }
+    }
+    if let Some(repo) = krate.strip_prefix("gh/") {
+        Some(("github-repos", repo.to_string()))
+    } else if let Some(name) = krate.strip_prefix("local/") {
+        Some(("local-crates", name.to_string()))
+    } else if krate.starts_with("path/") {
+        None
+    } else {
+        Some(("crates", krate.to_string()))
+    }
+}
+
+/// `key` as a TOML key, quoted unless it is a valid bare key.
+fn toml_key(key: &str) -> String {
+    if !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        key.to_string()
+    } else {
+        toml::Value::String(key.to_string()).to_string()
+    }
+}
@@ -0,0 +1,42 @@
+//! Crates failing only because one of their dependencies failed to build, see [`blocking_dependency`].
+
+use std::sync::LazyLock;
+
+use regex::bytes::Regex;
+
+/// Prefix of the category a crate blocked on a dependency is reclassified as, followed by the dependency.
+pub const BLOCKED_ON: &str = "blocked-on: ";
+
+/// ``error: could not compile `foo` (lib) due to 2 previous errors`` or
+/// ``error: failed to run custom build command for `foo v0.1.0` ``
+static FAILED_PACKAGE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"error: (?:could not compile|failed to run custom build command for) `([^` ]+)")
+        .unwrap()
+});
+
+/// The dependency that failed to build if no package other than dependencies failed in the log of `krate`,
+/// together with the 1-based number of the line reporting its failure.
+///
+/// Packages are compared regardless of `-` and `_`, for GitHub crates (`gh/<owner>/<repo>`)
+/// the repository name is assumed to be the package name.
+pub fn blocking_dependency(log: &[u8], krate: &str) -> Option<(String, usize)> {
+    let own_name = normalize_package(krate.rsplit('/').next().unwrap_or(krate));
+
+    let mut blocking = None;
+    for captures in FAILED_PACKAGE.captures_iter(log) {
+        let package = String::from_utf8_lossy(&captures[1]);
+        if normalize_package(&package) == own_name {
+            return None;
+        }
+        if blocking.is_none() {
+            let start = captures.get(0).unwrap().start();
+            let line_number = log[..start].iter().filter(|&&c| c == b'\n').count() + 1;
+            blocking = Some((package.into_owned(), line_number));
+        }
+    }
+    blocking
+}
+
+fn normalize_package(name: &str) -> String {
+    name.replace('_', "-").to_ascii_lowercase()
+}
@@ -0,0 +1,187 @@
+//! Commands in GitHub comments in the style of craterbot, see [`parse_bot_command`].
+//!
+//! ```text
+//! @crater-analysis analyze name=pr-123456-1 compare-with=pr-123456 ignore=no-space,download
+//! ```
+
+/// Parameters of craterbot's `run` command that are accepted, so that its comments can be reused, but don't
+/// matter for the analysis; the mode, for instance, is taken from the experiment's config published by crater.
+const CRATERBOT_PARAMETERS: [&str; 10] = [
+    "start",
+    "end",
+    "mode",
+    "crates",
+    "cap-lints",
+    "p",
+    "priority",
+    "assign",
+    "requirement",
+    "ignore-blacklist",
+];
+
+/// An `analyze` command addressed to the bot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BotCommand {
+    /// The experiment, `name=` like for craterbot, if given
+    pub name: Option<String>,
+    /// The experiment to compare with, `compare-with=`
+    pub compare_with: Option<String>,
+    /// Categories left out of the report, `ignore=` separated by commas
+    pub ignore: Vec<String>,
+}
+
+impl BotCommand {
+    /// The experiment to analyze, named like craterbot names the experiments of a pull request by default.
+    pub fn experiment(&self, pull_request: u64) -> String {
+        self.name
+            .clone()
+            .unwrap_or_else(|| format!("pr-{pull_request}"))
+    }
+}
+
+/// The command of the first line of `comment` addressed to `@bot`, `None` if there is none.
+///
+/// Fails for commands other than `analyze` and for unknown parameters, so that typos are reported back.
+pub fn parse_bot_command(comment: &str, bot: &str) -> Option<Result<BotCommand, String>> {
+    let mention = format!("@{bot}");
+    let line = comment.lines().find_map(|line| {
+        let rest = line.trim().strip_prefix(&mention)?;
+        // `@bot-other` mentions another user
+        (rest.is_empty() || rest.starts_with(char::is_whitespace)).then_some(rest)
+    })?;
+    Some(parse_words(line.split_whitespace()))
+}
+
+fn parse_words<'a>(mut words: impl Iterator<Item = &'a str>) -> Result<BotCommand, String> {
+    match words.next() {
+        Some("analyze") => {}
+        Some(other) => return Err(format!("unknown command `{other}`, expected `analyze`")),
+        None => return Err("missing command, expected `analyze`".to_string()),
+    }
+    let mut command = BotCommand {
+        name: None,
+        compare_with: None,
+        ignore: Vec::new(),
+    };
+    for word in words {
+        let (key, value) = word
+            .split_once('=')
+            .ok_or_else(|| format!("expected `key=value`, got `{word}`"))?;
+        let value = value.trim_matches('"');
+        match key {
+            "name" => command.name = Some(check_experiment_name(value)?.to_string()),
+            "compare-with" => {
+                command.compare_with = Some(check_experiment_name(value)?.to_string())
+            }
+            "ignore" => command.ignore.extend(
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|category| !category.is_empty())
+                    .map(str::to_string),
+            ),
+            key if CRATERBOT_PARAMETERS.contains(&key) => {}
+            key => return Err(format!("unknown parameter `{key}`")),
+        }
+    }
+    Ok(command)
+}
+
+/// Checks that `name` is a valid experiment name, as it becomes part of cache paths (`results/{experiment}/`)
+/// and URLs: ASCII letters, digits, `.`, `_` and `-`, not starting with a `.`.
+pub fn check_experiment_name(name: &str) -> Result<&str, String> {
+    if name.is_empty() {
+        return Err("empty experiment name".to_string());
+    }
+    if name.starts_with('.')
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+    {
+        return Err(format!(
+            "invalid experiment name `{name}`, expected only letters, digits, `.`, `_` and `-`, not starting with `.`"
+        ));
+    }
+    Ok(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(comment: &str) -> Option<Result<BotCommand, String>> {
+        parse_bot_command(comment, "crater-analysis")
+    }
+
+    #[test]
+    fn parses_the_first_line_addressed_to_the_bot() {
+        let comment = "Thanks!\n  @crater-analysis analyze name=pr-1-1 compare-with=\"pr-1\" ignore=no-space,,download \n@crater-analysis analyze";
+        assert_eq!(
+            parse(comment),
+            Some(Ok(BotCommand {
+                name: Some("pr-1-1".to_string()),
+                compare_with: Some("pr-1".to_string()),
+                ignore: vec!["no-space".to_string(), "download".to_string()],
+            }))
+        );
+    }
+
+    #[test]
+    fn defaults_to_the_experiment_of_the_pull_request() {
+        let command = parse("@crater-analysis analyze").unwrap().unwrap();
+        assert_eq!(command.experiment(123456), "pr-123456");
+    }
+
+    #[test]
+    fn accepts_the_parameters_of_craterbot() {
+        assert!(matches!(
+            parse(
+                "@crater-analysis analyze name=pr-1 start=master end=try#abc mode=check-only p=1"
+            ),
+            Some(Ok(_))
+        ));
+    }
+
+    #[test]
+    fn ignores_comments_not_addressed_to_the_bot() {
+        assert_eq!(parse("analyze name=pr-1"), None);
+        assert_eq!(parse("@crater-analysis-dev analyze"), None);
+        assert_eq!(parse("cc @crater-analysis analyze"), None);
+    }
+
+    #[test]
+    fn reports_invalid_commands() {
+        assert_eq!(
+            parse("@crater-analysis"),
+            Some(Err("missing command, expected `analyze`".to_string()))
+        );
+        assert_eq!(
+            parse("@crater-analysis run name=pr-1"),
+            Some(Err("unknown command `run`, expected `analyze`".to_string()))
+        );
+        assert_eq!(
+            parse("@crater-analysis analyze pr-1"),
+            Some(Err("expected `key=value`, got `pr-1`".to_string()))
+        );
+        assert_eq!(
+            parse("@crater-analysis analyze nmae=pr-1"),
+            Some(Err("unknown parameter `nmae`".to_string()))
+        );
+        assert!(matches!(
+            parse("@crater-analysis analyze name=../results"),
+            Some(Err(err)) if err.starts_with("invalid experiment name `../results`")
+        ));
+    }
+
+    #[test]
+    fn experiment_names() {
+        assert_eq!(check_experiment_name("pr-123_4.5"), Ok("pr-123_4.5"));
+        assert_eq!(
+            check_experiment_name(""),
+            Err("empty experiment name".to_string())
+        );
+        for name in [".hidden", "..", "a/b", "a\\b", "a b", "ä"] {
+            assert!(check_experiment_name(name).is_err(), "{name}");
+        }
+    }
+}
@@ -0,0 +1,301 @@
+//! Rules compiled into the binary, enabled per group via [`Config::builtin_rules`].
+//!
+//! Targets defined in the config take precedence over built-in targets of the same name,
+//! so any built-in category can be overridden without disabling its whole group.
+
+use std::collections::HashMap;
+
+use super::{Config, Target};
+
+type Rules = &'static [(&'static str, &'static [&'static str])];
+
+/// The built-in rule groups by name.
+pub const BUILTIN_RULE_GROUPS: &[(&str, Rules)] = &[
+    ("general", GENERAL),
+    ("edition", EDITION),
+    ("cargo-fix", CARGO_FIX),
+    ("proc-macro-abi", PROC_MACRO_ABI),
+    ("sysroot", SYSROOT),
+];
+
+/// Common infrastructure, dependency resolution and compiler failures.
+const GENERAL: Rules = &[
+    (
+        "docker",
+        [
+            "[INFO] [stderr] Error response from daemon:",
+            ": no such file or directory",
+        ]
+        .as_slice(),
+    ),
+    (
+        "docker",
+        &[
+            "[INFO] [stderr] Error response from daemon:",
+            ": file exists",
+        ],
+    ),
+    ("compile_error!", &["compile_error!"]),
+    (
+        "missing-env-var",
+        &["note: this error originates in the macro `env`"],
+    ),
+    (
+        "delimiter mismatch",
+        &["error: mismatched closing delimiter:"],
+    ),
+    ("no-space", &["no space left on device"]),
+    (
+        "linker-bus-error",
+        &["collect2: fatal error: ld terminated with signal 7 [Bus error]"],
+    ),
+    ("useless-conversion", &["error: this conversion is useless"]),
+    (
+        "build-script",
+        &["[INFO] [stderr] error: failed to run custom build command for"],
+    ),
+    ("download", &["[INFO] [stderr] error: failed to download"]),
+    (
+        "linker-undefined-symbol",
+        &["rust-lld: error: undefined symbol:"],
+    ),
+    (
+        "linker-missing-library",
+        &["rust-lld: error: unable to find library"],
+    ),
+    (
+        "linker-write-output",
+        &[
+            "rust-lld: error: failed to write output",
+            "No such file or directory",
+        ],
+    ),
+    (
+        "include_str-missing-file",
+        &["note: this error originates in the macro `include_str`"],
+    ),
+    (
+        "include_bytes-missing-file",
+        &["note: this error originates in the macro `include_bytes`"],
+    ),
+    ("ice", &["error: internal compiler error:"]),
+    (
+        "task or parent failed (no space)",
+        &["this task or one of its parent failed: No space left on device"],
+    ),
+    (
+        "task or parent failed (no space)",
+        &["this task or one of its parent failed: Io Error: No space left on device"],
+    ),
+    (
+        "task or parent failed (failed to clone)",
+        &["this task or one of its parent failed: failed to clone"],
+    ),
+    ("invalid manifest", &["error: failed to parse manifest at"]),
+    ("invalid manifest", &["error: invalid table header"]),
+    (
+        "invalid manifest",
+        &["error: invalid type: ", ", expected "],
+    ),
+    ("invalid lockfile", &["error: failed to parse lock file at"]),
+    (
+        "timeout",
+        &["[ERROR] error running command: no output for 300 seconds"],
+    ),
+    (
+        "checksum mismatch",
+        &["error: checksum for ", " changed between lock files"],
+    ),
+    (
+        "links conflict",
+        &[
+            "the package ",
+            " links to the native library ",
+            ", but it conflicts with a previous package which links to ",
+            " as well:",
+        ],
+    ),
+    (
+        "links conflict",
+        &["error: Attempting to resolve a dependency with more than one crate with links="],
+    ),
+    (
+        "version selection failed",
+        &["error: failed to select a version for "],
+    ),
+    (
+        "missing dep",
+        &["error: no matching package named ", " found"],
+    ),
+    ("missing dep", &["error: no matching package found"]),
+    (
+        "missing dep",
+        &["no matching package for override ", " found"],
+    ),
+    (
+        "dep removed feature",
+        &[
+            "the package ",
+            " depends on ",
+            ", with features: ",
+            " but ",
+            " does not have these features",
+        ],
+    ),
+    (
+        "missing registry",
+        &["registry index was not found in any configuration:"],
+    ),
+    (
+        "cyclic package dependency",
+        &[
+            "error: cyclic package dependency: package ",
+            " depends on itself. Cycle:",
+        ],
+    ),
+    (
+        "cyclic feature dependency",
+        &[
+            "error: cyclic feature dependency: feature ",
+            " depends on itself",
+        ],
+    ),
+    (
+        "filename too long",
+        &["error: unable to create ", ": File name too long"],
+    ),
+    ("invalid UTF-8", &["stream did not contain valid UTF-8"]),
+];
+
+/// Crates using an edition the toolchain doesn't support, or code broken by an edition change.
+const EDITION: Rules = &[
+    (
+        "edition-unsupported",
+        &["feature `edition", "` is required"],
+    ),
+    (
+        "edition-unsupported",
+        &["this version of Cargo is older than the `", "` edition"],
+    ),
+    (
+        "edition-unsupported",
+        &["error: failed to parse the `edition` key"],
+    ),
+    (
+        "edition-reserved-prefix",
+        &["error: prefix `", "` is unknown"],
+    ),
+    ("edition-reserved-keyword", &["found reserved keyword `"]),
+    (
+        "edition-unsafe-extern",
+        &["error: extern blocks must be unsafe"],
+    ),
+    (
+        "edition-unsafe-attribute",
+        &["error: unsafe attribute used without unsafe"],
+    ),
+];
+
+/// Failures of `cargo fix`, e.g. during edition migration experiments.
+const CARGO_FIX: Rules = &[
+    (
+        "cargo-fix-failed",
+        &["failed to automatically apply fixes suggested by rustc"],
+    ),
+    (
+        "cargo-fix-dirty",
+        &[
+            "error: the working directory of this package has uncommitted changes, and `cargo fix` can potentially perform destructive changes",
+        ],
+    ),
+    (
+        "cargo-fix-no-vcs",
+        &[
+            "error: no VCS found for this package and `cargo fix` can potentially perform destructive changes",
+        ],
+    ),
+];
+
+/// Artifacts, most prominently proc-macros, built by a different compiler than the one loading them.
+const PROC_MACRO_ABI: Rules = &[
+    (
+        "proc-macro-abi-mismatch",
+        &["compiled by an incompatible version of rustc"],
+    ),
+    (
+        "proc-macro-abi-mismatch",
+        &["error[E0786]: found invalid metadata files for crate"],
+    ),
+    (
+        "proc-macro-abi-mismatch",
+        &[
+            "error[E0461]: couldn't find crate ",
+            " with expected target triple",
+        ],
+    ),
+];
+
+/// A broken or incomplete toolchain installation.
+const SYSROOT: Rules = &[
+    ("sysroot-missing-std", &["can't find crate for `std`"]),
+    ("sysroot-missing-std", &["can't find crate for `core`"]),
+    (
+        "sysroot-codegen-backend",
+        &["error: couldn't load codegen backend"],
+    ),
+    (
+        "sysroot-codegen-backend",
+        &["failed to find a `codegen-backends` folder in the sysroot candidates"],
+    ),
+];
+
+/// A built-in rule group named in the config doesn't exist.
+#[derive(thiserror::Error, Debug)]
+#[error("unknown built-in rule group '{0}', expected one of {known}", known = known_groups())]
+pub struct UnknownRuleGroup(pub String);
+
+fn known_groups() -> String {
+    BUILTIN_RULE_GROUPS
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl Config {
+    /// Adds the targets of all enabled built-in rule groups,
+    /// except for categories already defined by the config itself.
+    pub fn with_builtin_rules(mut self) -> Result<Self, UnknownRuleGroup> {
+        let mut builtin = HashMap::<String, Vec<Target>>::new();
+
+        for group in &self.builtin_rules {
+            let (_, rules) = BUILTIN_RULE_GROUPS
+                .iter()
+                .find(|(name, _)| name == group)
+                .ok_or_else(|| UnknownRuleGroup(group.clone()))?;
+
+            for (category, all) in *rules {
+                builtin
+                    .entry(category.to_string())
+                    .or_default()
+                    .push(Target {
+                        all: all.iter().map(|part| part.to_string()).collect(),
+                        any: Vec::new(),
+                        none: Vec::new(),
+                        regex: None,
+                        description: None,
+                        case_insensitive: false,
+                        collapse_whitespace: false,
+                        context_before: None,
+                        context_after: None,
+                    });
+            }
+        }
+
+        for (category, targets) in builtin {
+            self.targets.entry(category).or_insert(targets);
+        }
+
+        Ok(self)
+    }
+}
@@ -0,0 +1,46 @@
+//! Dependency chains cargo prints when it fails to resolve or build a transitive dependency, see [`dependency_chain`].
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+/// ``required by package `bar v0.1.0` ``
+static REQUIRED_BY: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"required by package `([^`]+)`").unwrap());
+/// ``... which satisfies dependency `bar = "^0.1"` of package `baz v0.2.0` `` or, by older cargo versions,
+/// ``... which is depended on by `baz v0.2.0` ``
+static DEPENDED_ON_BY: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"\.\.\. which (?:satisfies dependency `[^`]*` of package|is depended on by) `([^`]+)`",
+    )
+    .unwrap()
+});
+
+/// The first dependency chain printed at or after the 1-based `line_number`,
+/// from the package requiring the broken dependency up to the crate being built.
+///
+/// The second to last package of a chain is the direct dependency that pulled in the broken crate.
+/// Local paths cargo appends to packages, e.g. ` (/opt/rustwide/workdir)`, are removed.
+pub fn dependency_chain(log: &[u8], line_number: usize) -> Option<Vec<String>> {
+    let mut lines = log
+        .split(|&c| c == b'\n')
+        .skip(line_number.saturating_sub(1))
+        .map(String::from_utf8_lossy);
+
+    let first = lines.find_map(|line| Some(package(&REQUIRED_BY.captures(&line)?[1])))?;
+    let mut chain = vec![first];
+    for line in lines {
+        match DEPENDED_ON_BY.captures(&line) {
+            Some(captures) => chain.push(package(&captures[1])),
+            None => break,
+        }
+    }
+    Some(chain)
+}
+
+fn package(package: &str) -> String {
+    match package.split_once(" (") {
+        Some((package, _path)) => package.to_string(),
+        None => package.to_string(),
+    }
+}
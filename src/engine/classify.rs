@@ -0,0 +1,600 @@
+use std::{
+    borrow::Cow,
+    cell::OnceCell,
+    collections::{BTreeMap, HashMap},
+    sync::LazyLock,
+};
+
+use regex::bytes::Regex;
+
+use super::{
+    Config, RefinementConfig, Target, WarningsConfig, extract_windowed, proc_macro_panics,
+};
+
+/// The categories found in a single log and where they matched first.
+pub type LogFindings = BTreeMap<String, LineMatch>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineMatch {
+    /// 1-based number of the first line matching the category
+    pub line_number: usize,
+}
+
+/// Classifies a single log, returning all matched targets, [extracted](Config::extractors) categories, error codes and
+/// [proc-macro panics](super::proc_macro_panics), as well as the denied clippy lints for [clippy experiments](Config::extract_clippy_lints).
+pub fn process_log(config: &Config, log: &[u8]) -> LogFindings {
+    let mut log_findings = match_targets(&config.targets, log);
+    extract_windowed(&config.extractors, log, &mut log_findings);
+
+    extract_categories(&ERROR_REGEX, "", log, &mut log_findings);
+    if config.extract_clippy_lints {
+        extract_categories(&CLIPPY_LINT_REGEX, "clippy::", log, &mut log_findings);
+    }
+    for panic in proc_macro_panics(log) {
+        log_findings.entry(panic.category()).or_insert(LineMatch {
+            line_number: panic.line_number,
+        });
+    }
+
+    log_findings
+}
+
+/// Adds the first capture group of each match with `prefix` as a category.
+fn extract_categories(regex: &Regex, prefix: &str, log: &[u8], log_findings: &mut LogFindings) {
+    let mut line_number = 1;
+    let mut counted_until = 0;
+    for needle in regex.captures_iter(log) {
+        if let Some(capture) = needle.get(1) {
+            line_number += log[counted_until..capture.start()]
+                .iter()
+                .filter(|&&c| c == b'\n')
+                .count();
+            counted_until = capture.start();
+
+            log_findings
+                .entry(format!(
+                    "{prefix}{}",
+                    String::from_utf8_lossy(capture.as_bytes())
+                ))
+                .or_insert(LineMatch { line_number });
+        }
+    }
+}
+
+/// Classifies the log of a successful run, returning all matched warning targets.
+pub fn process_warnings(warnings: &WarningsConfig, log: &[u8]) -> LogFindings {
+    match_targets(&warnings.targets, log)
+}
+
+/// Classifies a log previously attributed to a category with the category's [refinement](Config::refinements).
+pub fn process_refinement(refinement: &RefinementConfig, log: &[u8]) -> LogFindings {
+    match_targets(&refinement.targets, log)
+}
+
+fn match_targets(targets: &HashMap<String, Vec<Target>>, log: &[u8]) -> LogFindings {
+    let mut log_findings = LogFindings::new();
+    let targets = prepare_targets(targets, None, log);
+
+    // patterns are matched against the parts between any line breaks,
+    // but only `\n` counts as a new line for the line numbers
+    for (index, line) in log.split(|&c| c == b'\n').enumerate() {
+        for segment in line.split(|&c| c == b'\r').filter(|s| !s.is_empty()) {
+            let segment = Segment::new(segment);
+            for (target_name, targets) in &targets {
+                if log_findings.contains_key(*target_name) {
+                    continue;
+                }
+
+                if targets.iter().any(|target| target.matches(&segment)) {
+                    log_findings.insert(
+                        (*target_name).clone(),
+                        LineMatch {
+                            line_number: index + 1,
+                        },
+                    );
+                }
+            }
+        }
+    }
+
+    log_findings
+}
+
+/// The patterns of a target normalized as configured by its flags, see [`Target::relaxation`].
+struct PreparedTarget<'a> {
+    original: &'a Target,
+    relaxation: Option<Relaxation>,
+    patterns: Vec<Cow<'a, [u8]>>,
+    alternatives: Vec<Cow<'a, [u8]>>,
+    exclusions: Vec<Cow<'a, [u8]>>,
+}
+
+impl<'a> PreparedTarget<'a> {
+    /// Prepares `target`, relaxing it further by `extra`.
+    fn new(target: &'a Target, extra: Option<Relaxation>) -> Self {
+        let relaxation = Relaxation::union(target.relaxation(), extra);
+        let prepare = |patterns: &'a [String]| {
+            patterns
+                .iter()
+                .map(|pat| match relaxation {
+                    Some(relaxation) => Cow::Owned(relaxation.normalize(pat.as_bytes())),
+                    None => Cow::Borrowed(pat.as_bytes()),
+                })
+                .collect()
+        };
+        Self {
+            original: target,
+            relaxation,
+            patterns: prepare(&target.all),
+            alternatives: prepare(&target.any),
+            exclusions: prepare(&target.none),
+        }
+    }
+
+    fn matches(&self, segment: &Segment<'_>) -> bool {
+        let segment = segment.get(self.relaxation);
+        self.patterns.iter().all(|pat| contains_bytes(segment, pat))
+            && self.any_matches(segment)
+            && self.regex_matches(segment)
+    }
+
+    /// Whether one of the target's `none` patterns occurs in `segment`, excluding the whole log.
+    fn is_excluded_by(&self, segment: &Segment<'_>) -> bool {
+        let segment = segment.get(self.relaxation);
+        self.exclusions
+            .iter()
+            .any(|pat| contains_bytes(segment, pat))
+    }
+
+    /// Whether one of the target's `any` patterns, if it has some, occurs in the already relaxed `segment`.
+    fn any_matches(&self, segment: &[u8]) -> bool {
+        self.alternatives.is_empty()
+            || self
+                .alternatives
+                .iter()
+                .any(|pat| contains_bytes(segment, pat))
+    }
+
+    /// Whether the target's regular expression, if any, matches the already relaxed `segment`.
+    fn regex_matches(&self, segment: &[u8]) -> bool {
+        let case_insensitive = self.relaxation.is_some_and(Relaxation::is_case_insensitive);
+        self.original
+            .regex
+            .as_ref()
+            .is_none_or(|regex| regex.is_match(segment, case_insensitive))
+    }
+}
+
+/// Prepares the `targets`, relaxed further by `extra`, leaving out those excluded from `log` by their `none` patterns.
+fn prepare_targets<'a>(
+    targets: &'a HashMap<String, Vec<Target>>,
+    extra: Option<Relaxation>,
+    log: &[u8],
+) -> Vec<(&'a String, Vec<PreparedTarget<'a>>)> {
+    let mut targets = targets
+        .iter()
+        .map(|(target_name, targets)| {
+            let targets = targets
+                .iter()
+                .map(|target| PreparedTarget::new(target, extra))
+                .collect::<Vec<_>>();
+            (target_name, targets)
+        })
+        .collect::<Vec<_>>();
+
+    let has_exclusions = targets
+        .iter()
+        .any(|(_, targets)| targets.iter().any(|target| !target.exclusions.is_empty()));
+    if has_exclusions {
+        for line in log.split(|&c| c == b'\n') {
+            for segment in line.split(|&c| c == b'\r').filter(|s| !s.is_empty()) {
+                let segment = Segment::new(segment);
+                for (_, targets) in &mut targets {
+                    targets.retain(|target| !target.is_excluded_by(&segment));
+                }
+            }
+        }
+    }
+    targets
+}
+
+/// A segment of a log line, normalized for each [`Relaxation`] on first use.
+struct Segment<'a> {
+    raw: &'a [u8],
+    relaxed: [OnceCell<Vec<u8>>; 3],
+}
+
+impl<'a> Segment<'a> {
+    fn new(raw: &'a [u8]) -> Self {
+        Self {
+            raw,
+            relaxed: Default::default(),
+        }
+    }
+
+    fn get(&self, relaxation: Option<Relaxation>) -> &[u8] {
+        match relaxation {
+            Some(relaxation) => {
+                self.relaxed[relaxation as usize].get_or_init(|| relaxation.normalize(self.raw))
+            }
+            None => self.raw,
+        }
+    }
+}
+
+/// A target of which some but not all patterns matched on the same line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NearMiss {
+    /// 1-based number of the first line matching the most patterns of the target
+    pub line_number: usize,
+    /// The patterns of the target that matched, its `any` patterns joined by ` | ` and its regular expression
+    /// written as `/regex/`
+    pub matched: Vec<String>,
+    pub missing: Vec<String>,
+}
+
+/// Finds the targets of unmatched categories that came closest to matching, to help with developing rules.
+///
+/// For each category not in `findings` the line matching the most patterns of any of its targets is reported,
+/// as long as at least one pattern matched there.
+pub fn near_misses(
+    targets: &HashMap<String, Vec<Target>>,
+    log: &[u8],
+    findings: &LogFindings,
+) -> BTreeMap<String, NearMiss> {
+    let mut misses = BTreeMap::<String, NearMiss>::new();
+    let targets = prepare_targets(targets, None, log);
+
+    for (index, line) in log.split(|&c| c == b'\n').enumerate() {
+        for segment in line.split(|&c| c == b'\r').filter(|s| !s.is_empty()) {
+            let segment = Segment::new(segment);
+            for (target_name, targets) in &targets {
+                if findings.contains_key(*target_name) {
+                    continue;
+                }
+
+                for target in targets {
+                    let relaxed_segment = segment.get(target.relaxation);
+                    let (mut matched, mut missing) = (Vec::new(), Vec::new());
+                    for (pattern, pat) in target.original.all.iter().zip(&target.patterns) {
+                        if contains_bytes(relaxed_segment, pat) {
+                            matched.push(pattern.clone());
+                        } else {
+                            missing.push(pattern.clone());
+                        }
+                    }
+                    if !target.original.any.is_empty() {
+                        let alternatives = target.original.any.join(" | ");
+                        if target.any_matches(relaxed_segment) {
+                            matched.push(alternatives);
+                        } else {
+                            missing.push(alternatives);
+                        }
+                    }
+                    if let Some(regex) = &target.original.regex {
+                        let regex_pattern = format!("/{regex}/");
+                        if target.regex_matches(relaxed_segment) {
+                            matched.push(regex_pattern);
+                        } else {
+                            missing.push(regex_pattern);
+                        }
+                    }
+                    let best = misses
+                        .get(*target_name)
+                        .map_or(0, |miss| miss.matched.len());
+                    if matched.len() > best {
+                        misses.insert(
+                            (*target_name).clone(),
+                            NearMiss {
+                                line_number: index + 1,
+                                matched,
+                                missing,
+                            },
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    misses
+}
+
+/// How a pattern was relaxed to match, see [`relaxed_matches`].
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, schemars::JsonSchema,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum Relaxation {
+    /// Runs of ASCII whitespace compared as a single space
+    Whitespace,
+    /// ASCII letters compared case-insensitively
+    CaseInsensitive,
+    /// Both of the above
+    WhitespaceAndCase,
+}
+
+impl Relaxation {
+    const ALL: [Self; 3] = [
+        Self::Whitespace,
+        Self::CaseInsensitive,
+        Self::WhitespaceAndCase,
+    ];
+
+    /// The relaxation matching both flags, `None` for exact matching.
+    pub fn from_flags(collapse_whitespace: bool, case_insensitive: bool) -> Option<Self> {
+        match (collapse_whitespace, case_insensitive) {
+            (false, false) => None,
+            (true, false) => Some(Self::Whitespace),
+            (false, true) => Some(Self::CaseInsensitive),
+            (true, true) => Some(Self::WhitespaceAndCase),
+        }
+    }
+
+    fn collapses_whitespace(self) -> bool {
+        self != Self::CaseInsensitive
+    }
+
+    fn is_case_insensitive(self) -> bool {
+        self != Self::Whitespace
+    }
+
+    /// The relaxation applying both `a` and `b`.
+    fn union(a: Option<Self>, b: Option<Self>) -> Option<Self> {
+        let any = |flag: fn(Self) -> bool| a.is_some_and(flag) || b.is_some_and(flag);
+        Self::from_flags(
+            any(Self::collapses_whitespace),
+            any(Self::is_case_insensitive),
+        )
+    }
+
+    fn normalize(self, text: &[u8]) -> Vec<u8> {
+        let text = match self {
+            Self::Whitespace | Self::WhitespaceAndCase => collapse_whitespace(text),
+            Self::CaseInsensitive => text.to_vec(),
+        };
+        match self {
+            Self::CaseInsensitive | Self::WhitespaceAndCase => text.to_ascii_lowercase(),
+            Self::Whitespace => text,
+        }
+    }
+}
+
+impl std::fmt::Display for Relaxation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Whitespace => "whitespace-normalized",
+            Self::CaseInsensitive => "case-insensitive",
+            Self::WhitespaceAndCase => "whitespace-normalized and case-insensitive",
+        })
+    }
+}
+
+/// A target that only matched once its patterns and the log were relaxed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RelaxedMatch {
+    /// 1-based number of the first line matching the relaxed target
+    pub line_number: usize,
+    pub relaxation: Relaxation,
+}
+
+/// Finds the categories not in `findings` that would have matched with whitespace-normalized
+/// or case-insensitive matching, e.g. as the compiler's wording changed slightly between releases.
+///
+/// For each category the first matching line is reported with the least relaxation needed there,
+/// whitespace normalization being tried before case-insensitivity.
+pub fn relaxed_matches(
+    targets: &HashMap<String, Vec<Target>>,
+    log: &[u8],
+    findings: &LogFindings,
+) -> BTreeMap<String, RelaxedMatch> {
+    let mut matches = BTreeMap::new();
+    let unmatched = targets
+        .iter()
+        .filter(|(target_name, _)| !findings.contains_key(*target_name))
+        .map(|(target_name, targets)| (target_name.clone(), targets.clone()))
+        .collect::<HashMap<_, _>>();
+    if unmatched.is_empty() {
+        return matches;
+    }
+    let relaxed =
+        Relaxation::ALL.map(|relaxation| prepare_targets(&unmatched, Some(relaxation), log));
+
+    for (index, line) in log.split(|&c| c == b'\n').enumerate() {
+        for segment in line.split(|&c| c == b'\r').filter(|s| !s.is_empty()) {
+            let segment = Segment::new(segment);
+            for (relaxation, targets) in Relaxation::ALL.into_iter().zip(&relaxed) {
+                for (target_name, targets) in targets {
+                    if matches.contains_key(*target_name) {
+                        continue;
+                    }
+
+                    if targets.iter().any(|target| target.matches(&segment)) {
+                        matches.insert(
+                            (*target_name).clone(),
+                            RelaxedMatch {
+                                line_number: index + 1,
+                                relaxation,
+                            },
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    matches
+}
+
+/// Replaces each run of ASCII whitespace with a single space, keeping runs at the edges
+/// so that patterns bordering on text left out between them still require the whitespace.
+fn collapse_whitespace(text: &[u8]) -> Vec<u8> {
+    let mut collapsed = Vec::with_capacity(text.len());
+    for &c in text {
+        if !c.is_ascii_whitespace() {
+            collapsed.push(c);
+        } else if collapsed.last() != Some(&b' ') {
+            collapsed.push(b' ');
+        }
+    }
+    collapsed
+}
+
+fn contains_bytes(haystack: &[u8], needle: &[u8]) -> bool {
+    // `windows` panics for a length of 0
+    needle.is_empty()
+        || haystack
+            .windows(needle.len())
+            .any(|window| window == needle)
+}
+
+static ERROR_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    regex::bytes::RegexBuilder::new(r#"^\[INFO\] \[stdout\] error\[(E\d+)\]:"#)
+        .multi_line(true)
+        .build()
+        .unwrap()
+});
+
+/// The note naming the lint that failed the build, e.g. ``= note: `-D clippy::needless-return` implied by `-D clippy::all` ``
+/// or ``= note: `#[deny(clippy::approx_constant)]` on by default``.
+static CLIPPY_LINT_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"note: `(?:#\[deny\(|-D )clippy::([a-z0-9_-]+)").unwrap());
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn targets(toml: &str) -> HashMap<String, Vec<Target>> {
+        toml::from_str(toml).unwrap()
+    }
+
+    fn matched(toml: &str, log: &str) -> Vec<(String, usize)> {
+        match_targets(&targets(toml), log.as_bytes())
+            .into_iter()
+            .map(|(category, found)| (category, found.line_number))
+            .collect()
+    }
+
+    #[test]
+    fn all_patterns_must_occur_in_the_same_line() {
+        let toml = r#"no-space = [{ all = ["No space", "left on device"] }]"#;
+        assert_eq!(
+            matched(toml, "compiling\nerror: No space left on device\n"),
+            [("no-space".to_string(), 2)]
+        );
+        assert!(matched(toml, "No space\nleft on device\n").is_empty());
+    }
+
+    #[test]
+    fn any_requires_one_of_its_patterns() {
+        let toml = r#"network = [{ all = ["failed to"], any = ["download", "fetch"] }]"#;
+        assert_eq!(
+            matched(toml, "failed to fetch `foo`"),
+            [("network".to_string(), 1)]
+        );
+        assert!(matched(toml, "failed to compile `foo`").is_empty());
+    }
+
+    #[test]
+    fn regex_must_match_the_same_line() {
+        let toml = r#"timeout = [{ regex = 'no output for \d+ seconds' }]"#;
+        assert_eq!(
+            matched(toml, "ok\nerror: no output for 300 seconds"),
+            [("timeout".to_string(), 2)]
+        );
+        assert!(matched(toml, "no output for many seconds").is_empty());
+    }
+
+    #[test]
+    fn none_excludes_the_whole_log() {
+        let toml = r#"build-script = [{ all = ["failed to run custom build command"], none = ["No space left"] }]"#;
+        let log = "error: failed to run custom build command\nNo space left on device";
+        assert!(matched(toml, log).is_empty());
+        assert_eq!(
+            matched(toml, "error: failed to run custom build command"),
+            [("build-script".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn carriage_returns_split_lines_but_not_line_numbers() {
+        let toml = r#"progress = [{ all = ["Downloading", "crates"] }]"#;
+        assert!(matched(toml, "Downloading\rcrates").is_empty());
+        assert_eq!(
+            matched(toml, "a\r\nb\rDownloading 3 crates\n"),
+            [("progress".to_string(), 2)]
+        );
+    }
+
+    #[test]
+    fn case_insensitive_ignores_ascii_case() {
+        let toml = r#"ice = [{ all = ["Internal Compiler Error"], case_insensitive = true }]"#;
+        assert_eq!(
+            matched(toml, "error: internal compiler error: boom"),
+            [("ice".to_string(), 1)]
+        );
+        assert!(
+            matched(
+                r#"ice = [{ all = ["Internal Compiler Error"] }]"#,
+                "internal compiler error"
+            )
+            .is_empty()
+        );
+    }
+
+    #[test]
+    fn case_insensitive_applies_to_regex() {
+        let toml = r#"ice = [{ regex = 'compiler error: \w+', case_insensitive = true }]"#;
+        assert_eq!(
+            matched(toml, "INTERNAL COMPILER ERROR: BOOM"),
+            [("ice".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn collapse_whitespace_matches_runs_as_one_space() {
+        let toml =
+            r#"linker = [{ all = ["linking  with `cc`\tfailed"], collapse_whitespace = true }]"#;
+        assert_eq!(
+            matched(toml, "error: linking with   `cc` failed: exit status: 1"),
+            [("linker".to_string(), 1)]
+        );
+        assert!(matched(toml, "error: linking with`cc` failed").is_empty());
+    }
+
+    #[test]
+    fn collapse_whitespace_keeps_runs_at_the_edges() {
+        assert_eq!(collapse_whitespace(b"  a \t\n b  "), b" a b ");
+        assert_eq!(collapse_whitespace(b""), b"");
+    }
+
+    #[test]
+    fn relaxed_matches_report_the_least_relaxation() {
+        let targets = targets(
+            r#"
+            spaces = [{ all = ["linking with `cc` failed"] }]
+            case = [{ all = ["Linking With"] }]
+            "#,
+        );
+        let log = b"LINKING WITH\nerror: linking  with `cc` failed";
+        let matches = relaxed_matches(&targets, log, &LogFindings::new());
+        assert_eq!(matches["spaces"].relaxation, Relaxation::Whitespace);
+        assert_eq!(matches["spaces"].line_number, 2);
+        assert_eq!(matches["case"].relaxation, Relaxation::CaseInsensitive);
+        assert_eq!(matches["case"].line_number, 1);
+    }
+
+    #[test]
+    fn near_misses_report_the_missing_patterns() {
+        let targets = targets(r#"no-space = [{ all = ["No space", "left on device"] }]"#);
+        let misses = near_misses(&targets, b"ok\nNo space here", &LogFindings::new());
+        assert_eq!(
+            misses["no-space"],
+            NearMiss {
+                line_number: 2,
+                matched: vec!["No space".to_string()],
+                missing: vec!["left on device".to_string()],
+            }
+        );
+    }
+}
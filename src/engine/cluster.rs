@@ -0,0 +1,69 @@
+//! Pre-clustering of unclassified logs by their last error line, see [`last_error_line`].
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+/// Error lines that follow the actual error in almost every failed build and thus tell nothing about it.
+const GENERIC_ERRORS: &[&str] = &[
+    "error: could not compile",
+    "error: aborting due to",
+    "error: build failed",
+    "error: failed to compile",
+];
+
+pub(crate) static CRATER_PREFIX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\[[A-Z]+\] \[std(out|err)\] ").unwrap());
+static QUOTED: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"`[^`]*`|"[^"]*""#).unwrap());
+static PATH: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(\.{0,2}/[^\s:,)]+)+").unwrap());
+/// Numbers except error codes, which are matched to keep them
+static NUMBER: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\bE\d{4}\b|\d+").unwrap());
+
+/// The last error line of a log normalized so that lines only differing in
+/// crate names, paths and numbers are equal, or `None` if the log has no error line.
+///
+/// Generic summary errors like `error: could not compile` are skipped in favor of an earlier error, if any.
+pub fn last_error_line(log: &[u8]) -> Option<String> {
+    let mut last_generic = None;
+
+    for line in log.rsplit(|&c| c == b'\n') {
+        let line = String::from_utf8_lossy(line);
+        let line = CRATER_PREFIX.replace(line.trim_end(), "");
+        let line = line.trim_start();
+        if !line.starts_with("error") {
+            continue;
+        }
+
+        let normalized = normalize_error_line(line);
+        if GENERIC_ERRORS
+            .iter()
+            .any(|generic| normalized.starts_with(generic))
+        {
+            last_generic.get_or_insert(normalized);
+        } else {
+            return Some(normalized);
+        }
+    }
+
+    last_generic
+}
+
+/// Normalizes a log line, so that lines only differing in crate names, paths and numbers are equal.
+pub(crate) fn normalize_error_line(line: &str) -> String {
+    let line = CRATER_PREFIX.replace(line.trim_end(), "");
+    let line = QUOTED.replace_all(line.trim_start(), |captures: &regex::Captures<'_>| {
+        let quote = &captures[0][..1];
+        format!("{quote}…{quote}")
+    });
+    let line = PATH.replace_all(&line, "<path>");
+    NUMBER
+        .replace_all(&line, |captures: &regex::Captures<'_>| {
+            let number = &captures[0];
+            if number.starts_with('E') {
+                number.to_string()
+            } else {
+                "N".to_string()
+            }
+        })
+        .into_owned()
+}
@@ -0,0 +1,989 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use super::{
+    BUILTIN_RULE_GROUPS, ExampleLimits, ExcerptContext, Extractor, Redactor, Relaxation,
+    ReportFormat, SelectExpr, format_date, stable_hash,
+};
+
+/// The configs `init --profile` can write by name, with what they are meant for.
+pub const CONFIG_PROFILES: [(&str, &str); 3] = [
+    (
+        "example",
+        "all built-in rules for the crates crater reports as errors",
+    ),
+    (
+        "beta-regression",
+        "regressed builds and tests of a beta run, skipping crates that ran out of disk space in the next run",
+    ),
+    (
+        "prepare-fail",
+        "crates that failed before the build started, mostly infrastructure issues",
+    ),
+];
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
+pub struct Config {
+    /// The crater crate results selected, e.g. `regressed` or `["regressed", "spurious-regressed"]`
+    #[serde(default)]
+    pub crate_result: ExpectedResults,
+    /// The crater run results selected of those crates, e.g. `build-fail:compiler-error`
+    #[serde(default)]
+    pub run_result: ExpectedResults,
+    /// Selects the interesting runs instead of `crate_result` and `run_result` when set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub select: Option<SelectExpr>,
+    /// Names of the enabled groups of [built-in rules](super::BUILTIN_RULE_GROUPS)
+    #[serde(default)]
+    pub builtin_rules: Vec<String>,
+    #[serde(default)]
+    pub targets: HashMap<String, Vec<Target>>,
+    /// Regular expressions deriving categories from several lines at a time, e.g. ICEs by the query in their backtrace
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extractors: Vec<Extractor>,
+    /// Patterns to look for in runs that did not fail, reported separately from the failures
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warnings: Option<WarningsConfig>,
+    /// Further selections classified in the same pass and reported in their own section,
+    /// e.g. the runs crater marked as timed out
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub sections: BTreeMap<String, SectionConfig>,
+    /// More detailed rule sets for the runs previously attributed to a category, e.g. ICE signatures for `ice`,
+    /// applied by `--refine-category` without classifying the other runs again
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub refinements: BTreeMap<String, RefinementConfig>,
+    /// The team owning the categories matching each pattern, `*` matches any text and `?` a single character,
+    /// e.g. `"E05??" = "t-types"`; reports group the found categories by owner
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub owners: BTreeMap<String, String>,
+    /// Regular expressions of credentials redacted from the log lines in reports in addition to common token formats,
+    /// only the capture group named `secret` is redacted if there is one
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redact_patterns: Vec<String>,
+    /// Categories caused by the infrastructure rather than the crate, e.g. network errors,
+    /// used by `crosstab` to find crates whose crater result disagrees with the analysis
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub spurious_categories: BTreeSet<String>,
+    /// Categories whose crates are written as entries to skip in crater's `config.toml` to
+    /// `results/<experiment>/blacklist.toml`, `*` matches any text and `?` a single character
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub blacklist_categories: Vec<String>,
+    /// Whether shields.io endpoint badges of the analysis are written to `results/<experiment>/badges/`,
+    /// `summary.json` with the runs and the share of unclassified runs and `category-<category>.json` per found category
+    #[serde(default)]
+    pub badges: bool,
+    /// A TOML file mapping tags to crate names, e.g. `ours = ["foo", "bar"]`;
+    /// reports list the selected crates of each tag with their categories
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub crate_tags_file: String,
+    /// Whether selected crates with runs without a recorded result are reported
+    #[serde(default)]
+    pub missing_runs: MissingRunHandling,
+    /// Whether selected crates whose version is yanked on crates.io are looked up and annotated or excluded
+    #[serde(default)]
+    pub yanked: YankedHandling,
+    /// What happens to the cache of an experiment whose crater report was regenerated after it was cached
+    #[serde(default)]
+    pub regenerated_reports: RegeneratedReportHandling,
+    /// What happens to experiments analyzed before, `--force-reanalyze` analyzes them again regardless
+    #[serde(default)]
+    pub existing_analyses: ExistingAnalysisHandling,
+    /// Config files used instead of this one for the experiments matching a pattern, e.g. `"beta-*" = "beta.toml"`,
+    /// where `*` matches any text and `?` a single character; of several matching patterns the longest wins
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub experiment_configs: BTreeMap<String, String>,
+    /// The directory the [experiment configs](Config::experiment_configs) are looked up in
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub config_dir: String,
+    /// Adjustments per crater experiment mode (e.g. `clippy`), see [`Config::for_mode`]
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub modes: BTreeMap<String, ModeConfig>,
+    /// Whether denied clippy lints are extracted as `clippy::<lint>` categories, set for clippy experiments
+    #[serde(skip)]
+    pub extract_clippy_lints: bool,
+    /// Upper bound for the total size of the logs being classified at the same time, 0 disables the limit
+    #[serde(default = "default_log_memory_budget_mib")]
+    pub log_memory_budget_mib: u32,
+    /// Bytes after which log lines are cut off before matching, excerpts and pruning, e.g. for lines of
+    /// emitted JSON megabytes long; 0 keeps the lines in full, see [`truncate_long_lines`](super::truncate_long_lines)
+    #[serde(default = "default_max_line_length")]
+    pub max_line_length: usize,
+    /// Whether logs identical to a log cached for another experiment, e.g. of retried crates, are taken from the
+    /// shared log store instead of being downloaded again, looked up by the ETag of the log's `GET` response
+    #[serde(default = "default_share_logs")]
+    pub share_logs: bool,
+    /// Compressed variants of the logs tried in order before the plain `log.txt`, the first one published
+    /// for a log is downloaded and decompressed; a variant missing for one log isn't tried for the other logs of
+    /// the experiment. Logs served with a compressed transfer encoding are decompressed regardless
+    #[serde(default)]
+    pub compressed_logs: Vec<LogCompression>,
+    /// Whether cached logs with findings are truncated to the matched lines and their context after classification,
+    /// they are downloaded again in full once the rule set changes
+    #[serde(default)]
+    pub prune_logs: bool,
+    /// Whether crates failing only because a dependency failed to build are reclassified as
+    /// `blocked-on: <dependency>` instead of the categories of the dependency's errors, see [`blocking_dependency`](super::blocking_dependency)
+    #[serde(default)]
+    pub reclassify_blocked: bool,
+    /// Whether selected logs are checked for categories that would have matched with whitespace-normalized
+    /// or case-insensitive matching, to find rules broken by minor wording changes, see [`relaxed_matches`](super::relaxed_matches)
+    #[serde(default)]
+    pub relaxed_diagnostics: bool,
+    /// Whether a line of JSON with the crate, categories and log URL of each classified run is appended to
+    /// `results/{experiment}/findings.jsonl` during the analysis, so that partial results can be followed;
+    /// the stream of the previous analysis of the experiment is truncated when it starts
+    #[serde(default)]
+    pub findings_stream: bool,
+    /// Whether a warning is logged at startup if a newer release of this tool was published, checked at most once a day
+    #[serde(default)]
+    pub check_for_updates: bool,
+    /// The experiment endpoint of crater's API, `{experiment}` is replaced with the experiment's name;
+    /// the status and dates it returns are shown in the report, an empty URL disables the lookup
+    #[serde(default = "default_crater_api_url")]
+    pub crater_api_url: String,
+    /// Warns when analyzing experiments completed more than this many days ago, 0 disables the warning
+    #[serde(default = "default_max_experiment_age_days")]
+    pub max_experiment_age_days: u64,
+    /// How long logs that failed to download with 404 are not requested again
+    #[serde(default = "default_missing_log_expiry_days")]
+    pub missing_log_expiry_days: u64,
+    /// Seconds between the plain text progress lines printed instead of progress bars when stderr is not a terminal
+    #[serde(default = "default_progress_interval_secs")]
+    pub progress_interval_secs: u64,
+    /// Put between groups of three digits of the counts in rendered reports, e.g. `,` for `12,345`
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub thousands_separator: String,
+    /// Where reports are written, `{experiment}`, `{date}` (UTC, `YYYY-MM-DD`), `{format}` (`text`, `markdown`
+    /// or `html`) and `{ext}` (`report`, `md` or `html`) are replaced, see [`Config::report_path`]
+    #[serde(default = "default_report_path")]
+    pub report_path: String,
+    /// Maximum number of crates listed per category in Markdown and HTML reports, 0 lists all
+    #[serde(default = "default_max_examples")]
+    pub max_examples: usize,
+    /// Overrides `max_examples` for individual categories
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub max_examples_per_category: BTreeMap<String, usize>,
+    /// Categories with more crates are listed on separate HTML pages of this many crates each,
+    /// so that the HTML report of large experiments stays responsive; 0 lists all crates in the report
+    #[serde(default = "default_html_page_size")]
+    pub html_page_size: usize,
+    /// Ranks affected crates among this many most downloaded crates on crates.io, 0 disables the ranking
+    #[serde(default)]
+    pub top_crates: usize,
+    /// Posts a summary of each analyzed experiment to Zulip
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zulip: Option<ZulipConfig>,
+    /// Executables that get each selected log on stdin and print the names of the matched categories on stdout
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub external_classifiers: Vec<ExternalClassifierConfig>,
+    /// Maximum number of external classifier processes running at the same time
+    #[serde(default = "default_external_classifier_concurrency")]
+    pub external_classifier_concurrency: usize,
+    /// External commands run with the JSON report on stdin after an experiment was analyzed
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub hooks: BTreeMap<String, HookConfig>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
+pub struct HookConfig {
+    /// The program followed by its arguments
+    pub command: Vec<String>,
+    #[serde(default = "default_hook_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+/// Replaces the selection for experiments of a crater mode.
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
+pub struct ModeConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crate_result: Option<ExpectedResults>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_result: Option<ExpectedResults>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub select: Option<SelectExpr>,
+}
+
+/// What to do with crates whose runs lack a result in the crater report,
+/// which sometimes indicates a bug in crater worth reporting.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MissingRunHandling {
+    /// List them in a separate "no run recorded" bucket
+    #[default]
+    Report,
+    Ignore,
+}
+
+/// What to do with the cache of an experiment whose crater report was regenerated after it was cached,
+/// detected by the `Last-Modified` header of its `results.json`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RegeneratedReportHandling {
+    /// Download the `results.json` again and retry the logs that were missing
+    #[default]
+    Results,
+    /// Additionally drop the cached logs, in case crater replaced them as well
+    Logs,
+    /// Keep using the cached report, without checking whether it was regenerated
+    Ignore,
+}
+
+/// What to do about an experiment whose `analysis.json` is left from an earlier run, e.g. when running
+/// the same list of experiments again after adding some to it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExistingAnalysisHandling {
+    /// Analyze the experiment again
+    #[default]
+    Reanalyze,
+    /// Report from the existing analysis if it was made from the same crater results, rules and selection,
+    /// see [`Config::reproducibility_hash`], and analyze the experiment again otherwise
+    Reuse,
+}
+
+/// What to do about selected crates whose version was yanked from crates.io, as nobody can depend on them anymore.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum YankedHandling {
+    /// Don't look up whether versions are yanked
+    #[default]
+    Ignore,
+    /// List the yanked crates in the report, but analyze them as usual
+    Annotate,
+    /// Leave the yanked crates out of the analysis, only listing them in the report
+    Exclude,
+}
+
+/// A compressed variant of crater's logs, next to `log.txt` as `log.txt.<extension>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogCompression {
+    Zstd,
+    Brotli,
+    Gzip,
+}
+
+impl LogCompression {
+    /// The extension appended to `log.txt`.
+    pub fn extension(self) -> &'static str {
+        match self {
+            LogCompression::Zstd => "zst",
+            LogCompression::Brotli => "br",
+            LogCompression::Gzip => "gz",
+        }
+    }
+}
+
+/// Where to post summaries, the bot's credentials are taken from the
+/// `ZULIP_BOT_EMAIL` and `ZULIP_API_KEY` environment variables.
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
+pub struct ZulipConfig {
+    /// e.g. `https://rust-lang.zulipchat.com`
+    pub site: String,
+    pub stream: String,
+    /// The topic to post to, `{experiment}` is replaced with the experiment's name
+    #[serde(default = "default_zulip_topic")]
+    pub topic: String,
+}
+
+fn default_zulip_topic() -> String {
+    "crater {experiment}".to_string()
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
+pub struct ExternalClassifierConfig {
+    /// The program followed by its arguments
+    pub command: Vec<String>,
+    #[serde(default = "default_hook_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_external_classifier_concurrency() -> usize {
+    4
+}
+
+fn default_hook_timeout_secs() -> u64 {
+    60
+}
+
+fn default_crater_api_url() -> String {
+    "https://crater.rust-lang.org/api/v1/experiments/{experiment}".to_string()
+}
+
+fn default_max_experiment_age_days() -> u64 {
+    30
+}
+
+fn default_progress_interval_secs() -> u64 {
+    10
+}
+
+fn default_report_path() -> String {
+    "results/{experiment}/{experiment}.{ext}".to_string()
+}
+
+fn default_max_examples() -> usize {
+    50
+}
+
+fn default_missing_log_expiry_days() -> u64 {
+    7
+}
+
+fn default_max_line_length() -> usize {
+    64 * 1024
+}
+
+fn default_log_memory_budget_mib() -> u32 {
+    2048
+}
+
+fn default_share_logs() -> bool {
+    true
+}
+
+fn default_html_page_size() -> usize {
+    1000
+}
+
+/// Selects successful runs and the targets to look for in them,
+/// e.g. future-incompat reports to size upcoming deprecations.
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
+pub struct WarningsConfig {
+    pub crate_result: ExpectedResults,
+    pub run_result: ExpectedResults,
+    pub targets: HashMap<String, Vec<Target>>,
+}
+
+/// A further selection of runs classified with the same targets, see [`Config::sections`].
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
+pub struct SectionConfig {
+    pub crate_result: ExpectedResults,
+    pub run_result: ExpectedResults,
+}
+
+/// One or several accepted crater results, written as a single string or a list of strings.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(from = "OneOrMany", into = "OneOrMany")]
+pub struct ExpectedResults(Vec<String>);
+
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl From<OneOrMany> for ExpectedResults {
+    fn from(results: OneOrMany) -> Self {
+        match results {
+            OneOrMany::One(result) => Self(vec![result]),
+            OneOrMany::Many(results) => Self(results),
+        }
+    }
+}
+
+impl From<ExpectedResults> for OneOrMany {
+    fn from(mut results: ExpectedResults) -> Self {
+        if results.0.len() == 1 {
+            OneOrMany::One(results.0.remove(0))
+        } else {
+            OneOrMany::Many(results.0)
+        }
+    }
+}
+
+impl From<&str> for ExpectedResults {
+    fn from(result: &str) -> Self {
+        Self(vec![result.to_string()])
+    }
+}
+
+impl ExpectedResults {
+    pub fn contains(&self, result: &str) -> bool {
+        self.0.iter().any(|expected| expected == result)
+    }
+
+    /// Whether no result is accepted, i.e. the results weren't configured.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// The results joined by `or`, e.g. `build-fail:compiler-error or test-fail:test-failed`.
+impl std::fmt::Display for ExpectedResults {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0.join(" or "))
+    }
+}
+
+/// The targets splitting a category into more specific categories, see [`Config::refinements`].
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
+pub struct RefinementConfig {
+    pub targets: HashMap<String, Vec<Target>>,
+}
+
+impl Config {
+    /// Describes the selected crates in reports.
+    pub fn expected_krate_result(&self) -> String {
+        if self.select.is_some() {
+            "selected".to_string()
+        } else {
+            self.crate_result.to_string()
+        }
+    }
+
+    /// Describes the selected runs in reports.
+    pub fn expected_run_result(&self) -> String {
+        if self.select.is_some() {
+            "selected".to_string()
+        } else {
+            self.run_result.to_string()
+        }
+    }
+
+    /// The config adjusted for experiments of a crater mode, e.g. `build-and-test` or `clippy`.
+    ///
+    /// The selection of the mode's [`ModeConfig`] replaces the configured one. Clippy experiments
+    /// additionally get their denied lints extracted and, where no selection is configured at all,
+    /// default to the regressed crates failing to build.
+    pub fn for_mode(&self, mode: &str) -> Self {
+        let mut config = self.clone();
+        let clippy = mode == "clippy";
+        config.extract_clippy_lints = clippy;
+
+        let mode_config = match self.modes.get(mode) {
+            Some(mode_config) => mode_config.clone(),
+            // a configured `crate_result`, `run_result` or `select` is kept
+            None if clippy && self.select.is_none() => ModeConfig {
+                crate_result: self.crate_result.is_empty().then(|| "regressed".into()),
+                run_result: self
+                    .run_result
+                    .is_empty()
+                    .then(|| "build-fail:compiler-error".into()),
+                select: None,
+            },
+            None => return config,
+        };
+        if let Some(crate_result) = mode_config.crate_result {
+            config.crate_result = crate_result;
+            config.select = None;
+        }
+        if let Some(run_result) = mode_config.run_result {
+            config.run_result = run_result;
+            config.select = None;
+        }
+        if let Some(select) = mode_config.select {
+            config.select = Some(select);
+        }
+        config
+    }
+
+    /// Identifies the rule set, so that results of different rule sets can be told apart.
+    pub fn ruleset_hash(&self) -> String {
+        // the targets are a HashMap, sort them for a stable serialization
+        let targets = self.targets.iter().collect::<BTreeMap<_, _>>();
+        let warning_targets = self
+            .warnings
+            .as_ref()
+            .map(|warnings| warnings.targets.iter().collect::<BTreeMap<_, _>>());
+        // only serialized if used, so that the hash of rule sets without extractors or reclassification stays the same
+        let serialized = if self.extractors.is_empty() && !self.reclassify_blocked {
+            serde_json::to_vec(&(targets, warning_targets))
+        } else if self.extractors.is_empty() {
+            serde_json::to_vec(&(targets, warning_targets, self.reclassify_blocked))
+        } else {
+            serde_json::to_vec(&(
+                targets,
+                warning_targets,
+                self.reclassify_blocked,
+                &self.extractors,
+            ))
+        }
+        .unwrap();
+        format!("{:016x}", stable_hash(&serialized))
+    }
+
+    /// Identifies an analysis of the crater results `results_json` with this config and version of the tool,
+    /// taking everything that influences the categories into account: the [rule set](Config::ruleset_hash),
+    /// the selections and the external classifiers. Reports with the same hash are guaranteed to be comparable.
+    pub fn reproducibility_hash(&self, results_json: &[u8]) -> String {
+        let sections = self
+            .sections
+            .iter()
+            .map(|(name, section)| (name, &section.crate_result, &section.run_result))
+            .collect::<Vec<_>>();
+        let warnings = self
+            .warnings
+            .as_ref()
+            .map(|warnings| (&warnings.crate_result, &warnings.run_result));
+        let external_classifiers = self
+            .external_classifiers
+            .iter()
+            .map(|classifier| &classifier.command)
+            .collect::<Vec<_>>();
+        let serialized = serde_json::to_vec(&(
+            env!("CARGO_PKG_VERSION"),
+            format!("{:016x}", stable_hash(results_json)),
+            self.ruleset_hash(),
+            (
+                &self.crate_result,
+                &self.run_result,
+                self.select.as_ref().map(SelectExpr::source),
+            ),
+            sections,
+            warnings,
+            (
+                &self.yanked,
+                &self.missing_runs,
+                self.extract_clippy_lints,
+                self.max_line_length,
+            ),
+            external_classifiers,
+        ))
+        .unwrap();
+        format!("{:016x}", stable_hash(&serialized))
+    }
+
+    /// Identifies the whole effective config an analysis was made with, so that it is only reused while nothing
+    /// that went into it changed, e.g. redaction, owners or excerpt limits, which don't affect the
+    /// [reproducibility hash](Config::reproducibility_hash). Where reports are written, how counts are
+    /// formatted and whether analyses are reused are left out, as they aren't part of the stored analysis.
+    pub fn config_hash(&self) -> String {
+        let mut config = serde_json::to_value(self).unwrap();
+        if let Some(config) = config.as_object_mut() {
+            config.remove("report_path");
+            config.remove("thousands_separator");
+            config.remove("existing_analyses");
+        }
+        // maps are sorted, so that neither the order in the file nor of the `HashMap`s count
+        let serialized = serde_json::to_vec(&(config, self.extract_clippy_lints)).unwrap();
+        format!("{:016x}", stable_hash(&serialized))
+    }
+
+    /// The [reproducibility hash](Config::reproducibility_hash) of an analysis with the reproducibility hash
+    /// `hash` after the runs of `category` were refined with this config's [refinement](Config::refinements).
+    pub fn refined_reproducibility_hash(&self, hash: &str, category: &str) -> String {
+        let refinement = self
+            .refinements
+            .get(category)
+            .map(|refinement| refinement.targets.iter().collect::<BTreeMap<_, _>>());
+        let serialized = serde_json::to_vec(&(hash, category, refinement)).unwrap();
+        format!("{:016x}", stable_hash(&serialized))
+    }
+
+    pub fn example_limits(&self) -> ExampleLimits {
+        ExampleLimits {
+            default: self.max_examples,
+            per_category: self.max_examples_per_category.clone(),
+            page_size: self.html_page_size,
+        }
+    }
+
+    /// The description of each category, taken from the first of its targets that has one.
+    pub fn category_descriptions(&self) -> BTreeMap<String, String> {
+        let warning_targets = self.warnings.iter().flat_map(|warnings| &warnings.targets);
+        let refined_targets = self
+            .refinements
+            .values()
+            .flat_map(|refinement| &refinement.targets);
+        let mut descriptions = BTreeMap::new();
+        for (name, targets) in self
+            .targets
+            .iter()
+            .chain(warning_targets)
+            .chain(refined_targets)
+        {
+            if let Some(description) = targets
+                .iter()
+                .find_map(|target| target.description.as_ref())
+            {
+                descriptions
+                    .entry(name.clone())
+                    .or_insert_with(|| description.clone());
+            }
+        }
+        descriptions
+    }
+
+    /// The lines of context captured around the matches of `category`, as set by the first of its targets
+    /// (in `targets`, `warnings.targets` or a refinement) setting it, the default otherwise.
+    pub fn excerpt_context(&self, category: &str) -> ExcerptContext {
+        let targets = || {
+            let warning_targets = self
+                .warnings
+                .iter()
+                .filter_map(|warnings| warnings.targets.get(category));
+            let refined_targets = self
+                .refinements
+                .values()
+                .filter_map(|refinement| refinement.targets.get(category));
+            self.targets
+                .get(category)
+                .into_iter()
+                .chain(warning_targets)
+                .chain(refined_targets)
+                .flatten()
+        };
+        let default = ExcerptContext::default();
+        ExcerptContext {
+            before: targets()
+                .find_map(|target| target.context_before)
+                .unwrap_or(default.before),
+            after: targets()
+                .find_map(|target| target.context_after)
+                .unwrap_or(default.after),
+        }
+    }
+
+    /// The redactor for the log lines in reports, failing for invalid [patterns](Config::redact_patterns).
+    pub fn redactor(&self) -> Result<Redactor, regex::Error> {
+        Redactor::new(&self.redact_patterns)
+    }
+
+    /// Groups the categories by the owners of the [patterns](Config::owners) they match,
+    /// a category matching the patterns of several owners is listed for each of them.
+    pub fn category_owners<'a>(
+        &self,
+        categories: impl IntoIterator<Item = &'a String>,
+    ) -> BTreeMap<String, Vec<String>> {
+        let mut owners = BTreeMap::<String, Vec<String>>::new();
+        for category in categories {
+            let category_owners = self
+                .owners
+                .iter()
+                .filter(|(pattern, _)| glob_matches(pattern, category))
+                .map(|(_, owner)| owner)
+                .collect::<BTreeSet<_>>();
+            for owner in category_owners {
+                owners
+                    .entry(owner.clone())
+                    .or_default()
+                    .push(category.clone());
+            }
+        }
+        owners
+    }
+
+    /// The path of the report of `experiment` in `format` written at `unix_seconds` according to [`Config::report_path`].
+    pub fn report_path(&self, experiment: &str, format: ReportFormat, unix_seconds: u64) -> String {
+        self.report_path
+            .replace("{experiment}", experiment)
+            .replace("{date}", &format_date(unix_seconds))
+            .replace("{format}", format.name())
+            .replace("{ext}", format.extension())
+    }
+
+    /// The path of the [experiment config](Config::experiment_configs) to use for `experiment`, if any.
+    pub fn experiment_config_path(&self, experiment: &str) -> Option<PathBuf> {
+        let (_, file) = self
+            .experiment_configs
+            .iter()
+            .filter(|(pattern, _)| glob_matches(pattern, experiment))
+            .max_by_key(|(pattern, _)| pattern.len())?;
+        Some(Path::new(&self.config_dir).join(file))
+    }
+
+    /// The paths of all [experiment configs](Config::experiment_configs).
+    pub fn experiment_config_paths(&self) -> BTreeSet<PathBuf> {
+        self.experiment_configs
+            .values()
+            .map(|file| Path::new(&self.config_dir).join(file))
+            .collect()
+    }
+
+    pub fn missing_log_expiry(&self) -> Duration {
+        Duration::from_secs(self.missing_log_expiry_days * 24 * 60 * 60)
+    }
+
+    /// The config written when no config file exists yet.
+    pub fn example() -> Self {
+        Self {
+            crate_result: "error".into(),
+            run_result: "error".into(),
+            select: None,
+            builtin_rules: BUILTIN_RULE_GROUPS
+                .iter()
+                .map(|(name, _)| name.to_string())
+                .collect(),
+            targets: HashMap::new(),
+            extractors: Vec::new(),
+            warnings: None,
+            sections: BTreeMap::new(),
+            refinements: BTreeMap::new(),
+            owners: BTreeMap::new(),
+            redact_patterns: Vec::new(),
+            spurious_categories: BTreeSet::new(),
+            blacklist_categories: Vec::new(),
+            badges: false,
+            crate_tags_file: String::new(),
+            missing_runs: MissingRunHandling::default(),
+            yanked: YankedHandling::default(),
+            regenerated_reports: RegeneratedReportHandling::default(),
+            existing_analyses: ExistingAnalysisHandling::default(),
+            experiment_configs: BTreeMap::new(),
+            config_dir: String::new(),
+            modes: BTreeMap::new(),
+            extract_clippy_lints: false,
+            log_memory_budget_mib: default_log_memory_budget_mib(),
+            max_line_length: default_max_line_length(),
+            prune_logs: false,
+            reclassify_blocked: false,
+            relaxed_diagnostics: false,
+            findings_stream: false,
+            check_for_updates: false,
+            crater_api_url: default_crater_api_url(),
+            max_experiment_age_days: default_max_experiment_age_days(),
+            missing_log_expiry_days: default_missing_log_expiry_days(),
+            progress_interval_secs: default_progress_interval_secs(),
+            thousands_separator: String::new(),
+            report_path: default_report_path(),
+            max_examples: default_max_examples(),
+            max_examples_per_category: BTreeMap::new(),
+            html_page_size: default_html_page_size(),
+            share_logs: default_share_logs(),
+            compressed_logs: Vec::new(),
+            top_crates: 0,
+            zulip: None,
+            external_classifiers: Vec::new(),
+            external_classifier_concurrency: default_external_classifier_concurrency(),
+            hooks: BTreeMap::new(),
+        }
+    }
+
+    /// The config of one of the [`CONFIG_PROFILES`], `None` for unknown profiles.
+    pub fn profile(name: &str) -> Option<Self> {
+        let spurious = || {
+            ["no-space", "download"]
+                .into_iter()
+                .map(str::to_string)
+                .collect::<BTreeSet<_>>()
+        };
+        match name {
+            "example" => Some(Self::example()),
+            "beta-regression" => Some(Self {
+                crate_result: "regressed".into(),
+                run_result: "build-fail:compiler-error".into(),
+                select: Some(
+                    SelectExpr::parse(
+                        r#"crate.res == "regressed" && (run.res.starts_with("build-fail") || run.res.starts_with("test-fail"))"#,
+                    )
+                    .unwrap(),
+                ),
+                spurious_categories: spurious(),
+                blacklist_categories: vec!["no-space".to_string()],
+                ..Self::example()
+            }),
+            "prepare-fail" => Some(Self {
+                crate_result: "prepare-fail".into(),
+                run_result: "prepare-fail:unknown".into(),
+                builtin_rules: vec!["general".to_string()],
+                spurious_categories: spurious(),
+                blacklist_categories: spurious().into_iter().collect(),
+                ..Self::example()
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// What a category looks for in the lines of a log.
+///
+/// Deserializing fails for targets without any of `all`, `any` and `regex`, which would match every line,
+/// e.g. as their patterns were given under a misspelled key.
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
+#[serde(remote = "Self")]
+pub struct Target {
+    /// Texts that must all occur in the same line
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub all: Vec<String>,
+    /// Texts of which at least one must occur in the same line as well, unless empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub any: Vec<String>,
+    /// Texts that must not occur in any line of the log, e.g. to leave build script failures
+    /// caused by a full disk to the category of the latter
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub none: Vec<String>,
+    /// A regular expression that must match the same line as well, e.g. `no output for \d+ seconds`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub regex: Option<TargetRegex>,
+    /// What the category means and what to do about it, shown in Markdown and HTML reports
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Whether ASCII letters match regardless of their case
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub case_insensitive: bool,
+    /// Whether each run of whitespace in the patterns and the log matches as a single space
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub collapse_whitespace: bool,
+    /// Lines before the matched line shown in the category's excerpts, e.g. 0 for one-line manifest errors,
+    /// [`EXCERPT_CONTEXT_LINES`](super::EXCERPT_CONTEXT_LINES) by default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_before: Option<usize>,
+    /// Lines after the matched line shown in the category's excerpts, e.g. 40 to include the query stack of ICEs,
+    /// [`EXCERPT_CONTEXT_LINES`](super::EXCERPT_CONTEXT_LINES) by default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_after: Option<usize>,
+}
+
+impl<'de> serde::Deserialize<'de> for Target {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let target = Target::deserialize(deserializer)?;
+        if target.all.is_empty() && target.any.is_empty() && target.regex.is_none() {
+            return Err(serde::de::Error::custom(
+                "target without `all`, `any` or `regex` patterns, it would match every line",
+            ));
+        }
+        Ok(target)
+    }
+}
+
+impl serde::Serialize for Target {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Target::serialize(self, serializer)
+    }
+}
+
+impl Target {
+    /// How the patterns and the log are normalized before matching, `None` if they are matched exactly.
+    pub fn relaxation(&self) -> Option<Relaxation> {
+        Relaxation::from_flags(self.collapse_whitespace, self.case_insensitive)
+    }
+}
+
+/// The regular expression of a [`Target`], compiled once when the config is loaded and (de)serialized from/to its source.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TargetRegex {
+    source: String,
+    regex: regex::bytes::Regex,
+    /// For [`Target::case_insensitive`] and case-insensitive [relaxed matching](Relaxation)
+    case_insensitive: regex::bytes::Regex,
+}
+
+impl TargetRegex {
+    pub fn new(source: &str) -> Result<Self, regex::Error> {
+        let build = |case_insensitive| {
+            regex::bytes::RegexBuilder::new(source)
+                .case_insensitive(case_insensitive)
+                .build()
+        };
+        Ok(Self {
+            source: source.to_string(),
+            regex: build(false)?,
+            case_insensitive: build(true)?,
+        })
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub(super) fn is_match(&self, line: &[u8], case_insensitive: bool) -> bool {
+        if case_insensitive {
+            self.case_insensitive.is_match(line)
+        } else {
+            self.regex.is_match(line)
+        }
+    }
+}
+
+impl TryFrom<String> for TargetRegex {
+    type Error = regex::Error;
+
+    fn try_from(source: String) -> Result<Self, Self::Error> {
+        Self::new(&source)
+    }
+}
+
+impl From<TargetRegex> for String {
+    fn from(regex: TargetRegex) -> Self {
+        regex.source
+    }
+}
+
+impl std::fmt::Display for TargetRegex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// Whether `text` matches `pattern` in full, where `*` matches any text and `?` a single character.
+pub(super) fn glob_matches(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let text = text.chars().collect::<Vec<_>>();
+
+    // the positions after the last `*` and the text it matched up to, to backtrack to
+    let mut star = None;
+    let (mut p, mut t) = (0, 0);
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p + 1, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((after_star, matched)) => {
+                    p = after_star;
+                    t = matched + 1;
+                    star = Some((after_star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_matches_literals_in_full() {
+        assert!(glob_matches("pr-123", "pr-123"));
+        assert!(!glob_matches("pr-123", "pr-1234"));
+        assert!(!glob_matches("pr-1234", "pr-123"));
+        assert!(glob_matches("", ""));
+        assert!(!glob_matches("", "a"));
+    }
+
+    #[test]
+    fn glob_matches_wildcards() {
+        assert!(glob_matches("pr-*", "pr-123"));
+        assert!(glob_matches("pr-*", "pr-"));
+        assert!(glob_matches("*-beta-*", "pr-1-beta-2"));
+        assert!(glob_matches("pr-???", "pr-123"));
+        assert!(!glob_matches("pr-???", "pr-12"));
+        assert!(glob_matches("*", ""));
+        assert!(glob_matches("**", "anything"));
+        assert!(!glob_matches("?", ""));
+    }
+
+    #[test]
+    fn glob_matches_backtracks_after_stars() {
+        assert!(glob_matches("*ab", "aab"));
+        assert!(glob_matches("a*b*c", "abbbcbc"));
+        assert!(!glob_matches("a*b*c", "abbbcb"));
+        assert!(glob_matches("*é?", "caféé!"));
+    }
+
+    #[test]
+    fn targets_without_patterns_are_rejected() {
+        let err = toml::from_str::<HashMap<String, Vec<Target>>>(
+            r#"typo = [{ al = ["error"], description = "misspelled" }]"#,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("would match every line"));
+        assert!(
+            toml::from_str::<HashMap<String, Vec<Target>>>(r#"ok = [{ any = ["error"] }]"#).is_ok()
+        );
+    }
+}
@@ -0,0 +1,117 @@
+//! Cross-tabulation of our categories against crater's own result of each crate,
+//! to find crates crater likely judged wrong, e.g. regressions that are actually spurious.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+use super::{AnalysisReport, UNCLASSIFIED};
+
+/// Prefix of crater's crate results for failures it considers spurious, e.g. `spurious-regressed`.
+const CRATER_SPURIOUS_PREFIX: &str = "spurious-";
+
+/// The number of crates per crater crate result and category, and the crates where both disagree.
+#[derive(Debug, serde::Serialize)]
+pub struct CrateResultCrossTab {
+    pub experiment: String,
+    /// crater crate result -> category -> number of crates
+    pub counts: BTreeMap<String, BTreeMap<String, usize>>,
+    /// Crates crater and the analysis disagree on, sorted by name
+    pub disagreements: Vec<Disagreement>,
+}
+
+/// A crate whose crater result contradicts its categories.
+#[derive(Debug, serde::Serialize)]
+pub struct Disagreement {
+    pub krate: String,
+    pub crater_result: String,
+    pub categories: BTreeSet<String>,
+    /// Whether crater considers the failure spurious, while the categories don't
+    pub crater_spurious: bool,
+}
+
+impl CrateResultCrossTab {
+    /// Crates are in disagreement when crater considers them spurious but they have a non-spurious category,
+    /// or when crater doesn't but all their categories are spurious. Unclassified crates never are.
+    pub fn new(report: &AnalysisReport, spurious_categories: &BTreeSet<String>) -> Self {
+        let mut counts = BTreeMap::<String, BTreeMap<String, usize>>::new();
+        let mut disagreements = Vec::new();
+
+        for (krate, categories) in report.crate_categories() {
+            let crater_result = report
+                .crater_results
+                .get(&krate)
+                .cloned()
+                .unwrap_or_else(|| report.expected_krate_result.clone());
+
+            let row = counts.entry(crater_result.clone()).or_default();
+            for category in &categories {
+                *row.entry(category.clone()).or_default() += 1;
+            }
+
+            let classified = categories
+                .iter()
+                .filter(|category| *category != UNCLASSIFIED)
+                .collect::<Vec<_>>();
+            if classified.is_empty() {
+                continue;
+            }
+            let crater_spurious = crater_result.starts_with(CRATER_SPURIOUS_PREFIX);
+            let all_spurious = classified
+                .iter()
+                .all(|category| spurious_categories.contains(*category));
+            if crater_spurious != all_spurious {
+                disagreements.push(Disagreement {
+                    krate,
+                    crater_result,
+                    categories,
+                    crater_spurious,
+                });
+            }
+        }
+
+        Self {
+            experiment: report.experiment.clone(),
+            counts,
+            disagreements,
+        }
+    }
+}
+
+/// The plain text cross table
+impl fmt::Display for CrateResultCrossTab {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Crater results of {} by category", self.experiment)?;
+        writeln!(f, "----------------------------------")?;
+        for (crater_result, categories) in &self.counts {
+            writeln!(f, "{crater_result}:")?;
+            let mut categories = categories.iter().collect::<Vec<_>>();
+            categories.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+            for (category, count) in categories {
+                writeln!(f, "  {category}: {count}")?;
+            }
+        }
+        writeln!(f, "----------------------------------")?;
+        writeln!(f, "Disagreements ({}):", self.disagreements.len())?;
+        for disagreement in &self.disagreements {
+            let categories = disagreement
+                .categories
+                .iter()
+                .map(String::as_str)
+                .collect::<Vec<_>>()
+                .join(", ");
+            let verdict = if disagreement.crater_spurious {
+                "not spurious"
+            } else {
+                "spurious"
+            };
+            writeln!(
+                f,
+                "{}: crater says {}, classified as {verdict} ({categories})",
+                disagreement.krate, disagreement.crater_result
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,144 @@
+//! Comparison of the crates of two analyzed experiments, e.g. successive try builds.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+use super::{AnalysisReport, percent};
+
+/// The crates selected in either of two experiments and their categories in each.
+#[derive(Debug, serde::Serialize)]
+pub struct ExperimentDiff {
+    pub old: String,
+    pub new: String,
+    /// Crates selected in both experiments, with their categories in the old and the new one
+    pub persistent: BTreeMap<String, (BTreeSet<String>, BTreeSet<String>)>,
+    /// Crates only selected in the old experiment
+    pub only_old: BTreeMap<String, BTreeSet<String>>,
+    /// Crates only selected in the new experiment
+    pub only_new: BTreeMap<String, BTreeSet<String>>,
+    /// The interesting runs of the old experiment, which its category counts are relative to
+    pub old_runs: usize,
+    /// The interesting runs of the new experiment, which its category counts are relative to
+    pub new_runs: usize,
+    /// The runs per category found in either experiment relative to the experiment's interesting runs,
+    /// so that experiments of different sizes, e.g. a try run and a full beta run, can be compared
+    pub categories: BTreeMap<String, CategoryShare>,
+}
+
+/// How many of the interesting runs of the old and the new experiment a category matched.
+#[derive(Debug, serde::Serialize)]
+pub struct CategoryShare {
+    pub old_count: usize,
+    pub new_count: usize,
+    pub old_percent: f64,
+    pub new_percent: f64,
+    /// `new_percent - old_percent` in percentage points
+    pub delta_points: f64,
+}
+
+impl ExperimentDiff {
+    pub fn new(old: &AnalysisReport, new: &AnalysisReport) -> Self {
+        let mut old_crates = old.crate_categories();
+        let mut new_crates = new.crate_categories();
+
+        let mut persistent = BTreeMap::new();
+        old_crates.retain(|krate, old_categories| match new_crates.remove(krate) {
+            Some(new_categories) => {
+                persistent.insert(
+                    krate.clone(),
+                    (std::mem::take(old_categories), new_categories),
+                );
+                false
+            }
+            None => true,
+        });
+
+        let categories = old
+            .findings
+            .keys()
+            .chain(new.findings.keys())
+            .map(|category| {
+                let old_count = old.findings.get(category).copied().unwrap_or_default();
+                let new_count = new.findings.get(category).copied().unwrap_or_default();
+                let old_percent = percent(old_count, old.interesting_results_count);
+                let new_percent = percent(new_count, new.interesting_results_count);
+                let share = CategoryShare {
+                    old_count,
+                    new_count,
+                    old_percent,
+                    new_percent,
+                    delta_points: new_percent - old_percent,
+                };
+                (category.clone(), share)
+            })
+            .collect();
+
+        Self {
+            old: old.experiment.clone(),
+            new: new.experiment.clone(),
+            persistent,
+            only_old: old_crates,
+            only_new: new_crates,
+            old_runs: old.interesting_results_count,
+            new_runs: new.interesting_results_count,
+            categories,
+        }
+    }
+}
+
+fn join(categories: &BTreeSet<String>) -> String {
+    categories
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The plain text diff
+impl fmt::Display for ExperimentDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Diff of {} and {}", self.old, self.new)?;
+        writeln!(f, "----------------------------------")?;
+        writeln!(
+            f,
+            "Share of the interesting runs ({} in {}, {} in {}), largest change first:",
+            self.old_runs, self.old, self.new_runs, self.new
+        )?;
+        let mut categories = self.categories.iter().collect::<Vec<_>>();
+        // stable, so categories with the same change stay ordered by name
+        categories.sort_by(|(_, a), (_, b)| b.delta_points.abs().total_cmp(&a.delta_points.abs()));
+        for (category, share) in categories {
+            writeln!(
+                f,
+                "{category}: {:.2}% -> {:.2}% ({:+.2} pp; {} -> {} runs)",
+                share.old_percent,
+                share.new_percent,
+                share.delta_points,
+                share.old_count,
+                share.new_count
+            )?;
+        }
+        writeln!(f, "----------------------------------")?;
+        writeln!(f, "In both ({}):", self.persistent.len())?;
+        for (krate, (old, new)) in &self.persistent {
+            if old == new {
+                writeln!(f, "{krate}: {}", join(old))?;
+            } else {
+                writeln!(f, "{krate}: {} -> {}", join(old), join(new))?;
+            }
+        }
+        writeln!(f, "----------------------------------")?;
+        writeln!(f, "Only in {} ({}):", self.old, self.only_old.len())?;
+        for (krate, categories) in &self.only_old {
+            writeln!(f, "{krate}: {}", join(categories))?;
+        }
+        writeln!(f, "----------------------------------")?;
+        writeln!(f, "Only in {} ({}):", self.new, self.only_new.len())?;
+        for (krate, categories) in &self.only_new {
+            writeln!(f, "{krate}: {}", join(categories))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,135 @@
+use super::{LogFindings, Redactor};
+
+/// Lines of context included before and after the matched line of an excerpt, unless its rules say otherwise.
+pub const EXCERPT_CONTEXT_LINES: usize = 5;
+
+/// Lines of context captured before and after the matched line of an excerpt,
+/// see [`Config::excerpt_context`](super::Config::excerpt_context).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExcerptContext {
+    pub before: usize,
+    pub after: usize,
+}
+
+impl Default for ExcerptContext {
+    fn default() -> Self {
+        Self {
+            before: EXCERPT_CONTEXT_LINES,
+            after: EXCERPT_CONTEXT_LINES,
+        }
+    }
+}
+
+/// A few lines of a log around a matched line.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct Excerpt {
+    /// 1-based line number of the first line in `lines`
+    pub first_line_number: usize,
+    /// 1-based line number of the matched line
+    pub matched_line_number: usize,
+    pub lines: Vec<String>,
+}
+
+impl Excerpt {
+    /// Extracts the lines around `line_number` (1-based) from `log`.
+    pub fn extract(log: &[u8], line_number: usize, context: ExcerptContext) -> Self {
+        let first_line_number = line_number.saturating_sub(context.before).max(1);
+        // a trailing line break doesn't start another line
+        let log = log.strip_suffix(b"\n").unwrap_or(log);
+        let lines = log
+            .split(|&c| c == b'\n')
+            .skip(first_line_number - 1)
+            .take(line_number + context.after + 1 - first_line_number)
+            .map(|line| {
+                String::from_utf8_lossy(line)
+                    .trim_end_matches('\r')
+                    .to_string()
+            })
+            .collect();
+
+        Self {
+            first_line_number,
+            matched_line_number: line_number,
+            lines,
+        }
+    }
+
+    /// The matched line itself.
+    pub fn matched_line(&self) -> Option<&str> {
+        self.lines
+            .get(self.matched_line_number - self.first_line_number)
+            .map(String::as_str)
+    }
+
+    /// Redacts credentials from the lines, so that reports can be shared safely.
+    pub fn redact(&mut self, redactor: &Redactor) {
+        for line in &mut self.lines {
+            *line = redactor.redact(line);
+        }
+    }
+}
+
+/// Blanks all lines of `log` except the matched lines of `findings` and the `context` of their category around them,
+/// as well as the first and last line, whose timestamps give the [build duration](super::log_duration).
+///
+/// The line breaks are kept, so that the remaining lines keep their line numbers
+/// and reclassifying the pruned log yields the same findings and excerpts.
+pub fn prune_log(
+    log: &[u8],
+    findings: &LogFindings,
+    context: impl Fn(&str) -> ExcerptContext,
+) -> Vec<u8> {
+    let mut kept = findings
+        .iter()
+        .map(|(name, line_match)| {
+            let context = context(name);
+            line_match.line_number.saturating_sub(context.before).max(1)
+                ..=line_match.line_number + context.after
+        })
+        .collect::<Vec<_>>();
+    let lines = log.split(|&c| c == b'\n');
+    let line_count = lines.clone().count();
+    if let Some(from_end) = lines.clone().rev().position(|line| !line.is_empty()) {
+        let last = line_count - 1 - from_end;
+        let first = lines
+            .clone()
+            .position(|line| !line.is_empty())
+            .unwrap_or(last);
+        kept.extend([first + 1..=first + 1, last + 1..=last + 1]);
+    }
+
+    let mut pruned = Vec::new();
+    for (index, line) in lines.enumerate() {
+        if index > 0 {
+            pruned.push(b'\n');
+        }
+        if kept.iter().any(|range| range.contains(&(index + 1))) {
+            pruned.extend_from_slice(line);
+        }
+    }
+    pruned
+}
+
+/// The example shown for a category, see [`AnalysisReport::samples`](super::AnalysisReport::samples).
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct Sample {
+    pub krate: String,
+    /// The [stable ID](super::run_id) of the run the excerpt is taken from
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub run_id: String,
+    pub log: String,
+    pub excerpt: Excerpt,
+}
+
+/// Where a category matched in a run, so that tools can jump to the evidence without scanning the log again.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct FindingMatch {
+    /// The [stable ID](super::run_id) of the run
+    pub run_id: String,
+    pub krate: String,
+    pub log: String,
+    /// 1-based number of the first matching line
+    pub line_number: usize,
+    /// The matching line, with credentials redacted
+    pub line: String,
+}
@@ -0,0 +1,155 @@
+//! Expected findings of an experiment checked by the `assert` command, see [`check_expectations`].
+//!
+//! ```toml
+//! [categories]
+//! ice = 0                     # exactly
+//! E0277 = { max = 3 }         # at most
+//! "clippy::*" = { min = 1 }   # at least, summed over all matching categories
+//!
+//! unclassified = { max = 10 }
+//! ```
+
+use std::{collections::BTreeMap, fmt};
+
+use super::{AnalysisReport, config::glob_matches};
+
+/// What an experiment's analysis is expected to have found.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Expectations {
+    /// The expected runs per category pattern, `*` matches any text and `?` a single character;
+    /// the runs of all matching categories are summed
+    #[serde(default)]
+    pub categories: BTreeMap<String, CountRange>,
+    /// The expected runs none of the categories matched
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unclassified: Option<CountRange>,
+}
+
+/// An expected number of runs, either exactly or within bounds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+pub enum CountRange {
+    Exactly(usize),
+    Within(Bounds),
+}
+
+/// Inclusive bounds of a [`CountRange`], unknown keys are rejected so that typos don't pass as no bound.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Bounds {
+    #[serde(default)]
+    pub min: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<usize>,
+}
+
+impl CountRange {
+    pub fn contains(self, count: usize) -> bool {
+        match self {
+            Self::Exactly(expected) => count == expected,
+            Self::Within(Bounds { min, max }) => count >= min && max.is_none_or(|max| count <= max),
+        }
+    }
+}
+
+impl fmt::Display for CountRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Exactly(expected) => write!(f, "exactly {expected}"),
+            Self::Within(Bounds {
+                min,
+                max: Some(max),
+            }) if min == max => {
+                write!(f, "exactly {min}")
+            }
+            Self::Within(Bounds { min: 0, max: None }) => f.write_str("any number"),
+            Self::Within(Bounds { min, max: None }) => write!(f, "at least {min}"),
+            Self::Within(Bounds {
+                min: 0,
+                max: Some(max),
+            }) => write!(f, "at most {max}"),
+            Self::Within(Bounds {
+                min,
+                max: Some(max),
+            }) => write!(f, "{min} to {max}"),
+        }
+    }
+}
+
+/// The outcome of a single expectation.
+#[derive(Debug, Clone)]
+pub struct ExpectationResult {
+    /// The category pattern, or `unclassified`
+    pub name: String,
+    pub expected: CountRange,
+    pub found: usize,
+    /// The found categories counted for a pattern, with their runs
+    pub categories: Vec<(String, usize)>,
+}
+
+impl ExpectationResult {
+    pub fn holds(&self) -> bool {
+        self.expected.contains(self.found)
+    }
+}
+
+impl fmt::Display for ExpectationResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: expected {}, found {}",
+            self.name, self.expected, self.found
+        )?;
+        // patterns matching other categories than themselves tell which ones were counted
+        if self.categories.len() > 1
+            || self
+                .categories
+                .first()
+                .is_some_and(|(category, _)| *category != self.name)
+        {
+            let counted = self
+                .categories
+                .iter()
+                .map(|(category, count)| format!("{category}: {count}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            write!(f, " ({counted})")?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks each of the `expectations` against the findings of `report`, in the order of their patterns.
+pub fn check_expectations(
+    report: &AnalysisReport,
+    expectations: &Expectations,
+) -> Vec<ExpectationResult> {
+    let mut results = expectations
+        .categories
+        .iter()
+        .map(|(pattern, &expected)| {
+            let categories = report
+                .findings
+                .iter()
+                .filter(|(category, _)| glob_matches(pattern, category))
+                .map(|(category, &count)| (category.clone(), count))
+                .collect::<Vec<_>>();
+            ExpectationResult {
+                name: pattern.clone(),
+                expected,
+                found: categories.iter().map(|(_, count)| count).sum(),
+                categories,
+            }
+        })
+        .collect::<Vec<_>>();
+    if let Some(expected) = expectations.unclassified {
+        results.push(ExpectationResult {
+            name: "unclassified".to_string(),
+            expected,
+            found: report.unclassified_count(),
+            categories: Vec::new(),
+        });
+    }
+    results
+}
@@ -0,0 +1,203 @@
+//! What makes up a category, printed by the `explain` command, see [`explain_category`].
+
+use std::fmt;
+
+use super::{
+    BLOCKED_ON, BUILTIN_RULE_GROUPS, Config, ExcerptContext, Extractor, PROC_MACRO_PANICKED, Target,
+};
+
+/// The rules and extractors a category comes from.
+#[derive(Debug)]
+pub struct CategoryExplanation<'a> {
+    pub category: String,
+    pub description: Option<&'a str>,
+    /// The targets defining the category by where they are configured, e.g. `targets` or `refinements.ice.targets`
+    pub rules: Vec<(String, &'a [Target])>,
+    /// The enabled built-in rule groups with rules for the category, unless the config defines it itself
+    pub builtin_groups: Vec<&'static str>,
+    /// What derives the category from the logs without a configured rule, e.g. the rustc error codes
+    pub extractor: Option<&'static str>,
+    /// The [configured extractors](Config::extractors) that may produce the category
+    pub extractors: Vec<&'a Extractor>,
+    /// The categories `--refine-category` splits the category into
+    pub refined_into: Vec<&'a str>,
+    /// Whether external classifiers are configured, which may report any category
+    pub external_classifiers: bool,
+    /// The lines shown around the category's matches in excerpts
+    pub excerpt_context: ExcerptContext,
+}
+
+impl CategoryExplanation<'_> {
+    /// Whether anything in the config produces the category, other than external classifiers.
+    pub fn is_known(&self) -> bool {
+        !self.rules.is_empty() || self.extractor.is_some() || !self.extractors.is_empty()
+    }
+}
+
+/// Explains where `category` comes from according to `config`, with its built-in rules already added.
+pub fn explain_category<'a>(config: &'a Config, category: &str) -> CategoryExplanation<'a> {
+    let mut rules = Vec::new();
+    if let Some(targets) = config.targets.get(category) {
+        rules.push(("targets".to_string(), targets.as_slice()));
+    }
+    if let Some(targets) = config
+        .warnings
+        .as_ref()
+        .and_then(|warnings| warnings.targets.get(category))
+    {
+        rules.push(("warnings.targets".to_string(), targets.as_slice()));
+    }
+    for (refined, refinement) in &config.refinements {
+        if let Some(targets) = refinement.targets.get(category) {
+            rules.push((format!("refinements.{refined}.targets"), targets.as_slice()));
+        }
+    }
+
+    let builtin_groups = BUILTIN_RULE_GROUPS
+        .iter()
+        .filter(|(name, _)| config.builtin_rules.iter().any(|enabled| enabled == name))
+        .filter(|(_, rules)| rules.iter().any(|(name, _)| *name == category))
+        .map(|(name, _)| *name)
+        .collect();
+
+    let extractors = config
+        .extractors
+        .iter()
+        .filter(|extractor| extractor.may_produce(category))
+        .collect::<Vec<_>>();
+    let description = rules
+        .iter()
+        .flat_map(|(_, targets)| targets.iter())
+        .find_map(|target| target.description.as_deref())
+        .or_else(|| {
+            extractors
+                .iter()
+                .find_map(|extractor| extractor.description.as_deref())
+        });
+    let mut refined_into = config
+        .refinements
+        .get(category)
+        .map(|refinement| {
+            refinement
+                .targets
+                .keys()
+                .map(String::as_str)
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    refined_into.sort_unstable();
+
+    CategoryExplanation {
+        category: category.to_string(),
+        description,
+        rules,
+        builtin_groups,
+        extractor: category_extractor(config, category),
+        extractors,
+        refined_into,
+        external_classifiers: !config.external_classifiers.is_empty(),
+        excerpt_context: config.excerpt_context(category),
+    }
+}
+
+/// The extractor deriving `category` from the logs, if it is enabled.
+fn category_extractor(config: &Config, category: &str) -> Option<&'static str> {
+    if let Some(code) = category.strip_prefix('E')
+        && !code.is_empty()
+        && code.bytes().all(|c| c.is_ascii_digit())
+    {
+        Some("rustc error codes, from lines like `[INFO] [stdout] error[E0308]:`")
+    } else if category.starts_with("clippy::") && config.extract_clippy_lints {
+        Some("denied clippy lints, from notes like ``note: `#[deny(clippy::…)]` ``")
+    } else if category.starts_with(PROC_MACRO_PANICKED) {
+        Some("panicking proc macros, from their panic messages")
+    } else if category.starts_with(BLOCKED_ON) && config.reclassify_blocked {
+        Some("crates failing only because a dependency failed to build (`reclassify_blocked`)")
+    } else {
+        None
+    }
+}
+
+impl fmt::Display for CategoryExplanation<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.category)?;
+        if let Some(description) = self.description {
+            writeln!(f, "  {description}")?;
+        }
+        for (source, targets) in &self.rules {
+            writeln!(
+                f,
+                "  matched by {source} if a log contains all patterns of any of:"
+            )?;
+            for target in *targets {
+                let patterns = target
+                    .all
+                    .iter()
+                    .map(|pattern| format!("\"{}\"", pattern.escape_default()))
+                    .chain(
+                        (!target.any.is_empty())
+                            .then(|| format!("one of ({})", quoted_alternatives(&target.any))),
+                    )
+                    .chain(target.regex.iter().map(|regex| format!("/{regex}/")))
+                    .chain((!target.none.is_empty()).then(|| {
+                        format!(
+                            "unless the log contains any of ({})",
+                            quoted_alternatives(&target.none)
+                        )
+                    }))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                match target.relaxation() {
+                    Some(relaxation) => writeln!(f, "    - ({relaxation}) {patterns}")?,
+                    None => writeln!(f, "    - {patterns}")?,
+                }
+            }
+        }
+        if self.excerpt_context != ExcerptContext::default() {
+            writeln!(
+                f,
+                "  excerpts show {} line(s) before and {} after the match",
+                self.excerpt_context.before, self.excerpt_context.after
+            )?;
+        }
+        if !self.builtin_groups.is_empty() {
+            writeln!(
+                f,
+                "  built-in rule group(s): {}",
+                self.builtin_groups.join(", ")
+            )?;
+        }
+        if let Some(extractor) = self.extractor {
+            writeln!(f, "  extracted from {extractor}")?;
+        }
+        for extractor in &self.extractors {
+            writeln!(
+                f,
+                "  extracted as \"{}\" by \"{}\" in windows of {} lines",
+                extractor.category.escape_default(),
+                extractor.pattern.source().escape_default(),
+                extractor.window
+            )?;
+        }
+        if !self.refined_into.is_empty() {
+            writeln!(
+                f,
+                "  refined by --refine-category into: {}",
+                self.refined_into.join(", ")
+            )?;
+        }
+        if self.external_classifiers {
+            writeln!(f, "  external classifiers may report it as well")?;
+        }
+        Ok(())
+    }
+}
+
+/// The patterns quoted and joined by `or`, e.g. `"a" or "b"`.
+fn quoted_alternatives(patterns: &[String]) -> String {
+    patterns
+        .iter()
+        .map(|pattern| format!("\"{}\"", pattern.escape_default()))
+        .collect::<Vec<_>>()
+        .join(" or ")
+}
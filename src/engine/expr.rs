@@ -0,0 +1,566 @@
+//! A small expression language selecting the runs to analyze, e.g.
+//!
+//! ```text
+//! crate.res == "regressed" && run.res in ["build-fail", "test-fail"] && !crate.name.starts_with("gh/")
+//! ```
+//!
+//! Supported are the fields `crate.name`, `crate.res`, `crate.url`, `run.res` and `run.log`,
+//! string literals, `==`, `!=`, `in [..]`, the string methods `starts_with`, `ends_with`
+//! and `contains`, `!`, `&&`, `||`, `true`, `false` and parentheses.
+
+use std::fmt;
+
+use super::{CrateResult, RunResult};
+
+/// A parsed selection expression, (de)serialized from/to its source.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct SelectExpr {
+    source: String,
+    expr: Expr,
+}
+
+impl SelectExpr {
+    pub fn parse(source: &str) -> Result<Self, ParseError> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+            end: source.len(),
+        };
+        let expr = parser.parse_or()?;
+        if let Some((position, token)) = parser.tokens.get(parser.position) {
+            return Err(ParseError {
+                message: format!("unexpected {token}"),
+                position: *position,
+            });
+        }
+        Ok(Self {
+            source: source.to_string(),
+            expr,
+        })
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Whether `run` of `krate` is selected.
+    pub fn matches(&self, krate: &CrateResult, run: &RunResult) -> bool {
+        self.expr.eval(krate, run)
+    }
+}
+
+impl TryFrom<String> for SelectExpr {
+    type Error = ParseError;
+
+    fn try_from(source: String) -> Result<Self, Self::Error> {
+        Self::parse(&source)
+    }
+}
+
+impl From<SelectExpr> for String {
+    fn from(expr: SelectExpr) -> Self {
+        expr.source
+    }
+}
+
+impl fmt::Display for SelectExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+#[error("invalid selection expression: {message} at position {position}")]
+pub struct ParseError {
+    message: String,
+    /// byte offset into the source
+    position: usize,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Bool(bool),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Eq(Operand, Operand),
+    Ne(Operand, Operand),
+    In(Operand, Vec<String>),
+    Method(Operand, Method, String),
+}
+
+impl Expr {
+    fn eval(&self, krate: &CrateResult, run: &RunResult) -> bool {
+        match self {
+            Expr::Bool(value) => *value,
+            Expr::Not(inner) => !inner.eval(krate, run),
+            Expr::And(lhs, rhs) => lhs.eval(krate, run) && rhs.eval(krate, run),
+            Expr::Or(lhs, rhs) => lhs.eval(krate, run) || rhs.eval(krate, run),
+            Expr::Eq(lhs, rhs) => lhs.value(krate, run) == rhs.value(krate, run),
+            Expr::Ne(lhs, rhs) => lhs.value(krate, run) != rhs.value(krate, run),
+            Expr::In(value, list) => {
+                let value = value.value(krate, run);
+                list.iter().any(|item| item == value)
+            }
+            Expr::Method(value, method, arg) => {
+                let value = value.value(krate, run);
+                match method {
+                    Method::StartsWith => value.starts_with(arg.as_str()),
+                    Method::EndsWith => value.ends_with(arg.as_str()),
+                    Method::Contains => value.contains(arg.as_str()),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Operand {
+    Literal(String),
+    CrateName,
+    CrateRes,
+    CrateUrl,
+    RunRes,
+    RunLog,
+}
+
+impl Operand {
+    fn value<'a>(&'a self, krate: &'a CrateResult, run: &'a RunResult) -> &'a str {
+        match self {
+            Operand::Literal(value) => value,
+            Operand::CrateName => &krate.name,
+            Operand::CrateRes => &krate.res,
+            Operand::CrateUrl => krate.url.as_deref().unwrap_or(""),
+            Operand::RunRes => &run.res,
+            Operand::RunLog => &run.log,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Method {
+    StartsWith,
+    EndsWith,
+    Contains,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Dot,
+    Comma,
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Not,
+    And,
+    Or,
+    Eq,
+    Ne,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Ident(ident) => write!(f, "'{ident}'"),
+            Token::Str(value) => write!(f, "string {value:?}"),
+            Token::Dot => f.write_str("'.'"),
+            Token::Comma => f.write_str("','"),
+            Token::LParen => f.write_str("'('"),
+            Token::RParen => f.write_str("')'"),
+            Token::LBracket => f.write_str("'['"),
+            Token::RBracket => f.write_str("']'"),
+            Token::Not => f.write_str("'!'"),
+            Token::And => f.write_str("'&&'"),
+            Token::Or => f.write_str("'||'"),
+            Token::Eq => f.write_str("'=='"),
+            Token::Ne => f.write_str("'!='"),
+        }
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+
+    while let Some((position, c)) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '.' => Token::Dot,
+            ',' => Token::Comma,
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            '[' => Token::LBracket,
+            ']' => Token::RBracket,
+            '!' if chars.next_if(|(_, c)| *c == '=').is_some() => Token::Ne,
+            '!' => Token::Not,
+            '=' if chars.next_if(|(_, c)| *c == '=').is_some() => Token::Eq,
+            '&' if chars.next_if(|(_, c)| *c == '&').is_some() => Token::And,
+            '|' if chars.next_if(|(_, c)| *c == '|').is_some() => Token::Or,
+            '"' => {
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, c @ ('"' | '\\'))) => value.push(c),
+                            Some((_, 'n')) => value.push('\n'),
+                            Some((_, 't')) => value.push('\t'),
+                            Some((escape, c)) => {
+                                return Err(ParseError {
+                                    message: format!("unknown escape sequence '\\{c}'"),
+                                    position: escape - 1,
+                                });
+                            }
+                            None => {
+                                return Err(ParseError {
+                                    message: "unterminated string".to_string(),
+                                    position,
+                                });
+                            }
+                        },
+                        Some((_, c)) => value.push(c),
+                        None => {
+                            return Err(ParseError {
+                                message: "unterminated string".to_string(),
+                                position,
+                            });
+                        }
+                    }
+                }
+                Token::Str(value)
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut ident = String::from(c);
+                while let Some((_, c)) = chars.next_if(|(_, c)| c.is_alphanumeric() || *c == '_') {
+                    ident.push(c);
+                }
+                Token::Ident(ident)
+            }
+            c => {
+                return Err(ParseError {
+                    message: format!("unexpected character '{c}'"),
+                    position,
+                });
+            }
+        };
+        tokens.push((position, token));
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    position: usize,
+    /// length of the source, for errors at the end of the input
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(_, token)| token)
+    }
+
+    fn next(&mut self) -> Result<(usize, Token), ParseError> {
+        let token = self.tokens.get(self.position).cloned().ok_or(ParseError {
+            message: "unexpected end of expression".to_string(),
+            position: self.end,
+        })?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn eat(&mut self, expected: &Token) -> bool {
+        if self.peek() == Some(expected) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), ParseError> {
+        let (position, token) = self.next()?;
+        if token == expected {
+            Ok(())
+        } else {
+            Err(ParseError {
+                message: format!("expected {expected}, found {token}"),
+                position,
+            })
+        }
+    }
+
+    fn expect_string(&mut self) -> Result<String, ParseError> {
+        match self.next()? {
+            (_, Token::Str(value)) => Ok(value),
+            (position, token) => Err(ParseError {
+                message: format!("expected a string, found {token}"),
+                position,
+            }),
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.parse_and()?;
+        while self.eat(&Token::Or) {
+            expr = Expr::Or(Box::new(expr), Box::new(self.parse_and()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.parse_unary()?;
+        while self.eat(&Token::And) {
+            expr = Expr::And(Box::new(expr), Box::new(self.parse_unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, ParseError> {
+        if self.eat(&Token::Not) {
+            return Ok(Expr::Not(Box::new(self.parse_unary()?)));
+        }
+        if self.eat(&Token::LParen) {
+            let expr = self.parse_or()?;
+            self.expect(Token::RParen)?;
+            return Ok(expr);
+        }
+        if let Some(Token::Ident(ident)) = self.peek() {
+            let value = match ident.as_str() {
+                "true" => Some(true),
+                "false" => Some(false),
+                _ => None,
+            };
+            if let Some(value) = value {
+                self.position += 1;
+                return Ok(Expr::Bool(value));
+            }
+        }
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr, ParseError> {
+        let lhs = self.parse_operand()?;
+
+        let (position, token) = self.next()?;
+        match token {
+            Token::Eq => Ok(Expr::Eq(lhs, self.parse_operand()?)),
+            Token::Ne => Ok(Expr::Ne(lhs, self.parse_operand()?)),
+            Token::Ident(ident) if ident == "in" => {
+                self.expect(Token::LBracket)?;
+                let mut list = Vec::new();
+                while !self.eat(&Token::RBracket) {
+                    list.push(self.expect_string()?);
+                    if !self.eat(&Token::Comma) {
+                        self.expect(Token::RBracket)?;
+                        break;
+                    }
+                }
+                Ok(Expr::In(lhs, list))
+            }
+            Token::Dot => {
+                let method = match self.next()? {
+                    (_, Token::Ident(ident)) if ident == "starts_with" => Method::StartsWith,
+                    (_, Token::Ident(ident)) if ident == "ends_with" => Method::EndsWith,
+                    (_, Token::Ident(ident)) if ident == "contains" => Method::Contains,
+                    (position, token) => {
+                        return Err(ParseError {
+                            message: format!(
+                                "expected one of starts_with, ends_with or contains, found {token}"
+                            ),
+                            position,
+                        });
+                    }
+                };
+                self.expect(Token::LParen)?;
+                let arg = self.expect_string()?;
+                self.expect(Token::RParen)?;
+                Ok(Expr::Method(lhs, method, arg))
+            }
+            token => Err(ParseError {
+                message: format!("expected '==', '!=', 'in' or a method call, found {token}"),
+                position,
+            }),
+        }
+    }
+
+    fn parse_operand(&mut self) -> Result<Operand, ParseError> {
+        match self.next()? {
+            (_, Token::Str(value)) => Ok(Operand::Literal(value)),
+            (position, Token::Ident(object)) => {
+                self.expect(Token::Dot)?;
+                let (_, field) = self.next()?;
+                match (object.as_str(), &field) {
+                    ("crate", Token::Ident(field)) if field == "name" => Ok(Operand::CrateName),
+                    ("crate", Token::Ident(field)) if field == "res" => Ok(Operand::CrateRes),
+                    ("crate", Token::Ident(field)) if field == "url" => Ok(Operand::CrateUrl),
+                    ("run", Token::Ident(field)) if field == "res" => Ok(Operand::RunRes),
+                    ("run", Token::Ident(field)) if field == "log" => Ok(Operand::RunLog),
+                    _ => Err(ParseError {
+                        message: format!(
+                            "unknown field '{object}.{}', expected one of crate.name, crate.res, crate.url, run.res or run.log",
+                            match &field {
+                                Token::Ident(field) => field.clone(),
+                                token => token.to_string(),
+                            }
+                        ),
+                        position,
+                    }),
+                }
+            }
+            (position, token) => Err(ParseError {
+                message: format!("expected a field or a string, found {token}"),
+                position,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(res: &str, log: &str) -> RunResult {
+        RunResult {
+            res: res.to_string(),
+            log: log.to_string(),
+        }
+    }
+
+    fn selects(source: &str, krate: &CrateResult, run: &RunResult) -> bool {
+        SelectExpr::parse(source).unwrap().matches(krate, run)
+    }
+
+    fn error(source: &str) -> (String, usize) {
+        let err = SelectExpr::parse(source).unwrap_err();
+        (err.message, err.position)
+    }
+
+    fn krate() -> CrateResult {
+        CrateResult {
+            name: "gh/foo/bar".to_string(),
+            url: None,
+            res: "regressed".to_string(),
+            runs: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn comparisons_and_lists() {
+        let krate = krate();
+        let run = run("build-fail:compiler-error", "beta/gh/foo/bar");
+        assert!(selects(r#"crate.res == "regressed""#, &krate, &run));
+        assert!(!selects(r#"crate.res != "regressed""#, &krate, &run));
+        assert!(selects(r#""regressed" == crate.res"#, &krate, &run));
+        assert!(selects(
+            r#"run.res in ["test-fail", "build-fail:compiler-error",]"#,
+            &krate,
+            &run
+        ));
+        assert!(!selects(r#"run.res in []"#, &krate, &run));
+        assert!(selects(r#"crate.url == """#, &krate, &run));
+    }
+
+    #[test]
+    fn methods() {
+        let krate = krate();
+        let run = run("test-fail", "beta/gh/foo/bar");
+        assert!(selects(r#"crate.name.starts_with("gh/")"#, &krate, &run));
+        assert!(selects(r#"crate.name.ends_with("/bar")"#, &krate, &run));
+        assert!(selects(r#"run.log.contains("foo")"#, &krate, &run));
+        assert!(!selects(r#"run.log.contains("stable")"#, &krate, &run));
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        let krate = krate();
+        let run = run("test-fail", "");
+        assert!(selects("true || false && false", &krate, &run));
+        assert!(!selects("(true || false) && false", &krate, &run));
+        assert!(selects("!false && !(false || false)", &krate, &run));
+        assert!(!selects("!!false", &krate, &run));
+    }
+
+    #[test]
+    fn string_escapes() {
+        let krate = krate();
+        let run = run("test-fail", "a\"b\\c\n");
+        assert!(selects(r#"run.log == "a\"b\\c\n""#, &krate, &run));
+        assert_eq!(
+            error(r#"run.log == "\x""#),
+            ("unknown escape sequence '\\x'".to_string(), 12)
+        );
+    }
+
+    #[test]
+    fn source_round_trips() {
+        let source = r#"crate.res == "regressed""#;
+        let expr = serde_json::from_value::<SelectExpr>(serde_json::json!(source)).unwrap();
+        assert_eq!(expr.source(), source);
+        assert_eq!(serde_json::to_value(&expr).unwrap(), source);
+    }
+
+    #[test]
+    fn errors_point_at_the_offending_token() {
+        assert_eq!(
+            error(r#"crate.res == "regressed"#),
+            ("unterminated string".to_string(), 13)
+        );
+        assert_eq!(
+            error(r#"crate.res = "regressed""#),
+            ("unexpected character '='".to_string(), 10)
+        );
+        assert_eq!(
+            error(r#"crate.res == "a" "b""#),
+            ("unexpected string \"b\"".to_string(), 17)
+        );
+        assert_eq!(
+            error("crate.res =="),
+            ("unexpected end of expression".to_string(), 12)
+        );
+        assert_eq!(
+            error(r#"crate.version == "1""#),
+            (
+                "unknown field 'crate.version', expected one of crate.name, crate.res, crate.url, run.res or run.log"
+                    .to_string(),
+                0
+            )
+        );
+        assert_eq!(
+            error(r#"crate.name.matches("a")"#),
+            (
+                "expected one of starts_with, ends_with or contains, found 'matches'".to_string(),
+                11
+            )
+        );
+        assert_eq!(
+            error(r#"run.res in ["a" "b"]"#),
+            ("expected ']', found string \"b\"".to_string(), 16)
+        );
+        assert_eq!(
+            error("crate.res"),
+            ("unexpected end of expression".to_string(), 9)
+        );
+        assert_eq!(
+            error(r#"(crate.res == "a""#),
+            ("unexpected end of expression".to_string(), 17)
+        );
+        assert_eq!(
+            error(r#"crate.res < "a""#),
+            ("unexpected character '<'".to_string(), 10)
+        );
+        assert_eq!(
+            error(r#"crate.res true"#),
+            (
+                "expected '==', '!=', 'in' or a method call, found 'true'".to_string(),
+                10
+            )
+        );
+    }
+}
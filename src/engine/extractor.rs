@@ -0,0 +1,143 @@
+//! Categories derived with configured regular expressions spanning several log lines, see [`Extractor`].
+
+use std::fmt;
+
+use regex::{Regex, RegexBuilder};
+
+use super::{LineMatch, LogFindings, cluster::CRATER_PREFIX, config::glob_matches};
+
+/// A regular expression deriving categories from windows of several lines of the logs, e.g. ICEs by their query:
+///
+/// ```toml
+/// [[extractors]]
+/// pattern = 'panicked at .*?query stack during panic:\n#0 \[(?<query>\w+)\]'
+/// category = "ice: ${query}"
+/// window = 50
+/// ```
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Extractor {
+    /// Matched against `window` consecutive lines at a time, with crater's prefixes like `[INFO] [stderr] ` removed
+    /// from each line so that messages interleaved from stdout and stderr read as one text; `.` matches line breaks
+    pub pattern: ExtractorPattern,
+    /// The category of each match, with `$name` and `${name}` replaced by the capture group `name` (or number)
+    pub category: String,
+    /// Lines matched at a time, successive windows overlap by half, so that matches spanning up to half
+    /// the lines are always found
+    #[serde(default = "default_window")]
+    pub window: usize,
+    /// What the categories mean and what to do about them, shown in Markdown and HTML reports
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+fn default_window() -> usize {
+    50
+}
+
+/// The regular expression of an [`Extractor`], (de)serialized from/to its source.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ExtractorPattern {
+    source: String,
+    regex: Regex,
+}
+
+impl ExtractorPattern {
+    pub fn new(source: &str) -> Result<Self, regex::Error> {
+        let regex = RegexBuilder::new(source)
+            .dot_matches_new_line(true)
+            .multi_line(true)
+            .build()?;
+        Ok(Self {
+            source: source.to_string(),
+            regex,
+        })
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+}
+
+impl TryFrom<String> for ExtractorPattern {
+    type Error = regex::Error;
+
+    fn try_from(source: String) -> Result<Self, Self::Error> {
+        Self::new(&source)
+    }
+}
+
+impl From<ExtractorPattern> for String {
+    fn from(pattern: ExtractorPattern) -> Self {
+        pattern.source
+    }
+}
+
+impl fmt::Display for ExtractorPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl Extractor {
+    /// Whether the extractor may produce `category`, taking the capture groups in its category to match any text.
+    pub fn may_produce(&self, category: &str) -> bool {
+        let mut literal = self.category.as_str();
+        let mut pattern = String::new();
+        while let Some(position) = literal.find('$') {
+            pattern.push_str(&literal[..position]);
+            let rest = &literal[position + 1..];
+            let name_length = match rest.strip_prefix('{') {
+                Some(braced) => braced.find('}').map_or(rest.len(), |end| end + 2),
+                None => rest
+                    .find(|c: char| !c.is_alphanumeric() && c != '_')
+                    .unwrap_or(rest.len()),
+            };
+            pattern.push('*');
+            literal = &rest[name_length..];
+        }
+        pattern.push_str(literal);
+        glob_matches(&pattern, category)
+    }
+}
+
+/// Adds the categories of all matches of the `extractors` in `log`, each with the line its first match starts on.
+pub fn extract_windowed(extractors: &[Extractor], log: &[u8], log_findings: &mut LogFindings) {
+    if extractors.is_empty() {
+        return;
+    }
+    let lines = log
+        .split(|&c| c == b'\n')
+        .map(|line| {
+            let line = String::from_utf8_lossy(line);
+            CRATER_PREFIX.replace(line.trim_end(), "").into_owned()
+        })
+        .collect::<Vec<_>>();
+
+    for extractor in extractors {
+        let window = extractor.window.max(1);
+        let step = (window / 2).max(1);
+        let mut start = 0;
+        loop {
+            let end = (start + window).min(lines.len());
+            let text = lines[start..end].join("\n");
+            for captures in extractor.pattern.regex.captures_iter(&text) {
+                let mut category = String::new();
+                captures.expand(&extractor.category, &mut category);
+                if category.is_empty() {
+                    continue;
+                }
+                let matched_at = captures.get(0).map_or(0, |matched| matched.start());
+                let line_number = start + 1 + text[..matched_at].matches('\n').count();
+                log_findings
+                    .entry(category)
+                    .and_modify(|first| first.line_number = first.line_number.min(line_number))
+                    .or_insert(LineMatch { line_number });
+            }
+            if end == lines.len() {
+                break;
+            }
+            start += step;
+        }
+    }
+}
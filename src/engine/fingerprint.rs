@@ -0,0 +1,130 @@
+//! Fingerprints identifying the same failure across experiments, see [`failure_fingerprint`].
+
+use std::collections::BTreeMap;
+
+use super::{AnalysisReport, cluster::normalize_error_line, stable_hash};
+
+/// A failure of one or more crates, identified by its category and normalized matched line.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct Fingerprint {
+    pub category: String,
+    /// The matched line normalized like the [last error line](super::last_error_line)
+    pub error_line: String,
+    /// The crates failing this way, sorted by name
+    pub crates: Vec<String>,
+    /// The [stable IDs](super::run_id) of the runs failing this way, sorted
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub run_ids: Vec<String>,
+}
+
+/// The normalized `line_number` (1-based) of `log` used to fingerprint a finding matched there.
+pub fn fingerprint_line(log: &[u8], line_number: usize) -> String {
+    let line = log
+        .split(|&c| c == b'\n')
+        .nth(line_number.saturating_sub(1))
+        .unwrap_or_default();
+    normalize_error_line(&String::from_utf8_lossy(line))
+}
+
+/// A stable fingerprint of a failure, equal for all crates and experiments
+/// failing with the same category on lines only differing in names, paths and numbers.
+pub fn failure_fingerprint(category: &str, error_line: &str) -> String {
+    format!(
+        "{:016x}",
+        stable_hash(format!("{category}\n{error_line}").as_bytes())
+    )
+}
+
+/// How far a recurring failure has been looked into.
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    serde::Serialize,
+    serde::Deserialize,
+    schemars::JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum TriageStatus {
+    #[default]
+    Untriaged,
+    /// An issue was filed, usually linked in the note
+    Reported,
+    /// The failure is intended, e.g. a deliberate breaking change
+    Expected,
+    Fixed,
+}
+
+impl std::str::FromStr for TriageStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "untriaged" => Ok(Self::Untriaged),
+            "reported" => Ok(Self::Reported),
+            "expected" => Ok(Self::Expected),
+            "fixed" => Ok(Self::Fixed),
+            other => Err(format!(
+                "unknown triage status '{other}', expected untriaged, reported, expected or fixed"
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for TriageStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Untriaged => "untriaged",
+            Self::Reported => "reported",
+            Self::Expected => "expected",
+            Self::Fixed => "fixed",
+        })
+    }
+}
+
+/// A failure seen before, as recorded in the [`FingerprintStore`].
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct KnownFailure {
+    pub category: String,
+    pub error_line: String,
+    /// The experiment the failure was first seen in
+    pub first_seen: String,
+    #[serde(default)]
+    pub status: TriageStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+/// All failures seen in any analyzed experiment by their fingerprint.
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct FingerprintStore {
+    pub failures: BTreeMap<String, KnownFailure>,
+}
+
+impl FingerprintStore {
+    /// Annotates the report with its failures seen in earlier experiments or already triaged,
+    /// and records the new ones as first seen in the report's experiment.
+    pub fn annotate(&mut self, report: &mut AnalysisReport) {
+        report.known_failures.clear();
+        for (fingerprint, failure) in &report.fingerprints {
+            let known = self
+                .failures
+                .entry(fingerprint.clone())
+                .or_insert_with(|| KnownFailure {
+                    category: failure.category.clone(),
+                    error_line: failure.error_line.clone(),
+                    first_seen: report.experiment.clone(),
+                    status: TriageStatus::Untriaged,
+                    note: None,
+                });
+            if known.first_seen != report.experiment || known.status != TriageStatus::Untriaged {
+                report
+                    .known_failures
+                    .insert(fingerprint.clone(), known.clone());
+            }
+        }
+    }
+}
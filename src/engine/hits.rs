@@ -0,0 +1,105 @@
+//! Samples of the lines each category matched across an experiment, to catch rules matching unrelated errors,
+//! see [`rule_hits`].
+
+use std::{collections::BTreeMap, fmt};
+
+use super::{
+    AnalysisReport,
+    cluster::{CRATER_PREFIX, normalize_error_line},
+};
+
+/// The lines a category matched in the selected runs of an experiment.
+#[derive(Debug, serde::Serialize)]
+pub struct RuleHits {
+    pub category: String,
+    /// Selected runs the category matched in
+    pub runs: usize,
+    /// Matched lines that still differ once crate names, paths and numbers are normalized
+    pub distinct_lines: usize,
+    /// The most common of the distinct lines, the most common first
+    pub samples: Vec<HitSample>,
+}
+
+/// One of the distinct lines a category matched.
+#[derive(Debug, serde::Serialize)]
+pub struct HitSample {
+    /// The line as first matched, without crater's prefixes
+    pub line: String,
+    /// The crate it was first matched for
+    pub krate: String,
+    /// Runs matching a line like it
+    pub runs: usize,
+}
+
+/// Up to `samples` distinct matched lines per found category, the categories with the most distinct lines first,
+/// as rules matching unrelated errors match many different lines.
+pub fn rule_hits(report: &AnalysisReport, samples: usize) -> Vec<RuleHits> {
+    let mut hits = report
+        .finding_matches
+        .iter()
+        .map(|(category, matches)| {
+            // matches are ordered by run ID, so the first of each line is stable across analyses
+            let mut lines = BTreeMap::<String, (usize, HitSample)>::new();
+            for (position, found) in matches.iter().enumerate() {
+                lines
+                    .entry(normalize_error_line(&found.line))
+                    .or_insert_with(|| {
+                        let sample = HitSample {
+                            line: CRATER_PREFIX.replace(found.line.trim(), "").into_owned(),
+                            krate: found.krate.clone(),
+                            runs: 0,
+                        };
+                        (position, sample)
+                    })
+                    .1
+                    .runs += 1;
+            }
+            let distinct_lines = lines.len();
+            let mut lines = lines.into_values().collect::<Vec<_>>();
+            lines.sort_by(|(a_position, a), (b_position, b)| {
+                b.runs.cmp(&a.runs).then(a_position.cmp(b_position))
+            });
+            RuleHits {
+                category: category.clone(),
+                runs: report
+                    .findings
+                    .get(category)
+                    .copied()
+                    .unwrap_or(matches.len()),
+                distinct_lines,
+                samples: lines
+                    .into_iter()
+                    .take(samples)
+                    .map(|(_, sample)| sample)
+                    .collect(),
+            }
+        })
+        .collect::<Vec<_>>();
+    hits.sort_by(|a, b| {
+        b.distinct_lines
+            .cmp(&a.distinct_lines)
+            .then_with(|| a.category.cmp(&b.category))
+    });
+    hits
+}
+
+impl fmt::Display for RuleHits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{}: {} run(s), {} distinct line(s)",
+            self.category, self.runs, self.distinct_lines
+        )?;
+        for sample in &self.samples {
+            writeln!(f, "  {}× {} ({})", sample.runs, sample.line, sample.krate)?;
+        }
+        if self.distinct_lines > self.samples.len() {
+            writeln!(
+                f,
+                "  ... and {} more distinct line(s)",
+                self.distinct_lines - self.samples.len()
+            )?;
+        }
+        Ok(())
+    }
+}
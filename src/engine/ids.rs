@@ -0,0 +1,15 @@
+//! Stable identifiers of crates and runs, for external tools to join data across experiments and report versions.
+//!
+//! The format is part of the JSON report: `<experiment>:<crate>` for crates and
+//! `<experiment>:<crate>#<run index>` for runs, e.g. `pr-12345:gh/rust-lang/regex#1`.
+//! Neither experiment nor crate names contain `:` or `#`, so the parts can be split off again.
+
+/// The stable ID of a crate within an experiment.
+pub fn crate_id(experiment: &str, krate: &str) -> String {
+    format!("{experiment}:{krate}")
+}
+
+/// The stable ID of the run at `index` (crater lists the baseline run first) of a crate within an experiment.
+pub fn run_id(experiment: &str, krate: &str, index: usize) -> String {
+    format!("{experiment}:{krate}#{index}")
+}
@@ -0,0 +1,102 @@
+//! Libraries and symbols the linker failed to find, see [`linker_items`].
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    sync::LazyLock,
+};
+
+use regex::bytes::Regex;
+
+/// What the linker failed to find.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    serde::Serialize,
+    serde::Deserialize,
+    schemars::JsonSchema,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum LinkerItemKind {
+    MissingLibrary,
+    UndefinedSymbol,
+}
+
+impl fmt::Display for LinkerItemKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LinkerItemKind::MissingLibrary => "missing library",
+            LinkerItemKind::UndefinedSymbol => "undefined symbol",
+        })
+    }
+}
+
+/// A library or symbol the linker failed to find.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LinkerItem {
+    pub kind: LinkerItemKind,
+    /// The library without `-l`, e.g. `ssl`, or the symbol without the hash of Rust symbols
+    pub name: String,
+}
+
+/// A library or symbol the linker failed to find with all crates failing because of it.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct LinkerIssue {
+    pub kind: LinkerItemKind,
+    pub name: String,
+    /// Sorted by name
+    pub crates: Vec<String>,
+}
+
+impl LinkerIssue {
+    /// One issue per item, the items affecting the most crates first.
+    pub fn aggregate(items: BTreeMap<LinkerItem, BTreeSet<String>>) -> Vec<Self> {
+        let mut issues = items
+            .into_iter()
+            .map(|(item, crates)| LinkerIssue {
+                kind: item.kind,
+                name: item.name,
+                crates: crates.into_iter().collect(),
+            })
+            .collect::<Vec<_>>();
+        // the sort is stable, ties keep the order of the items
+        issues.sort_by_key(|issue| std::cmp::Reverse(issue.crates.len()));
+        issues
+    }
+}
+
+/// `rust-lld: error: unable to find library -lssl`, `/usr/bin/ld: cannot find -lssl: No such file or directory`
+static MISSING_LIBRARY: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?:unable to find library|cannot find) -l([^\s:]+)").unwrap());
+/// `rust-lld: error: undefined symbol: SSL_new` or ``undefined reference to `SSL_new'``
+static UNDEFINED_SYMBOL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?:error: undefined symbol: ([^\r\n]+)|undefined reference to `([^'\r\n]+)')")
+        .unwrap()
+});
+/// The hash suffix of mangled Rust symbols, e.g. `::h0123456789abcdef`
+static SYMBOL_HASH: LazyLock<regex::Regex> =
+    LazyLock::new(|| regex::Regex::new(r"::h[0-9a-f]{16}$").unwrap());
+
+/// The libraries and symbols the linker failed to find according to `log`.
+pub fn linker_items(log: &[u8]) -> BTreeSet<LinkerItem> {
+    let libraries = MISSING_LIBRARY
+        .captures_iter(log)
+        .map(|captures| LinkerItem {
+            kind: LinkerItemKind::MissingLibrary,
+            name: String::from_utf8_lossy(&captures[1]).into_owned(),
+        });
+    let symbols = UNDEFINED_SYMBOL.captures_iter(log).filter_map(|captures| {
+        let symbol = captures.get(1).or_else(|| captures.get(2))?;
+        let symbol = String::from_utf8_lossy(symbol.as_bytes());
+        Some(LinkerItem {
+            kind: LinkerItemKind::UndefinedSymbol,
+            name: SYMBOL_HASH.replace(symbol.trim(), "").into_owned(),
+        })
+    });
+    libraries.chain(symbols).collect()
+}
@@ -0,0 +1,108 @@
+//! Checks for patterns that are likely not what their author meant, see [`lint_target`].
+
+use std::fmt;
+
+use super::Target;
+
+/// Something suspicious about a pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatternIssue {
+    Empty,
+    LeadingWhitespace,
+    TrailingWhitespace,
+    /// Typographic quotes, usually pasted from a rendered page instead of the log
+    SmartQuote(char),
+    /// Whitespace that looks like a space but is not one, e.g. a non-breaking space
+    UnusualWhitespace(char),
+    /// Control characters other than tabs, logs are split at line breaks before matching
+    ControlCharacter(char),
+    UnbalancedBracket(char),
+}
+
+impl fmt::Display for PatternIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatternIssue::Empty => write!(f, "empty pattern, matches every log"),
+            PatternIssue::LeadingWhitespace => write!(f, "leading whitespace"),
+            PatternIssue::TrailingWhitespace => write!(f, "trailing whitespace"),
+            PatternIssue::SmartQuote(c) => {
+                write!(f, "typographic quote {c:?}, logs use ASCII quotes")
+            }
+            PatternIssue::UnusualWhitespace(c) => {
+                write!(f, "unusual whitespace {}", c.escape_unicode())
+            }
+            PatternIssue::ControlCharacter(c) => write!(
+                f,
+                "control character {}, patterns never span lines",
+                c.escape_default()
+            ),
+            PatternIssue::UnbalancedBracket(c) => write!(f, "unbalanced {c:?}"),
+        }
+    }
+}
+
+/// Flags patterns of a target that likely never match as intended,
+/// returning the index of the affected pattern with each issue, counting the `any` and then the `none` patterns
+/// after the `all` patterns.
+///
+/// Whitespace at the edges is expected where a pattern borders on text left out between the
+/// patterns of a target, so it is only flagged for targets with a single pattern, the `any` patterns counting as one.
+/// Likewise, brackets only need to be balanced across the `all` patterns of a target, and are not checked
+/// for the `any` and `none` patterns, which may close brackets opened by them.
+pub fn lint_target(target: &Target) -> Vec<(usize, PatternIssue)> {
+    let mut issues = Vec::new();
+    let single = target.all.len() + usize::from(!target.any.is_empty()) == 1;
+    let mut open = Vec::new();
+
+    let patterns = target.all.iter().chain(&target.any).chain(&target.none);
+    for (index, pattern) in patterns.enumerate() {
+        let alternative = index >= target.all.len();
+        if pattern.is_empty() {
+            issues.push((index, PatternIssue::Empty));
+            continue;
+        }
+        if single && pattern.starts_with(char::is_whitespace) {
+            issues.push((index, PatternIssue::LeadingWhitespace));
+        }
+        if single && pattern.ends_with(char::is_whitespace) {
+            issues.push((index, PatternIssue::TrailingWhitespace));
+        }
+
+        for c in pattern.chars() {
+            let issue = match c {
+                '\u{2018}' | '\u{2019}' | '\u{201C}' | '\u{201D}' => PatternIssue::SmartQuote(c),
+                ' ' | '\t' => continue,
+                c if c.is_control() => PatternIssue::ControlCharacter(c),
+                c if c.is_whitespace() || matches!(c, '\u{200B}' | '\u{FEFF}') => {
+                    PatternIssue::UnusualWhitespace(c)
+                }
+                '(' | '[' | '{' | ')' | ']' | '}' if alternative => continue,
+                '(' | '[' | '{' => {
+                    open.push((index, c));
+                    continue;
+                }
+                ')' | ']' | '}' => {
+                    let opening = match c {
+                        ')' => '(',
+                        ']' => '[',
+                        _ => '{',
+                    };
+                    if open.last().is_some_and(|(_, open)| *open == opening) {
+                        open.pop();
+                        continue;
+                    }
+                    PatternIssue::UnbalancedBracket(c)
+                }
+                _ => continue,
+            };
+            issues.push((index, issue));
+        }
+    }
+
+    issues.extend(
+        open.into_iter()
+            .map(|(index, c)| (index, PatternIssue::UnbalancedBracket(c))),
+    );
+    issues.sort_by_key(|(index, _)| *index);
+    issues
+}
@@ -0,0 +1,52 @@
+use std::collections::BTreeMap;
+
+/// How many of the most downloaded crates on crates.io were considered and the ranks of the affected crates among them.
+#[derive(Debug, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct Popularity {
+    /// The number of most downloaded crates considered
+    pub top: usize,
+    /// The download rank, starting at 1, of every mentioned crate among the `top` crates
+    pub ranks: BTreeMap<String, usize>,
+}
+
+impl Popularity {
+    /// Ranks the crates by their position in `top_crates`, the crates.io names ordered by downloads.
+    ///
+    /// Crates are identified by their crates.io URL, crates from other sources are never ranked.
+    pub fn new(top_crates: &[String], crate_urls: &BTreeMap<String, String>) -> Self {
+        let positions = top_crates
+            .iter()
+            .enumerate()
+            .map(|(index, name)| (name.as_str(), index + 1))
+            .collect::<BTreeMap<_, _>>();
+        let ranks = crate_urls
+            .iter()
+            .filter_map(|(krate, url)| {
+                let rank = positions.get(crates_io_name(url)?)?;
+                Some((krate.clone(), *rank))
+            })
+            .collect();
+        Self {
+            top: top_crates.len(),
+            ranks,
+        }
+    }
+}
+
+/// The crates.io name of a crate from its crater URL, e.g. `https://crates.io/crates/serde/1.0.0`.
+pub fn crates_io_name(url: &str) -> Option<&str> {
+    let name = url
+        .strip_prefix("https://crates.io/crates/")?
+        .split('/')
+        .next()?;
+    (!name.is_empty()).then_some(name)
+}
+
+/// The crates.io name and version of a crate from its crater URL, e.g. `https://crates.io/crates/serde/1.0.0`.
+pub fn crates_io_version(url: &str) -> Option<(&str, &str)> {
+    let (name, version) = url
+        .strip_prefix("https://crates.io/crates/")?
+        .split_once('/')?;
+    let version = version.trim_end_matches('/');
+    (!name.is_empty() && !version.is_empty() && !version.contains('/')).then_some((name, version))
+}
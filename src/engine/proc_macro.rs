@@ -0,0 +1,110 @@
+//! Panics of proc-macros while expanding the crate's code, see [`proc_macro_panics`].
+
+use super::cluster::{CRATER_PREFIX, normalize_error_line};
+
+/// Prefix of the categories of proc-macro panics, followed by the macro and the panic message.
+pub const PROC_MACRO_PANICKED: &str = "proc-macro panicked: ";
+
+/// Panic messages are cut off after this many characters, so that categories stay readable.
+const MAX_MESSAGE_LENGTH: usize = 80;
+
+/// Lines after the error searched for the source snippet and the panic message.
+const MAX_DIAGNOSTIC_LINES: usize = 20;
+
+/// A proc-macro panicked while expanding the crate's code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcMacroPanic {
+    /// The panicking macro, e.g. `Serialize` for `#[derive(Serialize)]` or `sqlx::query` for `sqlx::query!(...)`,
+    /// if the source snippet points at it
+    pub macro_name: Option<String>,
+    /// The panic message normalized like a [fingerprint line](super::fingerprint_line), if reported
+    pub message: Option<String>,
+    /// 1-based number of the line reporting the panic
+    pub line_number: usize,
+}
+
+impl ProcMacroPanic {
+    /// The category of the panic, e.g. `proc-macro panicked: Serialize (called `…` on a `…` value)`.
+    pub fn category(&self) -> String {
+        let macro_name = self.macro_name.as_deref().unwrap_or("unknown macro");
+        match &self.message {
+            Some(message) => format!("{PROC_MACRO_PANICKED}{macro_name} ({message})"),
+            None => format!("{PROC_MACRO_PANICKED}{macro_name}"),
+        }
+    }
+}
+
+/// The `error: proc-macro derive panicked` and `error: proc macro panicked` diagnostics of `log`,
+/// with the macro taken from the span the diagnostic points at and the message from its `= help: message:` note.
+pub fn proc_macro_panics(log: &[u8]) -> Vec<ProcMacroPanic> {
+    let lines = log
+        .split(|&c| c == b'\n')
+        .map(|line| {
+            let line = String::from_utf8_lossy(line);
+            CRATER_PREFIX.replace(line.trim_end(), "").into_owned()
+        })
+        .collect::<Vec<_>>();
+
+    let mut panics = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        let line = line.trim_start();
+        if !(line.starts_with("error: proc-macro derive panicked")
+            || line.starts_with("error: proc macro panicked"))
+        {
+            continue;
+        }
+
+        let mut macro_name = None;
+        let mut message = None;
+        let mut source_line = None;
+        for next in lines.iter().skip(index + 1).take(MAX_DIAGNOSTIC_LINES) {
+            let trimmed = next.trim_start();
+            if trimmed.starts_with("error") || trimmed.starts_with("warning") {
+                break;
+            }
+            if let Some(text) = trimmed.strip_prefix("= help: message: ") {
+                message = Some(shorten(&normalize_error_line(text)));
+                break;
+            }
+            let Some((gutter, code)) = next.split_once('|') else {
+                continue;
+            };
+            if gutter.trim().parse::<usize>().is_ok() {
+                source_line = Some(code);
+            } else if let Some(source) = source_line.filter(|_| macro_name.is_none()) {
+                macro_name = marked_macro(source, code);
+            }
+        }
+
+        panics.push(ProcMacroPanic {
+            macro_name,
+            message,
+            line_number: index + 1,
+        });
+    }
+    panics
+}
+
+/// The name of the macro in the part of `source` marked by the `^` of `markers`, both following the `|` of the snippet.
+fn marked_macro(source: &str, markers: &str) -> Option<String> {
+    let start = markers.find('^')?;
+    let end = markers[start..]
+        .find(|c| c != '^')
+        .map_or(markers.len(), |length| start + length);
+    let marked = source.get(start..end)?.trim();
+    // `#[my_attribute(...)]` or `my_macro!(...)`, derives are marked by their name alone
+    let marked = marked.strip_prefix("#[").unwrap_or(marked);
+    // keep the path, `tokio::main` tells more than `main`
+    let path = marked
+        .split(|c: char| !(c.is_alphanumeric() || c == '_' || c == ':'))
+        .next()?
+        .trim_matches(':');
+    (!path.is_empty()).then(|| path.to_string())
+}
+
+fn shorten(message: &str) -> String {
+    match message.char_indices().nth(MAX_MESSAGE_LENGTH) {
+        Some((index, _)) => format!("{}…", &message[..index]),
+        None => message.to_string(),
+    }
+}
@@ -0,0 +1,1836 @@
+//! Rendering of an [`AnalysisReport`] into the supported output formats.
+
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write as _,
+    str::FromStr,
+};
+
+use super::{
+    AnalysisReport, OtherCrate, format_bytes, format_duration, percent, report::format_chain,
+    run_role, strip_ansi,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Text,
+    Markdown,
+    Html,
+}
+
+impl ReportFormat {
+    /// The file extension of reports in this format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            ReportFormat::Text => "report",
+            ReportFormat::Markdown => "md",
+            ReportFormat::Html => "html",
+        }
+    }
+
+    /// The name of the format in [report paths](super::Config::report_path).
+    pub fn name(self) -> &'static str {
+        match self {
+            ReportFormat::Text => "text",
+            ReportFormat::Markdown => "markdown",
+            ReportFormat::Html => "html",
+        }
+    }
+
+    /// Renders the report, the text report always lists all crates.
+    ///
+    /// ANSI escape sequences in log lines are only kept in text reports, see [`strip_ansi`].
+    pub fn render(self, report: &AnalysisReport, limits: &ExampleLimits) -> String {
+        let rendered = match self {
+            ReportFormat::Text => return report.to_string(),
+            ReportFormat::Markdown => add_markdown_contents(&render_markdown(report, limits)),
+            ReportFormat::Html => add_html_contents(&render_html(report, limits)),
+        };
+        match strip_ansi(&rendered) {
+            Cow::Borrowed(_) => rendered,
+            Cow::Owned(stripped) => stripped,
+        }
+    }
+
+    /// The separate HTML pages listing all crates of the categories with more crates than the
+    /// [page size](ExampleLimits::page_size), by file name; they belong next to the HTML report linking them.
+    pub fn pages(self, report: &AnalysisReport, limits: &ExampleLimits) -> Vec<(String, String)> {
+        if self != ReportFormat::Html {
+            return Vec::new();
+        }
+        let mut pages = Vec::new();
+        for (name, _) in report.sorted_findings() {
+            let crates = report.sorted_category_crates(name);
+            let items = crates
+                .iter()
+                .map(|krate| category_crate_html(report, name, krate))
+                .collect::<Vec<_>>();
+            pages.extend(render_html_pages(
+                report,
+                limits,
+                &category_anchor(name),
+                &escape_html(name),
+                &items,
+            ));
+        }
+        let others = report
+            .other
+            .iter()
+            .map(|(krate, other)| other_crate_html(report, krate, other))
+            .collect::<Vec<_>>();
+        pages.extend(render_html_pages(
+            report, limits, "others", "Others", &others,
+        ));
+        pages
+    }
+}
+
+/// How many crates are listed per category before folding the rest into "… and N more".
+#[derive(Debug, Clone, Default)]
+pub struct ExampleLimits {
+    /// Limit for categories without an override, 0 lists all crates
+    pub default: usize,
+    pub per_category: BTreeMap<String, usize>,
+    /// Categories with more crates are listed on [separate HTML pages](ReportFormat::pages) of this many crates,
+    /// and the HTML report itself lists at most this many; 0 disables the pages
+    pub page_size: usize,
+}
+
+impl ExampleLimits {
+    /// Lists all crates of every category.
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    /// Keeps the [HTML pages](ReportFormat::pages) of `page_size` crates.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size;
+        self
+    }
+
+    /// The number of HTML pages listing `crates` crates, `None` if they are listed in the report itself.
+    fn page_count(&self, crates: usize) -> Option<usize> {
+        (self.page_size > 0 && crates > self.page_size).then(|| crates.div_ceil(self.page_size))
+    }
+
+    /// Like [`ExampleLimits::apply`], also limited to a page if the crates are listed on separate pages.
+    fn apply_html<'a, T>(&self, category: &str, crates: &'a [T]) -> (&'a [T], usize) {
+        let (listed, folded) = self.apply(category, crates);
+        if self.page_count(crates.len()).is_some() && listed.len() > self.page_size {
+            (
+                &listed[..self.page_size],
+                folded + listed.len() - self.page_size,
+            )
+        } else {
+            (listed, folded)
+        }
+    }
+
+    /// The crates of a category to list and the number of folded crates.
+    fn apply<'a, T>(&self, category: &str, crates: &'a [T]) -> (&'a [T], usize) {
+        let limit = self
+            .per_category
+            .get(category)
+            .copied()
+            .unwrap_or(self.default);
+        if limit == 0 || crates.len() <= limit {
+            (crates, 0)
+        } else {
+            (&crates[..limit], crates.len() - limit)
+        }
+    }
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" | "txt" => Ok(ReportFormat::Text),
+            "md" | "markdown" => Ok(ReportFormat::Markdown),
+            "html" => Ok(ReportFormat::Html),
+            _ => Err(format!(
+                "unknown report format '{s}', expected one of html, md or text"
+            )),
+        }
+    }
+}
+
+fn render_markdown(report: &AnalysisReport, limits: &ExampleLimits) -> String {
+    // writing to a String never fails
+    let mut out = String::new();
+
+    let _ = writeln!(
+        out,
+        "# Report for Crater Experiment {}\n",
+        escape_markdown(&report.experiment)
+    );
+    if let Some(mode) = &report.mode {
+        let _ = writeln!(out, "Mode: {}\n", escape_markdown(mode));
+    }
+    if let Some(status) = &report.status {
+        let _ = writeln!(out, "Status: {}\n", escape_markdown(&status.to_string()));
+        if status.stale {
+            let _ = writeln!(
+                out,
+                "**Warning**: the results of this experiment may be outdated\n"
+            );
+        }
+    }
+    if let Some(selection) = &report.selection {
+        let _ = writeln!(out, "Selection: `{selection}`\n");
+    }
+    if let Some(hash) = &report.reproducibility_hash {
+        let _ = writeln!(
+            out,
+            "Reproducibility hash: `{hash}` (reports with the same hash are comparable)\n"
+        );
+    }
+    let _ = writeln!(
+        out,
+        "- {} crates: {}",
+        escape_markdown(&report.expected_krate_result),
+        report.format_count(report.regressed_count)
+    );
+    let _ = writeln!(
+        out,
+        "- {} runs: {}\n",
+        escape_markdown(&report.expected_run_result),
+        report.format_count(report.interesting_results_count)
+    );
+
+    let _ = writeln!(out, "## Results\n");
+    if let Some(comparison) = &report.comparison {
+        let _ = writeln!(
+            out,
+            "**NEW** marks what did not appear in {}.\n",
+            escape_markdown(&comparison.experiment)
+        );
+    }
+    match &report.popularity {
+        Some(popularity) => {
+            let _ = writeln!(
+                out,
+                "| Category | Count | % of Runs | % of Crates | Top {} |\n| --- | ---: | ---: | ---: | ---: |",
+                popularity.top
+            );
+        }
+        None => {
+            let _ = writeln!(
+                out,
+                "| Category | Count | % of Runs | % of Crates |\n| --- | ---: | ---: | ---: |"
+            );
+        }
+    }
+    for (name, &count) in report.sorted_findings() {
+        let badge = if report.is_new_category(name) {
+            " **NEW**"
+        } else {
+            ""
+        };
+        let (of_runs, of_crates) = report.category_percentages(name, count);
+        let _ = write!(
+            out,
+            "| {}{badge} | {} | {of_runs:.1}% | {of_crates:.1}% |",
+            category_link_markdown(report, name),
+            report.format_count(count)
+        );
+        if report.popularity.is_some() {
+            let _ = write!(out, " {} |", report.top_crate_count(name));
+        }
+        let _ = writeln!(out);
+    }
+    let sum = report.format_count(report.findings.values().sum());
+    if report.popularity.is_some() {
+        let _ = writeln!(out, "| **sum** | **{sum}** | | | |\n");
+    } else {
+        let _ = writeln!(out, "| **sum** | **{sum}** | | |\n");
+    }
+    if !report.ignored_findings.is_empty() {
+        let _ = writeln!(
+            out,
+            "Not counted above, as ignored for this run: {}\n",
+            escape_markdown(&report.ignored_summary())
+        );
+    }
+    let (independent, blocked) = report.independent_and_blocked_counts();
+    if blocked > 0 {
+        let _ = writeln!(
+            out,
+            "{independent} crates are broken independently, {blocked} only because a dependency failed to build:\n"
+        );
+        for (dependency, crates) in report.blocking_dependencies() {
+            let _ = writeln!(out, "- `{dependency}` blocks {} crate(s)", crates.len());
+        }
+        let _ = writeln!(out);
+    }
+
+    if !report.descriptions.is_empty() {
+        let _ = writeln!(out, "## Categories\n");
+        for (name, description) in &report.descriptions {
+            let _ = writeln!(
+                out,
+                "- **{}**: {}",
+                escape_markdown(name),
+                escape_markdown(description)
+            );
+        }
+        let _ = writeln!(out);
+    }
+
+    if !report.owners.is_empty() {
+        let _ = writeln!(out, "## Owners\n");
+        write_owners_markdown(&mut out, report);
+        let _ = writeln!(out);
+    }
+
+    for (name, section) in &report.sections {
+        let _ = writeln!(out, "## {}\n", escape_markdown(name));
+        let _ = writeln!(
+            out,
+            "- {} crates: {}",
+            escape_markdown(&section.expected_krate_result),
+            section.krate_count
+        );
+        let _ = writeln!(
+            out,
+            "- {} runs: {}\n",
+            escape_markdown(&section.expected_run_result),
+            section.run_count
+        );
+        let _ = writeln!(out, "| Category | Count |\n| --- | ---: |");
+        for (name, count) in &section.findings {
+            let _ = writeln!(out, "| {} | {count} |", escape_markdown(name));
+        }
+        let (listed, folded) = limits.apply("others", &section.other);
+        let mut listed = listed
+            .iter()
+            .map(|krate| crate_link_markdown(report, krate))
+            .collect::<Vec<_>>()
+            .join(", ");
+        if folded > 0 {
+            let _ = write!(listed, " … and {folded} more");
+        }
+        let _ = writeln!(out, "| *others* | {} |", section.other.len());
+        if !section.other.is_empty() {
+            let _ = writeln!(out, "\nOthers: {listed}");
+        }
+        let _ = writeln!(out);
+    }
+
+    if let Some(warnings) = &report.warnings {
+        let _ = writeln!(out, "## Warnings\n");
+        let _ = writeln!(
+            out,
+            "- {} crates: {}",
+            escape_markdown(&warnings.expected_krate_result),
+            warnings.krate_count
+        );
+        let _ = writeln!(
+            out,
+            "- {} runs: {}\n",
+            escape_markdown(&warnings.expected_run_result),
+            warnings.run_count
+        );
+        let _ = writeln!(out, "| Category | Count |\n| --- | ---: |");
+        for (name, count) in &warnings.findings {
+            let _ = writeln!(out, "| {} | {count} |", escape_markdown(name));
+        }
+        let _ = writeln!(out);
+    }
+
+    if !report.category_crates.is_empty() {
+        let _ = writeln!(out, "## Crates by Category\n");
+        for (name, _) in report.sorted_findings() {
+            let crates = report.sorted_category_crates(name);
+            if crates.is_empty() {
+                continue;
+            }
+            let (listed, folded) = limits.apply(name, &crates);
+            let listed = listed
+                .iter()
+                .map(|krate| {
+                    let mut listed = crate_link_markdown(report, krate);
+                    if let Some(rank) = report.crate_rank(krate) {
+                        let _ = write!(listed, " (#{rank})");
+                    }
+                    if report.is_new_crate(name, krate) {
+                        listed.push_str(" **NEW**");
+                    }
+                    listed
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            let _ = write!(
+                out,
+                "- <a id=\"{}\"></a>{}: {listed}",
+                category_anchor(name),
+                escape_markdown(name)
+            );
+            if folded > 0 {
+                let _ = write!(out, " … and {folded} more");
+            }
+            let _ = writeln!(out);
+        }
+        let _ = writeln!(out);
+    }
+
+    let most_categories = report.crates_by_category_count();
+    if !most_categories.is_empty() {
+        let _ = writeln!(
+            out,
+            "## Crates Matching the Most Categories\n\n| Crate | Categories | Matched |\n| --- | ---: | --- |"
+        );
+        for (krate, categories) in &most_categories {
+            let matched = categories
+                .iter()
+                .map(|category| category_link_markdown(report, category))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let _ = writeln!(
+                out,
+                "| {} | {} | {matched} |",
+                crate_link_markdown(report, krate),
+                categories.len()
+            );
+        }
+        let _ = writeln!(out);
+    }
+
+    if !report.dependency_chains.is_empty() {
+        let _ = writeln!(out, "## Dependency Chains\n");
+        for (name, crates) in &report.dependency_chains {
+            let _ = writeln!(out, "- {}", escape_markdown(name));
+            for (krate, chain) in crates {
+                let _ = writeln!(
+                    out,
+                    "  - {}: {}",
+                    crate_link_markdown(report, krate),
+                    escape_markdown(&format_chain(chain))
+                );
+            }
+        }
+        let _ = writeln!(out);
+    }
+
+    if !report.linker_issues.is_empty() {
+        let _ = writeln!(out, "## Linker Issues\n");
+        for issue in &report.linker_issues {
+            let (listed, folded) = limits.apply(&issue.name, &issue.crates);
+            let listed = listed
+                .iter()
+                .map(|krate| crate_link_markdown(report, krate))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let _ = write!(
+                out,
+                "- {} `{}` ({} crate(s)): {listed}",
+                issue.kind,
+                issue.name,
+                issue.crates.len()
+            );
+            if folded > 0 {
+                let _ = write!(out, " … and {folded} more");
+            }
+            let _ = writeln!(out);
+        }
+        let _ = writeln!(out);
+    }
+
+    if !report.resolver_culprits.is_empty() {
+        let _ = writeln!(out, "## Unresolvable Dependencies\n");
+        for culprit in &report.resolver_culprits {
+            let (listed, folded) = limits.apply(&culprit.package, &culprit.crates);
+            let listed = listed
+                .iter()
+                .map(|krate| crate_link_markdown(report, krate))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let requirements = culprit
+                .requirements
+                .iter()
+                .map(|requirement| format!(" `{requirement}`"))
+                .collect::<String>();
+            let _ = write!(
+                out,
+                "- `{}`{requirements} ({} crate(s)): {listed}",
+                culprit.package,
+                culprit.crates.len()
+            );
+            if folded > 0 {
+                let _ = write!(out, " … and {folded} more");
+            }
+            let _ = writeln!(out);
+        }
+        let _ = writeln!(out);
+    }
+
+    if !report.known_failures.is_empty() {
+        let _ = writeln!(
+            out,
+            "## Known Failures\n\n| Crates | Category | Line | First Seen | Status | Examples |\n| ---: | --- | --- | --- | --- | --- |"
+        );
+        for (known, crates) in report.sorted_known_failures() {
+            let (listed, folded) = limits.apply(&known.category, crates);
+            let mut listed = listed
+                .iter()
+                .map(|krate| crate_link_markdown(report, krate))
+                .collect::<Vec<_>>()
+                .join(", ");
+            if folded > 0 {
+                let _ = write!(listed, " … and {folded} more");
+            }
+            let mut status = known.status.to_string();
+            if let Some(note) = &known.note {
+                let _ = write!(status, ": {}", escape_markdown(note));
+            }
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} | {} | {status} | {listed} |",
+                crates.len(),
+                escape_markdown(&known.category),
+                escape_markdown(&known.error_line),
+                escape_markdown(&known.first_seen)
+            );
+        }
+        let _ = writeln!(out);
+    }
+
+    if !report.toolchain_mismatches.is_empty() {
+        let _ = writeln!(
+            out,
+            "## Unexpected Compilers\n\nThese runs were not built with the compiler of their toolchain.\n\n| Crate | Log | Expected | Found |\n| --- | --- | --- | --- |"
+        );
+        for mismatch in &report.toolchain_mismatches {
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} | `{}` |",
+                crate_link_markdown(report, &mismatch.krate),
+                escape_markdown(&mismatch.log),
+                escape_markdown(&mismatch.expected),
+                mismatch.found
+            );
+        }
+        let _ = writeln!(out);
+    }
+
+    if !report.tagged_crates.is_empty() {
+        let _ = writeln!(
+            out,
+            "## Tagged Crates\n\n| Tag | Crates | Selected crates and their categories |\n| --- | ---: | --- |"
+        );
+        for (tag, crates) in &report.tagged_crates {
+            let listed = crates
+                .iter()
+                .map(|(krate, categories)| {
+                    let categories = categories
+                        .iter()
+                        .map(|category| category_link_markdown(report, category))
+                        .collect::<Vec<_>>();
+                    format!(
+                        "{} ({})",
+                        crate_link_markdown(report, krate),
+                        categories.join(", ")
+                    )
+                })
+                .collect::<Vec<_>>();
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} |",
+                escape_markdown(tag),
+                report.format_count(crates.len()),
+                if listed.is_empty() {
+                    "none selected".to_string()
+                } else {
+                    listed.join("<br>")
+                }
+            );
+        }
+        let _ = writeln!(out);
+    }
+
+    if !report.annotations.is_empty() {
+        let _ = writeln!(out, "## Annotations\n");
+        for (krate, annotation) in &report.annotations {
+            let _ = writeln!(
+                out,
+                "- {}: {}",
+                crate_link_markdown(report, krate),
+                escape_markdown(&annotation.summary())
+            );
+        }
+        let _ = writeln!(out);
+    }
+
+    if !report.other_groups.is_empty() {
+        let _ = writeln!(
+            out,
+            "## Others by Last Error\n\n| Crates | Last Error Line | Examples |\n| ---: | --- | --- |"
+        );
+        for group in &report.other_groups {
+            let (listed, folded) = limits.apply("others", &group.crates);
+            let mut listed = listed
+                .iter()
+                .map(|krate| crate_link_markdown(report, krate))
+                .collect::<Vec<_>>()
+                .join(", ");
+            if folded > 0 {
+                let _ = write!(listed, " … and {folded} more");
+            }
+            let error_line = match &group.error_line {
+                Some(line) => escape_markdown(line),
+                None => "*no error line*".to_string(),
+            };
+            let _ = writeln!(out, "| {} | {error_line} | {listed} |", group.crates.len());
+        }
+        let _ = writeln!(out);
+    }
+
+    let others = report.other.iter().collect::<Vec<_>>();
+    let (others, folded) = limits.apply("others", &others);
+    let _ = writeln!(out, "## Others ({})\n", report.other.len());
+    for (krate, other) in others {
+        let runs = other
+            .runs
+            .iter()
+            .map(|run| format!("{} `{}`", run.role, run.log))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let _ = writeln!(
+            out,
+            "- {} ({}): {runs}",
+            crate_link_markdown(report, krate),
+            escape_markdown(&other.res)
+        );
+    }
+    if folded > 0 {
+        let _ = writeln!(out, "- … and {folded} more");
+    }
+
+    if !report.reconstructed_logs.is_empty() {
+        let _ = writeln!(
+            out,
+            "\n{} runs have no log recorded in the results, their logs were located by crater's layout.",
+            report.reconstructed_logs.len()
+        );
+    }
+
+    if !report.missing_runs.is_empty() {
+        let _ = writeln!(
+            out,
+            "\n## No Run Recorded ({})\n",
+            report.missing_runs.len()
+        );
+        for (krate, roles) in &report.missing_runs {
+            let _ = writeln!(
+                out,
+                "- {}: {}",
+                crate_link_markdown(report, krate),
+                roles.join(", ")
+            );
+        }
+    }
+
+    for (title, crates) in [
+        ("Yanked Versions", &report.yanked),
+        ("Yanked Versions, Not Analyzed", &report.excluded_yanked),
+    ] {
+        if !crates.is_empty() {
+            let crates = crates
+                .iter()
+                .map(|krate| crate_link_markdown(report, krate))
+                .collect::<Vec<_>>();
+            let _ = writeln!(out, "\n## {title}\n\n{}", crates.join(", "));
+        }
+    }
+
+    if !report.relaxed_matches.is_empty() {
+        let _ = writeln!(
+            out,
+            "\n## Only Matching with Relaxed Matching\n\nThese rules may be broken by changes to the compiler's wording.\n"
+        );
+        for (name, crates) in &report.relaxed_matches {
+            let _ = writeln!(out, "- {}", escape_markdown(name));
+            for (krate, relaxation) in crates {
+                let _ = writeln!(
+                    out,
+                    "  - {} ({relaxation})",
+                    crate_link_markdown(report, krate)
+                );
+            }
+        }
+    }
+
+    if let Some(sizes) = &report.log_sizes {
+        let _ = writeln!(
+            out,
+            "\n## Log Sizes\n\n{} in total\n",
+            format_bytes(sizes.total_bytes)
+        );
+        if let Some(reuse) = &sizes.reuse {
+            let _ = writeln!(
+                out,
+                "Of the fetched logs {} were downloaded and {} reused from other experiments ({} not downloaded)\n",
+                reuse.downloaded,
+                reuse.reused,
+                format_bytes(reuse.reused_bytes)
+            );
+        }
+        let _ = writeln!(out, "| Category | Size |\n| --- | ---: |");
+        for (name, &bytes) in &sizes.category_bytes {
+            let _ = writeln!(
+                out,
+                "| {} | {} |",
+                escape_markdown(name),
+                format_bytes(bytes)
+            );
+        }
+        let _ = writeln!(out, "\n| Largest Logs | Size |\n| --- | ---: |");
+        for size in &sizes.largest {
+            let _ = writeln!(
+                out,
+                "| {} `{}` | {} |",
+                crate_link_markdown(report, &size.krate),
+                size.log,
+                format_bytes(size.bytes)
+            );
+        }
+    }
+
+    if let Some(durations) = &report.durations {
+        let _ = writeln!(
+            out,
+            "\n## Build Durations\n\n| Slowest Builds | Duration |\n| --- | ---: |"
+        );
+        for duration in &durations.slowest {
+            let _ = writeln!(
+                out,
+                "| {} `{}` | {} |",
+                crate_link_markdown(report, &duration.krate),
+                duration.log,
+                format_duration(duration.seconds)
+            );
+        }
+        if !durations.regressions.is_empty() {
+            let _ = writeln!(
+                out,
+                "\n| Slower than Baseline | Baseline | Candidate |\n| --- | ---: | ---: |"
+            );
+            for regression in &durations.regressions {
+                let _ = writeln!(
+                    out,
+                    "| {} | {} | {} |",
+                    crate_link_markdown(report, &regression.krate),
+                    format_duration(regression.baseline_seconds),
+                    format_duration(regression.candidate_seconds)
+                );
+            }
+        }
+    }
+
+    out
+}
+
+/// A short Markdown summary for chat messages, listing the `top` most common categories.
+pub fn render_summary_markdown(report: &AnalysisReport, top: usize) -> String {
+    // writing to a String never fails
+    let mut out = String::new();
+
+    let analyzed = report.interesting_results_count;
+    let unclassified = report.unclassified_count();
+    let unclassified_percent = percent(unclassified, analyzed);
+    let _ = writeln!(
+        out,
+        "**{}**: {} {} runs of {} {} crates, {} unclassified ({unclassified_percent:.1}%)\n",
+        escape_markdown(&report.experiment),
+        report.format_count(analyzed),
+        escape_markdown(&report.expected_run_result),
+        report.format_count(report.regressed_count),
+        escape_markdown(&report.expected_krate_result),
+        report.format_count(unclassified),
+    );
+
+    let categories = report.findings_by_count();
+    let _ = writeln!(
+        out,
+        "| Category | Count | % of Runs | % of Crates |\n| --- | ---: | ---: | ---: |"
+    );
+    for &(name, &count) in categories.iter().take(top) {
+        let badge = if report.is_new_category(name) {
+            " **NEW**"
+        } else {
+            ""
+        };
+        let (of_runs, of_crates) = report.category_percentages(name, count);
+        let _ = writeln!(
+            out,
+            "| {}{badge} | {} | {of_runs:.1}% | {of_crates:.1}% |",
+            escape_markdown(name),
+            report.format_count(count)
+        );
+    }
+    if categories.len() > top {
+        let _ = writeln!(out, "\n… and {} more categories", categories.len() - top);
+    }
+    if !report.owners.is_empty() {
+        let _ = writeln!(out);
+        write_owners_markdown(&mut out, report);
+    }
+
+    out
+}
+
+/// One line per owner listing their categories, ready to be pasted to ping the teams.
+fn write_owners_markdown(out: &mut String, report: &AnalysisReport) {
+    for owner in report.owners.keys() {
+        let categories = report
+            .owner_categories(owner)
+            .into_iter()
+            .map(|(category, crates)| format!("{} ({crates})", escape_markdown(category)))
+            .collect::<Vec<_>>();
+        let _ = writeln!(
+            out,
+            "- {}: {}",
+            escape_markdown(owner),
+            categories.join(", ")
+        );
+    }
+}
+
+fn render_html(report: &AnalysisReport, limits: &ExampleLimits) -> String {
+    // writing to a String never fails
+    let mut out = String::new();
+    let experiment = escape_html(&report.experiment);
+
+    let _ = writeln!(
+        out,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>Report for Crater Experiment {experiment}</title>\n<style>{HTML_STYLE}</style>\n</head>\n<body>"
+    );
+    let _ = writeln!(out, "<h1>Report for Crater Experiment {experiment}</h1>");
+    if let Some(mode) = &report.mode {
+        let _ = writeln!(out, "<p>Mode: {}</p>", escape_html(mode));
+    }
+    if let Some(status) = &report.status {
+        let _ = writeln!(out, "<p>Status: {}</p>", escape_html(&status.to_string()));
+        if status.stale {
+            let _ = writeln!(
+                out,
+                "<p><strong>Warning</strong>: the results of this experiment may be outdated</p>"
+            );
+        }
+    }
+    if let Some(selection) = &report.selection {
+        let _ = writeln!(
+            out,
+            "<p>Selection: <code>{}</code></p>",
+            escape_html(selection)
+        );
+    }
+    if let Some(hash) = &report.reproducibility_hash {
+        let _ = writeln!(
+            out,
+            "<p>Reproducibility hash: <code>{}</code> (reports with the same hash are comparable)</p>",
+            escape_html(hash)
+        );
+    }
+    let _ = writeln!(
+        out,
+        "<ul>\n<li>{} crates: {}</li>\n<li>{} runs: {}</li>\n</ul>",
+        escape_html(&report.expected_krate_result),
+        report.format_count(report.regressed_count),
+        escape_html(&report.expected_run_result),
+        report.format_count(report.interesting_results_count)
+    );
+
+    let _ = writeln!(out, "<h2>Results</h2>");
+    if let Some(comparison) = &report.comparison {
+        let _ = writeln!(
+            out,
+            "<p>{NEW_BADGE} marks what did not appear in {}.</p>",
+            escape_html(&comparison.experiment)
+        );
+    }
+    let top_header = report
+        .popularity
+        .as_ref()
+        .map(|popularity| format!("<th>Top {}</th>", popularity.top))
+        .unwrap_or_default();
+    let _ = writeln!(
+        out,
+        "<table>\n<thead><tr><th>Category</th><th>Count</th><th>% of Runs</th><th>% of Crates</th>{top_header}</tr></thead>\n<tbody>"
+    );
+    for (name, &count) in report.sorted_findings() {
+        let badge = if report.is_new_category(name) {
+            NEW_BADGE
+        } else {
+            ""
+        };
+        let top_count = if report.popularity.is_some() {
+            format!("<td>{}</td>", report.top_crate_count(name))
+        } else {
+            String::new()
+        };
+        let (of_runs, of_crates) = report.category_percentages(name, count);
+        let _ = writeln!(
+            out,
+            "<tr><td>{} {badge}</td><td>{}</td><td>{of_runs:.1}%</td><td>{of_crates:.1}%</td>{top_count}</tr>",
+            category_link_html(report, name),
+            report.format_count(count)
+        );
+    }
+    let sum = report.format_count(report.findings.values().sum());
+    let top_footer = if report.popularity.is_some() {
+        "<th></th>"
+    } else {
+        ""
+    };
+    let _ = writeln!(
+        out,
+        "</tbody>\n<tfoot><tr><th>sum</th><th>{sum}</th><th></th><th></th>{top_footer}</tr></tfoot>\n</table>"
+    );
+    if !report.ignored_findings.is_empty() {
+        let _ = writeln!(
+            out,
+            "<p>Not counted above, as ignored for this run: {}</p>",
+            escape_html(&report.ignored_summary())
+        );
+    }
+    let (independent, blocked) = report.independent_and_blocked_counts();
+    if blocked > 0 {
+        let _ = writeln!(
+            out,
+            "<p>{independent} crates are broken independently, {blocked} only because a dependency failed to build:</p>\n<ul>"
+        );
+        for (dependency, crates) in report.blocking_dependencies() {
+            let _ = writeln!(
+                out,
+                "<li><code>{}</code> blocks {} crate(s)</li>",
+                escape_html(dependency),
+                crates.len()
+            );
+        }
+        let _ = writeln!(out, "</ul>");
+    }
+
+    if !report.descriptions.is_empty() {
+        let _ = writeln!(out, "<h2>Categories</h2>\n<dl>");
+        for (name, description) in &report.descriptions {
+            let _ = writeln!(
+                out,
+                "<dt>{}</dt><dd>{}</dd>",
+                escape_html(name),
+                escape_html(description)
+            );
+        }
+        let _ = writeln!(out, "</dl>");
+    }
+
+    if !report.owners.is_empty() {
+        let _ = writeln!(out, "<h2>Owners</h2>\n<ul>");
+        for owner in report.owners.keys() {
+            let categories = report
+                .owner_categories(owner)
+                .into_iter()
+                .map(|(category, crates)| format!("{} ({crates})", escape_html(category)))
+                .collect::<Vec<_>>();
+            let _ = writeln!(
+                out,
+                "<li>{}: {}</li>",
+                escape_html(owner),
+                categories.join(", ")
+            );
+        }
+        let _ = writeln!(out, "</ul>");
+    }
+
+    for (name, section) in &report.sections {
+        let _ = writeln!(out, "<h2>{}</h2>", escape_html(name));
+        let _ = writeln!(
+            out,
+            "<ul>\n<li>{} crates: {}</li>\n<li>{} runs: {}</li>\n</ul>",
+            escape_html(&section.expected_krate_result),
+            section.krate_count,
+            escape_html(&section.expected_run_result),
+            section.run_count
+        );
+        let _ = writeln!(
+            out,
+            "<table>\n<thead><tr><th>Category</th><th>Count</th></tr></thead>\n<tbody>"
+        );
+        for (name, count) in &section.findings {
+            let _ = writeln!(
+                out,
+                "<tr><td>{}</td><td>{count}</td></tr>",
+                escape_html(name)
+            );
+        }
+        let _ = writeln!(
+            out,
+            "</tbody>\n<tfoot><tr><th>others</th><th>{}</th></tr></tfoot>\n</table>",
+            section.other.len()
+        );
+        if !section.other.is_empty() {
+            let (listed, folded) = limits.apply("others", &section.other);
+            let mut listed = listed
+                .iter()
+                .map(|krate| crate_link_html(report, krate))
+                .collect::<Vec<_>>()
+                .join(", ");
+            if folded > 0 {
+                let _ = write!(listed, " … and {folded} more");
+            }
+            let _ = writeln!(out, "<p>Others: {listed}</p>");
+        }
+    }
+
+    if let Some(warnings) = &report.warnings {
+        let _ = writeln!(out, "<h2>Warnings</h2>");
+        let _ = writeln!(
+            out,
+            "<ul>\n<li>{} crates: {}</li>\n<li>{} runs: {}</li>\n</ul>",
+            escape_html(&warnings.expected_krate_result),
+            warnings.krate_count,
+            escape_html(&warnings.expected_run_result),
+            warnings.run_count
+        );
+        let _ = writeln!(
+            out,
+            "<table>\n<thead><tr><th>Category</th><th>Count</th></tr></thead>\n<tbody>"
+        );
+        for (name, count) in &warnings.findings {
+            let _ = writeln!(
+                out,
+                "<tr><td>{}</td><td>{count}</td></tr>",
+                escape_html(name)
+            );
+        }
+        let _ = writeln!(out, "</tbody>\n</table>");
+    }
+
+    if !report.samples.is_empty() {
+        let _ = writeln!(out, "<h2>Samples</h2>");
+        for (name, sample) in &report.samples {
+            let _ = writeln!(
+                out,
+                "<h3>{}</h3>\n<p>{}: <code>{}</code></p>\n<pre>",
+                escape_html(name),
+                crate_link_html(report, &sample.krate),
+                escape_html(&sample.log)
+            );
+            let excerpt = &sample.excerpt;
+            for (line_number, line) in (excerpt.first_line_number..).zip(&excerpt.lines) {
+                let line = escape_html(line);
+                if line_number == excerpt.matched_line_number {
+                    let _ = writeln!(out, "<mark>{line_number:>6} {line}</mark>");
+                } else {
+                    let _ = writeln!(out, "{line_number:>6} {line}");
+                }
+            }
+            let _ = writeln!(out, "</pre>");
+        }
+    }
+
+    if !report.category_crates.is_empty() {
+        let _ = writeln!(out, "<h2>Crates by Category</h2>\n<ul>");
+        for (name, _) in report.sorted_findings() {
+            let crates = report.sorted_category_crates(name);
+            if crates.is_empty() {
+                continue;
+            }
+            let (listed, folded) = limits.apply_html(name, &crates);
+            let listed = listed
+                .iter()
+                .map(|krate| category_crate_html(report, name, krate))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let _ = write!(
+                out,
+                "<li id=\"{}\">{}: {listed}",
+                category_anchor(name),
+                escape_html(name)
+            );
+            if folded > 0 {
+                let _ = write!(out, " … and {folded} more");
+            }
+            let _ = write!(
+                out,
+                "{}",
+                pages_link_html(report, limits, &category_anchor(name), crates.len())
+            );
+            let _ = writeln!(out, "</li>");
+        }
+        let _ = writeln!(out, "</ul>");
+    }
+
+    let most_categories = report.crates_by_category_count();
+    if !most_categories.is_empty() {
+        let _ = writeln!(
+            out,
+            "<h2>Crates Matching the Most Categories</h2>\n<table>\n<thead><tr><th>Crate</th><th>Categories</th><th>Matched</th></tr></thead>\n<tbody>"
+        );
+        for (krate, categories) in &most_categories {
+            let matched = categories
+                .iter()
+                .map(|category| category_link_html(report, category))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let _ = writeln!(
+                out,
+                "<tr><td>{}</td><td>{}</td><td>{matched}</td></tr>",
+                crate_link_html(report, krate),
+                categories.len()
+            );
+        }
+        let _ = writeln!(out, "</tbody>\n</table>");
+    }
+
+    if !report.dependency_chains.is_empty() {
+        let _ = writeln!(out, "<h2>Dependency Chains</h2>\n<ul>");
+        for (name, crates) in &report.dependency_chains {
+            let _ = writeln!(out, "<li>{}\n<ul>", escape_html(name));
+            for (krate, chain) in crates {
+                let _ = writeln!(
+                    out,
+                    "<li>{}: {}</li>",
+                    crate_link_html(report, krate),
+                    escape_html(&format_chain(chain))
+                );
+            }
+            let _ = writeln!(out, "</ul>\n</li>");
+        }
+        let _ = writeln!(out, "</ul>");
+    }
+
+    if !report.linker_issues.is_empty() {
+        let _ = writeln!(out, "<h2>Linker Issues</h2>\n<ul>");
+        for issue in &report.linker_issues {
+            let (listed, folded) = limits.apply(&issue.name, &issue.crates);
+            let listed = listed
+                .iter()
+                .map(|krate| crate_link_html(report, krate))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let _ = write!(
+                out,
+                "<li>{} <code>{}</code> ({} crate(s)): {listed}",
+                issue.kind,
+                escape_html(&issue.name),
+                issue.crates.len()
+            );
+            if folded > 0 {
+                let _ = write!(out, " … and {folded} more");
+            }
+            let _ = writeln!(out, "</li>");
+        }
+        let _ = writeln!(out, "</ul>");
+    }
+
+    if !report.resolver_culprits.is_empty() {
+        let _ = writeln!(out, "<h2>Unresolvable Dependencies</h2>\n<ul>");
+        for culprit in &report.resolver_culprits {
+            let (listed, folded) = limits.apply(&culprit.package, &culprit.crates);
+            let listed = listed
+                .iter()
+                .map(|krate| crate_link_html(report, krate))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let requirements = culprit
+                .requirements
+                .iter()
+                .map(|requirement| format!(" <code>{}</code>", escape_html(requirement)))
+                .collect::<String>();
+            let _ = write!(
+                out,
+                "<li><code>{}</code>{requirements} ({} crate(s)): {listed}",
+                escape_html(&culprit.package),
+                culprit.crates.len()
+            );
+            if folded > 0 {
+                let _ = write!(out, " … and {folded} more");
+            }
+            let _ = writeln!(out, "</li>");
+        }
+        let _ = writeln!(out, "</ul>");
+    }
+
+    if !report.known_failures.is_empty() {
+        let _ = writeln!(
+            out,
+            "<h2>Known Failures</h2>\n<table>\n<thead><tr><th>Crates</th><th>Category</th><th>Line</th><th>First Seen</th><th>Status</th><th>Examples</th></tr></thead>\n<tbody>"
+        );
+        for (known, crates) in report.sorted_known_failures() {
+            let (listed, folded) = limits.apply(&known.category, crates);
+            let mut listed = listed
+                .iter()
+                .map(|krate| crate_link_html(report, krate))
+                .collect::<Vec<_>>()
+                .join(", ");
+            if folded > 0 {
+                let _ = write!(listed, " … and {folded} more");
+            }
+            let mut status = known.status.to_string();
+            if let Some(note) = &known.note {
+                let _ = write!(status, ": {}", escape_html(note));
+            }
+            let _ = writeln!(
+                out,
+                "<tr><td>{}</td><td>{}</td><td><code>{}</code></td><td>{}</td><td>{status}</td><td>{listed}</td></tr>",
+                crates.len(),
+                escape_html(&known.category),
+                escape_html(&known.error_line),
+                escape_html(&known.first_seen)
+            );
+        }
+        let _ = writeln!(out, "</tbody>\n</table>");
+    }
+
+    if !report.toolchain_mismatches.is_empty() {
+        let _ = writeln!(
+            out,
+            "<h2>Unexpected Compilers</h2>\n<p>These runs were not built with the compiler of their toolchain.</p>\n<table>\n<thead><tr><th>Crate</th><th>Log</th><th>Expected</th><th>Found</th></tr></thead>\n<tbody>"
+        );
+        for mismatch in &report.toolchain_mismatches {
+            let _ = writeln!(
+                out,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td><code>{}</code></td></tr>",
+                crate_link_html(report, &mismatch.krate),
+                escape_html(&mismatch.log),
+                escape_html(&mismatch.expected),
+                escape_html(&mismatch.found)
+            );
+        }
+        let _ = writeln!(out, "</tbody>\n</table>");
+    }
+
+    if !report.tagged_crates.is_empty() {
+        let _ = writeln!(
+            out,
+            "<h2>Tagged Crates</h2>\n<table>\n<thead><tr><th>Tag</th><th>Crates</th><th>Selected crates and their categories</th></tr></thead>\n<tbody>"
+        );
+        for (tag, crates) in &report.tagged_crates {
+            let listed = crates
+                .iter()
+                .map(|(krate, categories)| {
+                    let categories = categories
+                        .iter()
+                        .map(|category| category_link_html(report, category))
+                        .collect::<Vec<_>>();
+                    format!(
+                        "{} ({})",
+                        crate_link_html(report, krate),
+                        categories.join(", ")
+                    )
+                })
+                .collect::<Vec<_>>();
+            let _ = writeln!(
+                out,
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+                escape_html(tag),
+                report.format_count(crates.len()),
+                if listed.is_empty() {
+                    "none selected".to_string()
+                } else {
+                    listed.join("<br>")
+                }
+            );
+        }
+        let _ = writeln!(out, "</tbody>\n</table>");
+    }
+
+    if !report.annotations.is_empty() {
+        let _ = writeln!(out, "<h2>Annotations</h2>\n<ul>");
+        for (krate, annotation) in &report.annotations {
+            let _ = writeln!(
+                out,
+                "<li>{}: {}</li>",
+                crate_link_html(report, krate),
+                escape_html(&annotation.summary())
+            );
+        }
+        let _ = writeln!(out, "</ul>");
+    }
+
+    if !report.other_groups.is_empty() {
+        let _ = writeln!(
+            out,
+            "<h2>Others by Last Error</h2>\n<table>\n<thead><tr><th>Crates</th><th>Last Error Line</th><th>Examples</th></tr></thead>\n<tbody>"
+        );
+        for group in &report.other_groups {
+            let (listed, folded) = limits.apply("others", &group.crates);
+            let mut listed = listed
+                .iter()
+                .map(|krate| crate_link_html(report, krate))
+                .collect::<Vec<_>>()
+                .join(", ");
+            if folded > 0 {
+                let _ = write!(listed, " … and {folded} more");
+            }
+            let error_line = match &group.error_line {
+                Some(line) => format!("<code>{}</code>", escape_html(line)),
+                None => "<em>no error line</em>".to_string(),
+            };
+            let _ = writeln!(
+                out,
+                "<tr><td>{}</td><td>{error_line}</td><td>{listed}</td></tr>",
+                group.crates.len()
+            );
+        }
+        let _ = writeln!(out, "</tbody>\n</table>");
+    }
+
+    let others = report.other.iter().collect::<Vec<_>>();
+    let (listed, folded) = limits.apply_html("others", &others);
+    let _ = writeln!(out, "<h2>Others ({})</h2>\n<ul>", report.other.len());
+    for (krate, other) in listed {
+        let _ = writeln!(out, "<li>{}</li>", other_crate_html(report, krate, other));
+    }
+    if folded > 0 {
+        let _ = writeln!(
+            out,
+            "<li>… and {folded} more{}</li>",
+            pages_link_html(report, limits, "others", others.len())
+        );
+    }
+    let _ = writeln!(out, "</ul>");
+
+    if !report.reconstructed_logs.is_empty() {
+        let _ = writeln!(
+            out,
+            "<p>{} runs have no log recorded in the results, their logs were located by crater's layout.</p>",
+            report.reconstructed_logs.len()
+        );
+    }
+
+    if !report.missing_runs.is_empty() {
+        let _ = writeln!(
+            out,
+            "<h2>No Run Recorded ({})</h2>\n<ul>",
+            report.missing_runs.len()
+        );
+        for (krate, roles) in &report.missing_runs {
+            let _ = writeln!(
+                out,
+                "<li>{}: {}</li>",
+                crate_link_html(report, krate),
+                escape_html(&roles.join(", "))
+            );
+        }
+        let _ = writeln!(out, "</ul>");
+    }
+
+    for (title, crates) in [
+        ("Yanked Versions", &report.yanked),
+        ("Yanked Versions, Not Analyzed", &report.excluded_yanked),
+    ] {
+        if !crates.is_empty() {
+            let crates = crates
+                .iter()
+                .map(|krate| crate_link_html(report, krate))
+                .collect::<Vec<_>>();
+            let _ = writeln!(out, "<h2>{title}</h2>\n<p>{}</p>", crates.join(", "));
+        }
+    }
+
+    if !report.relaxed_matches.is_empty() {
+        let _ = writeln!(
+            out,
+            "<h2>Only Matching with Relaxed Matching</h2>\n<p>These rules may be broken by changes to the compiler's wording.</p>\n<ul>"
+        );
+        for (name, crates) in &report.relaxed_matches {
+            let _ = writeln!(out, "<li>{}\n<ul>", escape_html(name));
+            for (krate, relaxation) in crates {
+                let _ = writeln!(
+                    out,
+                    "<li>{} ({relaxation})</li>",
+                    crate_link_html(report, krate)
+                );
+            }
+            let _ = writeln!(out, "</ul>\n</li>");
+        }
+        let _ = writeln!(out, "</ul>");
+    }
+
+    if let Some(sizes) = &report.log_sizes {
+        let _ = writeln!(
+            out,
+            "<h2>Log Sizes</h2>\n<p>{} in total</p>",
+            format_bytes(sizes.total_bytes)
+        );
+        if let Some(reuse) = &sizes.reuse {
+            let _ = writeln!(
+                out,
+                "<p>Of the fetched logs {} were downloaded and {} reused from other experiments ({} not downloaded)</p>",
+                reuse.downloaded,
+                reuse.reused,
+                format_bytes(reuse.reused_bytes)
+            );
+        }
+        let _ = writeln!(
+            out,
+            "<table>\n<thead><tr><th>Category</th><th>Size</th></tr></thead>\n<tbody>"
+        );
+        for (name, &bytes) in &sizes.category_bytes {
+            let _ = writeln!(
+                out,
+                "<tr><td>{}</td><td>{}</td></tr>",
+                escape_html(name),
+                format_bytes(bytes)
+            );
+        }
+        let _ = writeln!(
+            out,
+            "</tbody>\n</table>\n<table>\n<thead><tr><th>Largest Logs</th><th>Size</th></tr></thead>\n<tbody>"
+        );
+        for size in &sizes.largest {
+            let _ = writeln!(
+                out,
+                "<tr><td>{} <code>{}</code></td><td>{}</td></tr>",
+                crate_link_html(report, &size.krate),
+                escape_html(&size.log),
+                format_bytes(size.bytes)
+            );
+        }
+        let _ = writeln!(out, "</tbody>\n</table>");
+    }
+
+    if let Some(durations) = &report.durations {
+        let _ = writeln!(
+            out,
+            "<h2>Build Durations</h2>\n<table>\n<thead><tr><th>Slowest Builds</th><th>Duration</th></tr></thead>\n<tbody>"
+        );
+        for duration in &durations.slowest {
+            let _ = writeln!(
+                out,
+                "<tr><td>{} <code>{}</code></td><td>{}</td></tr>",
+                crate_link_html(report, &duration.krate),
+                escape_html(&duration.log),
+                format_duration(duration.seconds)
+            );
+        }
+        let _ = writeln!(out, "</tbody>\n</table>");
+        if !durations.regressions.is_empty() {
+            let _ = writeln!(
+                out,
+                "<table>\n<thead><tr><th>Slower than Baseline</th><th>Baseline</th><th>Candidate</th></tr></thead>\n<tbody>"
+            );
+            for regression in &durations.regressions {
+                let _ = writeln!(
+                    out,
+                    "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    crate_link_html(report, &regression.krate),
+                    format_duration(regression.baseline_seconds),
+                    format_duration(regression.candidate_seconds)
+                );
+            }
+            let _ = writeln!(out, "</tbody>\n</table>");
+        }
+    }
+    write_run_matrix_html(&mut out, report);
+    let _ = writeln!(out, "</body>\n</html>");
+
+    out
+}
+
+/// The selected crates with crater's result of each of their runs and their categories,
+/// filterable by crate name, run results and category.
+fn write_run_matrix_html(out: &mut String, report: &AnalysisReport) {
+    if report.run_results.is_empty() {
+        return;
+    }
+    let crate_categories = report.crate_categories();
+    let run_count = report.run_results.values().map(Vec::len).max().unwrap_or(0);
+    let run_result = |results: &[Option<String>], index: usize| {
+        results
+            .get(index)
+            .cloned()
+            .flatten()
+            .unwrap_or_else(|| "no result".to_string())
+    };
+
+    let _ = writeln!(
+        out,
+        "<h2>Run Matrix</h2>\n<p>crater's result of each run of the selected crates next to their categories.</p>\n<p id=\"run-matrix-filters\">"
+    );
+    let _ = writeln!(
+        out,
+        "<label>Crate <input type=\"search\" data-filter=\"crate\" oninput=\"filterRunMatrix()\"></label>"
+    );
+    let filter_select = |out: &mut String, label: &str, key: &str, values: &BTreeSet<String>| {
+        let _ = write!(
+            out,
+            "<label>{} <select data-filter=\"{key}\" oninput=\"filterRunMatrix()\"><option value=\"\">any</option>",
+            escape_html(label)
+        );
+        for value in values {
+            let value = escape_html(value);
+            let _ = write!(out, "<option value=\"{value}\">{value}</option>");
+        }
+        let _ = writeln!(out, "</select></label>");
+    };
+    for index in 0..run_count {
+        let results = report
+            .run_results
+            .values()
+            .map(|results| run_result(results, index))
+            .collect();
+        filter_select(out, &run_role(index), &format!("run{index}"), &results);
+    }
+    let categories = crate_categories.values().flatten().cloned().collect();
+    filter_select(out, "category", "categories", &categories);
+    let _ = writeln!(out, "</p>");
+
+    let run_headers = (0..run_count)
+        .map(|index| format!("<th>{}</th>", escape_html(&run_role(index))))
+        .collect::<String>();
+    let _ = writeln!(
+        out,
+        "<table id=\"run-matrix\">\n<thead><tr><th>Crate</th><th>crater Result</th>{run_headers}<th>Categories</th></tr></thead>\n<tbody>"
+    );
+    for (krate, results) in &report.run_results {
+        let categories = crate_categories.get(krate).cloned().unwrap_or_default();
+        let mut attributes = format!(" data-crate=\"{}\"", escape_html(krate));
+        let mut cells = String::new();
+        for index in 0..run_count {
+            let result = escape_html(&run_result(results, index));
+            let _ = write!(attributes, " data-run{index}=\"{result}\"");
+            let _ = write!(cells, "<td>{result}</td>");
+        }
+        let _ = write!(
+            attributes,
+            " data-categories=\"{}\"",
+            categories
+                .iter()
+                .map(|category| escape_html(category))
+                .collect::<Vec<_>>()
+                .join("\t")
+        );
+        let crater_result = report
+            .crater_results
+            .get(krate)
+            .map(String::as_str)
+            .unwrap_or(&report.expected_krate_result);
+        let categories = categories
+            .iter()
+            .map(|category| category_link_html(report, category))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let _ = writeln!(
+            out,
+            "<tr{attributes}><td>{}</td><td>{}</td>{cells}<td>{categories}</td></tr>",
+            crate_link_html(report, krate),
+            escape_html(crater_result)
+        );
+    }
+    let _ = writeln!(
+        out,
+        "</tbody>\n</table>\n<script>{RUN_MATRIX_SCRIPT}</script>"
+    );
+}
+
+/// The crate name as code, linking to the crate if its URL is known.
+fn crate_link_markdown(report: &AnalysisReport, krate: &str) -> String {
+    match report.crate_urls.get(krate) {
+        Some(url) => format!("[`{krate}`](<{url}>)"),
+        None if report.is_local_crate(krate) => format!("`{krate}` (local)"),
+        None => format!("`{krate}`"),
+    }
+}
+
+/// The crate name as code, linking to the crate if its URL is known.
+fn crate_link_html(report: &AnalysisReport, krate: &str) -> String {
+    match report.crate_urls.get(krate) {
+        Some(url) => format!(
+            "<a href=\"{}\"><code>{}</code></a>",
+            escape_html(url),
+            escape_html(krate)
+        ),
+        None if report.is_local_crate(krate) => {
+            format!("<code>{}</code> (local)", escape_html(krate))
+        }
+        None => format!("<code>{}</code>", escape_html(krate)),
+    }
+}
+
+/// A crate in the crates by category, with its rank and whether it is new in the category.
+fn category_crate_html(report: &AnalysisReport, category: &str, krate: &str) -> String {
+    let mut listed = crate_link_html(report, krate);
+    if let Some(rank) = report.crate_rank(krate) {
+        let _ = write!(listed, " (#{rank})");
+    }
+    if report.is_new_crate(category, krate) {
+        let _ = write!(listed, " {NEW_BADGE}");
+    }
+    listed
+}
+
+/// An unclassified crate with its crater result and unmatched runs.
+fn other_crate_html(report: &AnalysisReport, krate: &str, other: &OtherCrate) -> String {
+    let runs = other
+        .runs
+        .iter()
+        .map(|run| {
+            format!(
+                "{} <code>{}</code>",
+                escape_html(&run.role),
+                escape_html(&run.log)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(
+        "{} ({}): {runs}",
+        crate_link_html(report, krate),
+        escape_html(&other.res)
+    )
+}
+
+/// The file name of a [separate HTML page](ReportFormat::pages), `page` counting from 1.
+fn page_file_name(report: &AnalysisReport, key: &str, page: usize) -> String {
+    format!("{}-{key}-{page}.html", anchor(&report.experiment))
+}
+
+/// The link to the separate pages of `crates` crates, empty if they are listed in the report itself.
+fn pages_link_html(
+    report: &AnalysisReport,
+    limits: &ExampleLimits,
+    key: &str,
+    crates: usize,
+) -> String {
+    match limits.page_count(crates) {
+        Some(pages) => format!(
+            " (<a href=\"{}\">all {crates} crates on {pages} pages</a>)",
+            escape_html(&page_file_name(report, key, 1))
+        ),
+        None => String::new(),
+    }
+}
+
+/// The separate pages listing `items`, none if they fit the report itself.
+fn render_html_pages(
+    report: &AnalysisReport,
+    limits: &ExampleLimits,
+    key: &str,
+    title: &str,
+    items: &[String],
+) -> Vec<(String, String)> {
+    let Some(page_count) = limits.page_count(items.len()) else {
+        return Vec::new();
+    };
+    let experiment = escape_html(&report.experiment);
+    let navigation = |page: usize| {
+        let links = (1..=page_count)
+            .map(|other| {
+                if other == page {
+                    format!("<strong>{other}</strong>")
+                } else {
+                    format!(
+                        "<a href=\"{}\">{other}</a>",
+                        escape_html(&page_file_name(report, key, other))
+                    )
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        format!("<nav><p>Pages: {links}</p></nav>")
+    };
+
+    items
+        .chunks(limits.page_size)
+        .enumerate()
+        .map(|(index, chunk)| {
+            let page = index + 1;
+            let first = index * limits.page_size + 1;
+            let last = first + chunk.len() - 1;
+            // writing to a String never fails
+            let mut out = String::new();
+            let _ = writeln!(
+                out,
+                "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{title} in Crater Experiment {experiment} ({page}/{page_count})</title>\n<style>{HTML_STYLE}</style>\n</head>\n<body>"
+            );
+            let _ = writeln!(out, "<h1>{title} in Crater Experiment {experiment}</h1>");
+            let _ = writeln!(out, "<p>Crates {first}–{last} of {}</p>", items.len());
+            let _ = writeln!(out, "{}\n<ul>", navigation(page));
+            for item in chunk {
+                let _ = writeln!(out, "<li>{item}</li>");
+            }
+            let _ = writeln!(out, "</ul>\n{}", navigation(page));
+            let _ = writeln!(out, "</body>\n</html>");
+            (page_file_name(report, key, page), out)
+        })
+        .collect()
+}
+
+/// The anchor of a heading or category, its lowercase letters and digits separated by single dashes,
+/// e.g. `e0277` or `task-or-parent-failed-no-space`, so that links to it survive re-rendering the report.
+pub fn anchor(text: &str) -> String {
+    let mut anchor = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c.is_alphanumeric() {
+            anchor.extend(c.to_lowercase());
+        } else if !anchor.is_empty() && !anchor.ends_with('-') {
+            anchor.push('-');
+        }
+    }
+    let length = anchor.trim_end_matches('-').len();
+    anchor.truncate(length);
+    anchor
+}
+
+/// The anchor of a category's entry in the crates by category, e.g. `category-e0277`.
+pub fn category_anchor(category: &str) -> String {
+    format!("category-{}", anchor(category))
+}
+
+/// The category name linking to its entry in the crates by category, if it lists any crates.
+fn category_link_markdown(report: &AnalysisReport, category: &str) -> String {
+    if report.category_crates.contains_key(category) {
+        format!(
+            "[{}](#{})",
+            escape_markdown(category),
+            category_anchor(category)
+        )
+    } else {
+        escape_markdown(category)
+    }
+}
+
+/// The category name linking to its entry in the crates by category, if it lists any crates.
+fn category_link_html(report: &AnalysisReport, category: &str) -> String {
+    if report.category_crates.contains_key(category) {
+        format!(
+            "<a href=\"#{}\">{}</a>",
+            category_anchor(category),
+            escape_html(category)
+        )
+    } else {
+        escape_html(category)
+    }
+}
+
+/// The anchor of a heading, leaving out a trailing count like in `Others (12)` so that it doesn't change
+/// with the results; headings with the same anchor get a numbered suffix.
+fn heading_anchor(title: &str, taken: &mut BTreeSet<String>) -> String {
+    let stable = match title.rsplit_once(" (") {
+        Some((stable, count)) if count.trim_end_matches(')').parse::<usize>().is_ok() => stable,
+        _ => title,
+    };
+    let base = match anchor(stable) {
+        base if base.is_empty() => "section".to_string(),
+        base => base,
+    };
+    let mut anchor = base.clone();
+    for suffix in 2.. {
+        if taken.insert(anchor.clone()) {
+            break;
+        }
+        anchor = format!("{base}-{suffix}");
+    }
+    anchor
+}
+
+/// Puts an anchor before every `##` heading and a linked table of contents before the first one.
+fn add_markdown_contents(report: &str) -> String {
+    let mut taken = BTreeSet::new();
+    let mut contents = String::new();
+    let mut out = String::with_capacity(report.len());
+    let mut contents_at = None;
+    for line in report.lines() {
+        if let Some(title) = line.strip_prefix("## ") {
+            let anchor = heading_anchor(title, &mut taken);
+            contents_at.get_or_insert(out.len());
+            let _ = writeln!(contents, "- [{title}](#{anchor})");
+            let _ = writeln!(out, "<a id=\"{anchor}\"></a>\n");
+        }
+        let _ = writeln!(out, "{line}");
+    }
+    if let Some(index) = contents_at {
+        out.insert_str(index, &format!("Contents:\n\n{contents}\n"));
+    }
+    out
+}
+
+/// Gives every `<h2>` heading an ID and puts a linked table of contents before the first one.
+fn add_html_contents(report: &str) -> String {
+    let mut taken = BTreeSet::new();
+    let mut contents = String::new();
+    let mut out = String::with_capacity(report.len());
+    let mut contents_at = None;
+    for line in report.lines() {
+        if let Some((title, rest)) = line
+            .strip_prefix("<h2>")
+            .and_then(|line| line.split_once("</h2>"))
+        {
+            let anchor = heading_anchor(&unescape_html(title), &mut taken);
+            contents_at.get_or_insert(out.len());
+            let _ = writeln!(contents, "<li><a href=\"#{anchor}\">{title}</a></li>");
+            let _ = writeln!(out, "<h2 id=\"{anchor}\">{title}</h2>{rest}");
+        } else {
+            let _ = writeln!(out, "{line}");
+        }
+    }
+    if let Some(index) = contents_at {
+        out.insert_str(
+            index,
+            &format!("<nav>\n<p>Contents</p>\n<ul>\n{contents}</ul>\n</nav>\n"),
+        );
+    }
+    out
+}
+
+const HTML_STYLE: &str = "body{font-family:sans-serif;margin:2em auto;max-width:70em}\
+table{border-collapse:collapse}\
+th,td{border:1px solid #ccc;padding:.2em .6em;text-align:left}\
+td:last-child,tfoot th:last-child{text-align:right}\
+pre{background:#f6f6f6;overflow-x:auto;padding:.5em}\
+mark{background:#ffe58a}\
+dt{font-weight:bold}\
+.new{background:#c62828;border-radius:.3em;color:#fff;font-size:.75em;font-weight:bold;padding:0 .3em}";
+
+/// Hides the rows of the run matrix not matching its filters, crate names match partially and ignoring case,
+/// the results and categories of the selects exactly.
+const RUN_MATRIX_SCRIPT: &str = "function filterRunMatrix(){\
+const filters=[...document.querySelectorAll('#run-matrix-filters [data-filter]')].filter(filter=>filter.value!=='');\
+for(const row of document.querySelectorAll('#run-matrix tbody tr')){\
+row.hidden=!filters.every(filter=>{const value=row.dataset[filter.dataset.filter];\
+return filter.tagName==='INPUT'?value.toLowerCase().includes(filter.value.toLowerCase()):value.split('\\t').includes(filter.value);});}}";
+
+const NEW_BADGE: &str = "<span class=\"new\">NEW</span>";
+
+pub(crate) fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Reverses [`escape_html`].
+fn unescape_html(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+pub(crate) fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(
+            c,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '|' | '#' | '!'
+        ) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
@@ -0,0 +1,1167 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+use super::{
+    BLOCKED_ON, BuildDurations, ExperimentStatus, FindingMatch, Fingerprint, KnownFailure,
+    LinkerIssue, Popularity, Relaxation, ResolverCulprit, Sample, ToolchainMismatch,
+    crates_io_name, format_duration,
+};
+
+/// Version of the JSON report structure, see [`report_schema`].
+///
+/// Bumped whenever a change to [`AnalysisReport`] could break consumers of the JSON report,
+/// i.e. when fields are removed, renamed or change their meaning.
+pub const REPORT_SCHEMA_VERSION: u32 = 2;
+
+/// The JSON Schema of the JSON report.
+pub fn report_schema() -> schemars::Schema {
+    schemars::schema_for!(AnalysisReport)
+}
+
+/// The category listed for crates none of whose runs matched any target.
+pub const UNCLASSIFIED: &str = "(unclassified)";
+
+/// Crates listed at most in the crates matching the most distinct categories.
+pub const MOST_CATEGORIES_LISTED: usize = 20;
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct AnalysisReport {
+    /// Always [`REPORT_SCHEMA_VERSION`] for reports created by this version
+    pub schema_version: u32,
+    pub experiment: String,
+    /// The crater mode of the experiment, e.g. `build-and-test` or `clippy`, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+    /// The experiment's status and dates as reported by crater, if they could be looked up
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<ExperimentStatus>,
+    pub expected_krate_result: String,
+    pub expected_run_result: String,
+    /// The selection expression used instead of the expected results, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selection: Option<String>,
+    /// Identifies the crater results, rules, selections and tool version the analysis was made with,
+    /// see [`Config::reproducibility_hash`](super::Config::reproducibility_hash)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reproducibility_hash: Option<String>,
+    /// Identifies the whole config the analysis was made with, see [`Config::config_hash`](super::Config::config_hash)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_hash: Option<String>,
+    pub regressed_count: usize,
+    pub interesting_results_count: usize,
+    pub findings: BTreeMap<String, usize>,
+    /// Findings left out of `findings` for this run, e.g. via `--ignore-category`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub ignored_findings: BTreeMap<String, usize>,
+    /// The more specific categories the runs of a category were moved to by a [refinement](super::Config::refinements), sorted
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub refined_categories: BTreeMap<String, Vec<String>>,
+    /// Crates with selected runs none of the targets matched
+    pub other: BTreeMap<String, OtherCrate>,
+    /// The crates in `other` grouped by the normalized last error line of their unmatched runs, largest group first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub other_groups: Vec<OtherGroup>,
+    /// Selected crates with runs that have no result recorded, with the roles of those runs
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub missing_runs: BTreeMap<String, Vec<String>>,
+    /// The logs of runs crater recorded none for, located by crater's layout instead,
+    /// see [`Results::reconstruct_missing_logs`](super::Results::reconstruct_missing_logs)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reconstructed_logs: Vec<String>,
+    /// One representative excerpt per category, taken from the first crate (by name) in the category
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub samples: BTreeMap<String, Sample>,
+    /// The configured descriptions of the found categories
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub descriptions: BTreeMap<String, String>,
+    /// The found categories grouped by their configured owner, see [`Config::owners`](super::Config::owners)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub owners: BTreeMap<String, Vec<String>>,
+    /// The crates with runs matching each category, sorted by name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub category_crates: BTreeMap<String, Vec<String>>,
+    /// Analyzed crates whose version was yanked from crates.io, sorted by name
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub yanked: Vec<String>,
+    /// Crates whose version was yanked from crates.io and that were left out of the analysis, sorted by name
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub excluded_yanked: Vec<String>,
+    /// Mentioned crates from local crate lists or paths, which are labeled as such and never linked, sorted by name
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub local_crates: Vec<String>,
+    /// Where each category matched in the selected runs, ordered by run ID
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub finding_matches: BTreeMap<String, Vec<FindingMatch>>,
+    /// The dependency chain cargo printed for a category's error per crate,
+    /// from the package requiring the broken dependency up to the crate itself
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub dependency_chains: BTreeMap<String, BTreeMap<String, Vec<String>>>,
+    /// Every failure by its [fingerprint](super::failure_fingerprint), to recognize it in other experiments
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fingerprints: BTreeMap<String, Fingerprint>,
+    /// The fingerprinted failures seen in earlier experiments or already triaged, see [`FingerprintStore`](super::FingerprintStore)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub known_failures: BTreeMap<String, KnownFailure>,
+    /// Categories that only matched a crate's selected logs with relaxed matching, by category and crate,
+    /// see [`Config::relaxed_diagnostics`](super::Config::relaxed_diagnostics)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub relaxed_matches: BTreeMap<String, BTreeMap<String, Relaxation>>,
+    /// crater's crate result of every selected crate, e.g. `regressed` or `spurious-regressed`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub crater_results: BTreeMap<String, String>,
+    /// crater's result of each run of every selected crate, the baseline first and the candidate second,
+    /// `None` for runs without a recorded result
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub run_results: BTreeMap<String, Vec<Option<String>>>,
+    /// crates.io or repository URL of every crate mentioned in the report, as far as known
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub crate_urls: BTreeMap<String, String>,
+    /// The further selections classified in the same pass by their name, see [`Config::sections`](super::Config::sections)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub sections: BTreeMap<String, SectionReport>,
+    /// Only present when warnings are configured
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warnings: Option<WarningsReport>,
+    /// What is new compared to the previous comparable analysis, if there is one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comparison: Option<Comparison>,
+    /// Which mentioned crates are among the most downloaded crates, if enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub popularity: Option<Popularity>,
+    /// Separator between groups of three digits in rendered counts, see [`Config::thousands_separator`](super::Config::thousands_separator),
+    /// a rendering setting that is not part of the JSON report
+    #[serde(skip)]
+    pub thousands_separator: String,
+    /// Sizes of the classified logs, to find logs dominating the analysis time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_sizes: Option<LogSizes>,
+    /// Build durations estimated from the log timestamps, if the logs have any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub durations: Option<BuildDurations>,
+    /// The libraries and symbols the linker failed to find in the selected runs, the most affected crates first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub linker_issues: Vec<LinkerIssue>,
+    /// The dependencies cargo failed to resolve in the selected runs, the most affected crates first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub resolver_culprits: Vec<ResolverCulprit>,
+    /// The selected runs whose `rustc --version` banner doesn't fit their toolchain, e.g. of misconfigured experiments
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub toolchain_mismatches: Vec<ToolchainMismatch>,
+    /// The selected crates of each [tag](super::Config::crate_tags_file) with their categories,
+    /// including the tags without selected crates
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tagged_crates: BTreeMap<String, BTreeMap<String, BTreeSet<String>>>,
+    /// Triage notes on crates by crate, written with `annotate` or imported from an earlier analysis,
+    /// see [`AnalysisReport::import_annotations`]
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub annotations: BTreeMap<String, CrateAnnotation>,
+}
+
+/// What a human found out about the failure of a crate.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct CrateAnnotation {
+    /// The [fingerprint](super::failure_fingerprint) of the annotated failure,
+    /// the annotation only carries over to experiments in which the crate fails the same way
+    pub fingerprint: String,
+    /// The category the failure was confirmed to be
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// The experiment the annotation was written for, if it was imported
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub imported_from: Option<String>,
+}
+
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct LogSizes {
+    /// Bytes of all classified logs, including those checked for warnings
+    pub total_bytes: u64,
+    /// Bytes of the selected logs matching each category
+    pub category_bytes: BTreeMap<String, u64>,
+    /// The largest logs, largest first
+    pub largest: Vec<LogSize>,
+    /// How the logs that weren't cached yet were fetched, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reuse: Option<LogReuse>,
+}
+
+/// How the logs that weren't cached yet were fetched, see [`Config::share_logs`](super::Config::share_logs).
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct LogReuse {
+    pub downloaded: usize,
+    /// Logs identical to a log cached for another experiment, taken from the shared log store instead of being downloaded
+    pub reused: usize,
+    /// Bytes of the reused logs
+    pub reused_bytes: u64,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct LogSize {
+    pub krate: String,
+    /// The run's [stable ID](super::run_id)
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub run_id: String,
+    pub log: String,
+    pub bytes: u64,
+}
+
+/// Formats a count with `separator` between groups of three digits, e.g. `12,345` for `,`.
+pub fn format_count(count: usize, separator: &str) -> String {
+    let digits = count.to_string();
+    let mut formatted = String::with_capacity(digits.len() + digits.len() / 3 * separator.len());
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index).is_multiple_of(3) {
+            formatted.push_str(separator);
+        }
+        formatted.push(digit);
+    }
+    formatted
+}
+
+/// `part` as a percentage of `whole`, 0 if `whole` is 0.
+pub fn percent(part: usize, whole: usize) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 * 100.0 / whole as f64
+    }
+}
+
+/// Formats a number of bytes with binary prefixes, e.g. `1.5 MiB`.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+/// Differences to the previous analysis of an experiment with the same selection.
+#[derive(Debug, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct Comparison {
+    /// The experiment compared against
+    pub experiment: String,
+    /// Categories that had no findings in the compared experiment
+    pub new_categories: BTreeSet<String>,
+    /// Per category the crates that were not in the category in the compared experiment
+    pub new_crates: BTreeMap<String, BTreeSet<String>>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct OtherCrate {
+    /// The crate's [stable ID](super::crate_id)
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub id: String,
+    /// The overall result of the crate
+    pub res: String,
+    /// The unmatched runs ordered by their index, without duplicate logs
+    pub runs: Vec<OtherRun>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct OtherGroup {
+    /// The last error line with crate names, paths and numbers replaced, see [`last_error_line`](super::last_error_line),
+    /// or `None` for logs without any error line
+    pub error_line: Option<String>,
+    /// Sorted by name
+    pub crates: Vec<String>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct OtherRun {
+    /// The run's [stable ID](super::run_id)
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub id: String,
+    /// Index into the runs of the crate
+    pub index: usize,
+    /// `baseline` or `candidate` for the first two runs
+    pub role: String,
+    pub log: String,
+}
+
+impl CrateAnnotation {
+    /// The annotation as a single line, e.g. `confirmed as ice; fixed upstream (from pr-123)`.
+    pub fn summary(&self) -> String {
+        let mut summary = self
+            .category
+            .iter()
+            .map(|category| format!("confirmed as {category}"))
+            .chain(self.note.clone())
+            .collect::<Vec<_>>()
+            .join("; ");
+        if let Some(experiment) = &self.imported_from {
+            if !summary.is_empty() {
+                summary.push(' ');
+            }
+            summary.push_str(&format!("(from {experiment})"));
+        }
+        summary
+    }
+}
+
+impl AnalysisReport {
+    /// Whether `previous` selected runs the same way, so that their categories can be compared.
+    pub fn is_comparable(&self, previous: &AnalysisReport) -> bool {
+        self.expected_krate_result == previous.expected_krate_result
+            && self.expected_run_result == previous.expected_run_result
+            && self.selection == previous.selection
+    }
+
+    /// Marks the categories and crates that did not appear in `previous`.
+    pub fn compare_with(&mut self, previous: &AnalysisReport) {
+        let new_categories = self
+            .findings
+            .keys()
+            .filter(|name| !previous.findings.contains_key(*name))
+            .cloned()
+            .collect();
+        let new_crates = self
+            .category_crates
+            .iter()
+            .map(|(name, crates)| {
+                let previous_crates = previous.category_crates.get(name);
+                let new = crates
+                    .iter()
+                    .filter(|krate| {
+                        previous_crates.is_none_or(|previous| !previous.contains(krate))
+                    })
+                    .cloned()
+                    .collect::<BTreeSet<_>>();
+                (name.clone(), new)
+            })
+            .filter(|(_, new)| !new.is_empty())
+            .collect();
+        self.comparison = Some(Comparison {
+            experiment: previous.experiment.clone(),
+            new_categories,
+            new_crates,
+        });
+    }
+
+    /// Whether the category did not appear in the compared experiment.
+    pub fn is_new_category(&self, name: &str) -> bool {
+        self.comparison
+            .as_ref()
+            .is_some_and(|comparison| comparison.new_categories.contains(name))
+    }
+
+    /// Whether the crate was not in the category in the compared experiment.
+    pub fn is_new_crate(&self, category: &str, krate: &str) -> bool {
+        self.comparison.as_ref().is_some_and(|comparison| {
+            comparison
+                .new_crates
+                .get(category)
+                .is_some_and(|crates| crates.contains(krate))
+        })
+    }
+
+    /// The categories of every selected crate, [`UNCLASSIFIED`] for crates with unmatched runs.
+    pub fn crate_categories(&self) -> BTreeMap<String, BTreeSet<String>> {
+        let mut categories = BTreeMap::<String, BTreeSet<String>>::new();
+        for (category, crates) in &self.category_crates {
+            for krate in crates {
+                categories
+                    .entry(krate.clone())
+                    .or_default()
+                    .insert(category.clone());
+            }
+        }
+        for krate in self.other.keys() {
+            categories
+                .entry(krate.clone())
+                .or_default()
+                .insert(UNCLASSIFIED.to_string());
+        }
+        categories
+    }
+
+    /// Sets the [tagged crates](Self::tagged_crates) from `tags`, the crate names of each tag, which match
+    /// selected crates by their crater name or their name on crates.io according to `crate_urls`.
+    pub fn tag_crates(
+        &mut self,
+        tags: &BTreeMap<String, BTreeSet<String>>,
+        crate_urls: &BTreeMap<&str, &str>,
+    ) {
+        let crate_categories = self.crate_categories();
+        self.tagged_crates = tags
+            .iter()
+            .map(|(tag, names)| {
+                let crates = crate_categories
+                    .iter()
+                    .filter(|(krate, _)| {
+                        names.contains(*krate)
+                            || crate_urls
+                                .get(krate.as_str())
+                                .and_then(|url| crates_io_name(url))
+                                .is_some_and(|name| names.contains(name))
+                    })
+                    .map(|(krate, categories)| (krate.clone(), categories.clone()))
+                    .collect();
+                (tag.clone(), crates)
+            })
+            .collect();
+    }
+
+    /// The crates matching at least two distinct categories with their categories, most categories first,
+    /// ties ordered by name; at most [`MOST_CATEGORIES_LISTED`].
+    ///
+    /// Such crates often point at a broken build environment or a compiler bug surfacing in several ways.
+    pub fn crates_by_category_count(&self) -> Vec<(String, BTreeSet<String>)> {
+        let mut crates = self
+            .crate_categories()
+            .into_iter()
+            .map(|(krate, mut categories)| {
+                categories.remove(UNCLASSIFIED);
+                (krate, categories)
+            })
+            .filter(|(_, categories)| categories.len() > 1)
+            .collect::<Vec<_>>();
+        crates.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then_with(|| a.0.cmp(&b.0)));
+        crates.truncate(MOST_CATEGORIES_LISTED);
+        crates
+    }
+
+    /// The number of analyzed crates failing on their own and of those only [blocked](super::BLOCKED_ON)
+    /// on a dependency that failed to build.
+    pub fn independent_and_blocked_counts(&self) -> (usize, usize) {
+        let (blocked, independent): (Vec<_>, Vec<_>) = self
+            .crate_categories()
+            .into_values()
+            .partition(|categories| {
+                categories
+                    .iter()
+                    .all(|category| category.starts_with(BLOCKED_ON))
+            });
+        (independent.len(), blocked.len())
+    }
+
+    /// The dependencies other crates were blocked on with the blocked crates, most blocked crates first.
+    pub fn blocking_dependencies(&self) -> Vec<(&str, &[String])> {
+        let mut dependencies = self
+            .category_crates
+            .iter()
+            .filter_map(|(category, crates)| {
+                Some((category.strip_prefix(BLOCKED_ON)?, crates.as_slice()))
+            })
+            .collect::<Vec<_>>();
+        dependencies.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then_with(|| a.0.cmp(b.0)));
+        dependencies
+    }
+
+    /// Formats a count with the report's [thousands separator](Self::thousands_separator).
+    pub fn format_count(&self, count: usize) -> String {
+        format_count(count, &self.thousands_separator)
+    }
+
+    /// The percentage of the interesting runs with `count` findings of a category
+    /// and the percentage of the crates with the expected result the category was found in.
+    pub fn category_percentages(&self, category: &str, count: usize) -> (f64, f64) {
+        let crates = self.category_crates.get(category).map_or(0, Vec::len);
+        (
+            percent(count, self.interesting_results_count),
+            percent(crates, self.regressed_count),
+        )
+    }
+
+    /// Whether `krate` is a [local crate](Self::local_crates).
+    pub fn is_local_crate(&self, krate: &str) -> bool {
+        self.local_crates
+            .binary_search_by(|local| local.as_str().cmp(krate))
+            .is_ok()
+    }
+
+    /// The number of selected runs none of the targets matched.
+    pub fn unclassified_count(&self) -> usize {
+        self.other.values().map(|other| other.runs.len()).sum()
+    }
+
+    /// The share of the selected runs at least one category matched, 1 if no run was selected.
+    pub fn classified_share(&self) -> f64 {
+        if self.interesting_results_count == 0 {
+            1.0
+        } else {
+            1.0 - self.unclassified_count() as f64 / self.interesting_results_count as f64
+        }
+    }
+
+    /// The findings with the most common categories first, ties ordered by name.
+    pub fn findings_by_count(&self) -> Vec<(&String, &usize)> {
+        let mut findings = self.findings.iter().collect::<Vec<_>>();
+        findings.sort_by(|(name_a, count_a), (name_b, count_b)| {
+            count_b.cmp(count_a).then(name_a.cmp(name_b))
+        });
+        findings
+    }
+
+    /// The download rank of the crate if it is among the considered most downloaded crates.
+    pub fn crate_rank(&self, krate: &str) -> Option<usize> {
+        self.popularity.as_ref()?.ranks.get(krate).copied()
+    }
+
+    /// The number of crates in the category that are among the most downloaded crates.
+    pub fn top_crate_count(&self, category: &str) -> usize {
+        self.category_crates.get(category).map_or(0, |crates| {
+            crates
+                .iter()
+                .filter(|krate| self.crate_rank(krate).is_some())
+                .count()
+        })
+    }
+
+    /// The findings ordered by triage priority: new categories first,
+    /// then those affecting more of the most downloaded crates, then by name.
+    /// Moves the findings of `categories` to the [ignored findings](Self::ignored_findings),
+    /// leaving them out of the headline numbers.
+    pub fn ignore_categories<'a>(&mut self, categories: impl IntoIterator<Item = &'a String>) {
+        for category in categories {
+            if let Some(count) = self.findings.remove(category) {
+                self.ignored_findings.insert(category.clone(), count);
+            }
+        }
+    }
+
+    /// Moves the runs of `category` to the more specific categories they matched in a [refinement](super::Config::refinements),
+    /// given as samples of every matching run by refined category; runs no refined target matched stay in `category`.
+    ///
+    /// Fingerprints and dependency chains keep referring to `category`, as they are not derived again.
+    pub fn refine_category(&mut self, category: &str, refined: BTreeMap<String, Vec<Sample>>) {
+        let refined_runs = refined
+            .values()
+            .flatten()
+            .map(|sample| sample.run_id.as_str())
+            .collect::<BTreeSet<_>>();
+
+        let remaining = self.findings.get(category).copied().unwrap_or_default();
+        let remaining = remaining.saturating_sub(refined_runs.len());
+        let mut matches = self.finding_matches.remove(category).unwrap_or_default();
+        matches.retain(|found| !refined_runs.contains(found.run_id.as_str()));
+        if remaining == 0 {
+            self.findings.remove(category);
+            self.category_crates.remove(category);
+            self.samples.remove(category);
+            self.descriptions.remove(category);
+        } else {
+            self.findings.insert(category.to_string(), remaining);
+            let mut crates = matches
+                .iter()
+                .map(|found| found.krate.clone())
+                .collect::<Vec<_>>();
+            crates.sort();
+            crates.dedup();
+            self.category_crates.insert(category.to_string(), crates);
+            if self
+                .samples
+                .get(category)
+                .is_some_and(|sample| refined_runs.contains(sample.run_id.as_str()))
+            {
+                self.samples.remove(category);
+            }
+            self.finding_matches.insert(category.to_string(), matches);
+        }
+
+        for (refined_category, mut samples) in refined {
+            // keep the sample of the first crate by name, like the analysis
+            samples.sort_by(|a, b| (&a.krate, &a.log).cmp(&(&b.krate, &b.log)));
+            *self.findings.entry(refined_category.clone()).or_default() += samples.len();
+            let crates = self
+                .category_crates
+                .entry(refined_category.clone())
+                .or_default();
+            crates.extend(samples.iter().map(|sample| sample.krate.clone()));
+            crates.sort();
+            crates.dedup();
+            let matches = self
+                .finding_matches
+                .entry(refined_category.clone())
+                .or_default();
+            matches.extend(samples.iter().filter_map(|sample| {
+                Some(FindingMatch {
+                    run_id: sample.run_id.clone(),
+                    krate: sample.krate.clone(),
+                    log: sample.log.clone(),
+                    line_number: sample.excerpt.matched_line_number,
+                    line: sample.excerpt.matched_line()?.to_string(),
+                })
+            }));
+            matches.sort_by(|a, b| a.run_id.cmp(&b.run_id));
+            if let Some(sample) = samples.into_iter().next() {
+                self.samples
+                    .entry(refined_category.clone())
+                    .or_insert(sample);
+            }
+            let refined_categories = self
+                .refined_categories
+                .entry(category.to_string())
+                .or_default();
+            if let Err(index) = refined_categories.binary_search(&refined_category) {
+                refined_categories.insert(index, refined_category);
+            }
+        }
+    }
+
+    /// Takes over the [annotations](Self::annotations) of `previous` on crates failing with the same
+    /// fingerprint in this report, keeping annotations written for this report; returns how many were imported.
+    pub fn import_annotations(&mut self, previous: &AnalysisReport) -> usize {
+        let mut imported = 0;
+        for (krate, annotation) in &previous.annotations {
+            if self.annotations.contains_key(krate) {
+                continue;
+            }
+            let fails_the_same_way = self
+                .fingerprints
+                .get(&annotation.fingerprint)
+                .is_some_and(|failure| failure.crates.binary_search(krate).is_ok());
+            if !fails_the_same_way {
+                continue;
+            }
+            let mut annotation = annotation.clone();
+            // re-analyzing an experiment keeps its own annotations as they are
+            if previous.experiment != self.experiment {
+                annotation
+                    .imported_from
+                    .get_or_insert_with(|| previous.experiment.clone());
+            }
+            self.annotations.insert(krate.clone(), annotation);
+            imported += 1;
+        }
+        imported
+    }
+
+    /// The [fingerprints](Self::fingerprints) of the failures of `krate`, optionally only those of `category`.
+    pub fn crate_fingerprints(
+        &self,
+        krate: &str,
+        category: Option<&str>,
+    ) -> Vec<(&String, &Fingerprint)> {
+        self.fingerprints
+            .iter()
+            .filter(|(_, failure)| category.is_none_or(|category| failure.category == category))
+            .filter(|(_, failure)| {
+                failure
+                    .crates
+                    .binary_search_by(|name| name.as_str().cmp(krate))
+                    .is_ok()
+            })
+            .collect()
+    }
+
+    /// The ignored findings as a single line, e.g. `docker (3), no-space (1)`.
+    pub fn ignored_summary(&self) -> String {
+        self.ignored_findings
+            .iter()
+            .map(|(name, count)| format!("{name} ({count})"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    pub fn sorted_findings(&self) -> Vec<(&String, &usize)> {
+        let mut findings = self.findings.iter().collect::<Vec<_>>();
+        findings.sort_by_key(|(name, _)| {
+            (
+                !self.is_new_category(name),
+                std::cmp::Reverse(self.top_crate_count(name)),
+            )
+        });
+        findings
+    }
+
+    /// The crates of a category ordered by triage priority: new crates first,
+    /// then the most downloaded crates by rank, then by name.
+    pub fn sorted_category_crates(&self, category: &str) -> Vec<&String> {
+        let mut crates = self
+            .category_crates
+            .get(category)
+            .map(|crates| crates.iter().collect::<Vec<_>>())
+            .unwrap_or_default();
+        crates.sort_by_key(|krate| {
+            (
+                !self.is_new_crate(category, krate),
+                self.crate_rank(krate).unwrap_or(usize::MAX),
+            )
+        });
+        crates
+    }
+
+    /// All crates mentioned anywhere in the report.
+    /// The known failures with their crates, most crates first.
+    pub fn sorted_known_failures(&self) -> Vec<(&KnownFailure, &[String])> {
+        let mut known = self
+            .known_failures
+            .iter()
+            .map(|(fingerprint, known)| {
+                let crates = self
+                    .fingerprints
+                    .get(fingerprint)
+                    .map_or(&[][..], |failure| &failure.crates);
+                (known, crates)
+            })
+            .collect::<Vec<_>>();
+        known.sort_by(|a, b| {
+            b.1.len()
+                .cmp(&a.1.len())
+                .then_with(|| a.0.category.cmp(&b.0.category))
+                .then_with(|| a.0.error_line.cmp(&b.0.error_line))
+        });
+        known
+    }
+
+    /// The categories of an owner with their number of crates, most crates first.
+    pub fn owner_categories(&self, owner: &str) -> Vec<(&String, usize)> {
+        let mut categories = self
+            .owners
+            .get(owner)
+            .into_iter()
+            .flatten()
+            .map(|category| {
+                let crates = self.category_crates.get(category).map_or(0, Vec::len);
+                (category, crates)
+            })
+            .collect::<Vec<_>>();
+        categories.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        categories
+    }
+
+    pub fn mentioned_crates(&self) -> BTreeSet<&str> {
+        self.other
+            .keys()
+            .chain(self.missing_runs.keys())
+            .chain(self.samples.values().map(|sample| &sample.krate))
+            .chain(self.category_crates.values().flatten())
+            .chain(self.relaxed_matches.values().flat_map(BTreeMap::keys))
+            .chain(&self.yanked)
+            .chain(&self.excluded_yanked)
+            .chain(self.sections.values().flat_map(|section| &section.other))
+            .chain(
+                self.log_sizes
+                    .iter()
+                    .flat_map(|sizes| sizes.largest.iter().map(|size| &size.krate)),
+            )
+            .chain(self.durations.iter().flat_map(|durations| {
+                let slowest = durations.slowest.iter().map(|duration| &duration.krate);
+                slowest.chain(
+                    durations
+                        .regressions
+                        .iter()
+                        .map(|regression| &regression.krate),
+                )
+            }))
+            .chain(self.annotations.keys())
+            .chain(
+                self.toolchain_mismatches
+                    .iter()
+                    .map(|mismatch| &mismatch.krate),
+            )
+            .chain(self.linker_issues.iter().flat_map(|issue| &issue.crates))
+            .chain(self.tagged_crates.values().flat_map(BTreeMap::keys))
+            .chain(
+                self.resolver_culprits
+                    .iter()
+                    .flat_map(|culprit| &culprit.crates),
+            )
+            .map(String::as_str)
+            .collect()
+    }
+}
+
+/// The findings in the runs of a further selection.
+#[derive(Debug, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct SectionReport {
+    pub expected_krate_result: String,
+    pub expected_run_result: String,
+    pub krate_count: usize,
+    pub run_count: usize,
+    pub findings: BTreeMap<String, usize>,
+    /// Crates with runs none of the targets matched, sorted by name
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub other: Vec<String>,
+}
+
+/// The findings in successful runs, see [`WarningsConfig`](super::WarningsConfig).
+#[derive(Debug, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct WarningsReport {
+    pub expected_krate_result: String,
+    pub expected_run_result: String,
+    pub krate_count: usize,
+    pub run_count: usize,
+    pub findings: BTreeMap<String, usize>,
+}
+
+/// A dependency chain from the package requiring the broken dependency up to the crate,
+/// naming the direct dependency of the crate that pulled it in.
+pub(crate) fn format_chain(chain: &[String]) -> String {
+    let mut formatted = chain.join(" <- ");
+    if let [.., direct, _krate] = chain {
+        formatted.push_str(&format!(" (via {direct})"));
+    }
+    formatted
+}
+
+/// The plain text report
+impl fmt::Display for AnalysisReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Report for Crater Experiment {}", self.experiment)?;
+        if let Some(mode) = &self.mode {
+            writeln!(f, "Mode: {mode}")?;
+        }
+        if let Some(status) = &self.status {
+            writeln!(f, "Status: {status}")?;
+            if status.stale {
+                writeln!(f, "Warning: the results of this experiment may be outdated")?;
+            }
+        }
+        if let Some(selection) = &self.selection {
+            writeln!(f, "Selection: {selection}")?;
+        }
+        if let Some(hash) = &self.reproducibility_hash {
+            writeln!(f, "Reproducibility hash: {hash}")?;
+        }
+        writeln!(
+            f,
+            "{} crates: {}",
+            self.expected_krate_result,
+            self.format_count(self.regressed_count)
+        )?;
+        writeln!(
+            f,
+            "{} runs: {}",
+            self.expected_run_result,
+            self.format_count(self.interesting_results_count)
+        )?;
+
+        writeln!(f, "----------------------------------")?;
+        writeln!(f, "Results:")?;
+        if let Some(comparison) = &self.comparison {
+            writeln!(f, "(NEW compared to {})", comparison.experiment)?;
+        }
+
+        for (name, &count) in self.sorted_findings() {
+            let (of_runs, of_crates) = self.category_percentages(name, count);
+            write!(
+                f,
+                "{name}: {} ({of_runs:.1}% of runs, {of_crates:.1}% of crates)",
+                self.format_count(count)
+            )?;
+            if let Some(popularity) = &self.popularity {
+                write!(
+                    f,
+                    " ({} in top {})",
+                    self.top_crate_count(name),
+                    popularity.top
+                )?;
+            }
+            if self.is_new_category(name) {
+                write!(f, " NEW")?;
+            }
+            writeln!(f)?;
+        }
+
+        let sum: usize = self.findings.values().sum();
+        writeln!(f, "----------------------------------")?;
+        writeln!(f, "sum: {}", self.format_count(sum))?;
+        writeln!(f, "others: {}", self.other.len())?;
+        let (independent, blocked) = self.independent_and_blocked_counts();
+        if blocked > 0 {
+            writeln!(
+                f,
+                "independently broken crates: {independent}, blocked on a dependency: {blocked}"
+            )?;
+        }
+        if !self.ignored_findings.is_empty() {
+            writeln!(f, "ignored: {}", self.ignored_summary())?;
+        }
+        if !self.missing_runs.is_empty() {
+            writeln!(f, "no run recorded: {}", self.missing_runs.len())?;
+        }
+        if !self.reconstructed_logs.is_empty() {
+            writeln!(
+                f,
+                "no log recorded, located by crater's layout: {}",
+                self.reconstructed_logs.len()
+            )?;
+        }
+        if let Some(warnings) = &self.warnings {
+            writeln!(f, "----------------------------------")?;
+            writeln!(
+                f,
+                "Warnings in {} crates: {}",
+                warnings.expected_krate_result, warnings.krate_count
+            )?;
+            writeln!(
+                f,
+                "Warnings in {} runs: {}",
+                warnings.expected_run_result, warnings.run_count
+            )?;
+            for (name, &count) in &warnings.findings {
+                writeln!(f, "{name}: {count}")?;
+            }
+        }
+        if !self.owners.is_empty() {
+            writeln!(f, "----------------------------------")?;
+            writeln!(f, "Owners:")?;
+            for owner in self.owners.keys() {
+                let categories = self
+                    .owner_categories(owner)
+                    .into_iter()
+                    .map(|(category, crates)| format!("{category} ({crates} crates)"))
+                    .collect::<Vec<_>>();
+                writeln!(f, "{owner}: {}", categories.join(", "))?;
+            }
+        }
+        let most_categories = self.crates_by_category_count();
+        if !most_categories.is_empty() {
+            writeln!(f, "----------------------------------")?;
+            writeln!(f, "Crates matching the most categories:")?;
+            for (krate, categories) in &most_categories {
+                let categories = categories.iter().map(String::as_str).collect::<Vec<_>>();
+                writeln!(
+                    f,
+                    "{krate} ({}): {}",
+                    categories.len(),
+                    categories.join(", ")
+                )?;
+            }
+        }
+        for (name, section) in &self.sections {
+            writeln!(f, "----------------------------------")?;
+            writeln!(
+                f,
+                "{name} ({} crates: {}, {} runs: {}):",
+                section.expected_krate_result,
+                section.krate_count,
+                section.expected_run_result,
+                section.run_count
+            )?;
+            for (name, &count) in &section.findings {
+                writeln!(f, "{name}: {count}")?;
+            }
+            writeln!(f, "others: {}", section.other.len())?;
+            if !section.other.is_empty() {
+                writeln!(f, "    {}", section.other.join(", "))?;
+            }
+        }
+        if !self.dependency_chains.is_empty() {
+            writeln!(f, "----------------------------------")?;
+            writeln!(f, "Dependency chains:")?;
+            for (name, crates) in &self.dependency_chains {
+                writeln!(f, "{name}:")?;
+                for (krate, chain) in crates {
+                    writeln!(f, "    {krate}: {}", format_chain(chain))?;
+                }
+            }
+        }
+        if !self.known_failures.is_empty() {
+            writeln!(f, "----------------------------------")?;
+            writeln!(f, "Known failures:")?;
+            for (known, crates) in self.sorted_known_failures() {
+                writeln!(
+                    f,
+                    "{} crates: {} ({}, first seen in {}, {})",
+                    crates.len(),
+                    known.error_line,
+                    known.category,
+                    known.first_seen,
+                    known.status
+                )?;
+                if let Some(note) = &known.note {
+                    writeln!(f, "    note: {note}")?;
+                }
+                writeln!(f, "    {}", crates.join(", "))?;
+            }
+        }
+        if !self.toolchain_mismatches.is_empty() {
+            writeln!(f, "----------------------------------")?;
+            writeln!(f, "Runs with an unexpected compiler:")?;
+            for mismatch in &self.toolchain_mismatches {
+                writeln!(
+                    f,
+                    "{} ({}): expected {}, found {}",
+                    mismatch.krate, mismatch.log, mismatch.expected, mismatch.found
+                )?;
+            }
+        }
+        if !self.tagged_crates.is_empty() {
+            writeln!(f, "----------------------------------")?;
+            writeln!(f, "Tagged crates:")?;
+            for (tag, crates) in &self.tagged_crates {
+                if crates.is_empty() {
+                    writeln!(f, "{tag}: none selected")?;
+                    continue;
+                }
+                writeln!(f, "{tag} ({}):", crates.len())?;
+                for (krate, categories) in crates {
+                    let categories = categories.iter().cloned().collect::<Vec<_>>();
+                    writeln!(f, "  {krate}: {}", categories.join(", "))?;
+                }
+            }
+        }
+        if !self.annotations.is_empty() {
+            writeln!(f, "----------------------------------")?;
+            writeln!(f, "Annotations:")?;
+            for (krate, annotation) in &self.annotations {
+                writeln!(f, "{krate}: {}", annotation.summary())?;
+            }
+        }
+        if !self.other_groups.is_empty() {
+            writeln!(f, "----------------------------------")?;
+            writeln!(f, "Others by last error line:")?;
+            for group in &self.other_groups {
+                writeln!(
+                    f,
+                    "{} crates: {}",
+                    group.crates.len(),
+                    group.error_line.as_deref().unwrap_or("(no error line)")
+                )?;
+                writeln!(f, "    {}", group.crates.join(", "))?;
+            }
+        }
+        writeln!(f, "----------------------------------")?;
+        for (krate, other) in &self.other {
+            match self.crate_rank(krate) {
+                Some(rank) => writeln!(f, "{krate} ({}, #{rank} by downloads):", other.res)?,
+                None => writeln!(f, "{krate} ({}):", other.res)?,
+            }
+            for run in &other.runs {
+                writeln!(f, "    {}: {}", run.role, run.log)?;
+            }
+        }
+        if !self.missing_runs.is_empty() {
+            writeln!(f, "----------------------------------")?;
+            writeln!(f, "No run recorded:")?;
+            for (krate, roles) in &self.missing_runs {
+                writeln!(f, "{krate}: {}", roles.join(", "))?;
+            }
+        }
+        if !self.yanked.is_empty() {
+            writeln!(f, "----------------------------------")?;
+            writeln!(f, "Yanked versions: {}", self.yanked.join(", "))?;
+        }
+        if !self.excluded_yanked.is_empty() {
+            writeln!(f, "----------------------------------")?;
+            writeln!(
+                f,
+                "Yanked versions, not analyzed: {}",
+                self.excluded_yanked.join(", ")
+            )?;
+        }
+        if !self.local_crates.is_empty() {
+            writeln!(f, "----------------------------------")?;
+            writeln!(f, "Local crates: {}", self.local_crates.join(", "))?;
+        }
+        if !self.refined_categories.is_empty() {
+            writeln!(f, "----------------------------------")?;
+            writeln!(f, "Refined categories:")?;
+            for (category, refined) in &self.refined_categories {
+                writeln!(f, "{category} -> {}", refined.join(", "))?;
+            }
+        }
+        if !self.relaxed_matches.is_empty() {
+            writeln!(f, "----------------------------------")?;
+            writeln!(f, "Only matching with relaxed matching:")?;
+            for (name, crates) in &self.relaxed_matches {
+                writeln!(f, "{name}:")?;
+                for (krate, relaxation) in crates {
+                    writeln!(f, "    {krate} ({relaxation})")?;
+                }
+            }
+        }
+        if !self.linker_issues.is_empty() {
+            writeln!(f, "----------------------------------")?;
+            writeln!(f, "Linker issues:")?;
+            for issue in &self.linker_issues {
+                writeln!(
+                    f,
+                    "{} {} ({} crate(s)):",
+                    issue.kind,
+                    issue.name,
+                    issue.crates.len()
+                )?;
+                for krate in &issue.crates {
+                    writeln!(f, "    {krate}")?;
+                }
+            }
+        }
+        if !self.resolver_culprits.is_empty() {
+            writeln!(f, "----------------------------------")?;
+            writeln!(f, "Unresolvable dependencies:")?;
+            for culprit in &self.resolver_culprits {
+                write!(f, "{}", culprit.package)?;
+                if !culprit.requirements.is_empty() {
+                    write!(f, " {}", culprit.requirements.join(", "))?;
+                }
+                writeln!(f, " ({} crate(s)):", culprit.crates.len())?;
+                for krate in &culprit.crates {
+                    writeln!(f, "    {krate}")?;
+                }
+            }
+        }
+        if let Some(sizes) = &self.log_sizes {
+            writeln!(f, "----------------------------------")?;
+            writeln!(f, "Log sizes: {} total", format_bytes(sizes.total_bytes))?;
+            if let Some(reuse) = &sizes.reuse {
+                writeln!(
+                    f,
+                    "Fetched logs: {} downloaded, {} reused from other experiments ({} not downloaded)",
+                    reuse.downloaded,
+                    reuse.reused,
+                    format_bytes(reuse.reused_bytes)
+                )?;
+            }
+            for (name, &bytes) in &sizes.category_bytes {
+                writeln!(f, "{name}: {}", format_bytes(bytes))?;
+            }
+            writeln!(f, "Largest logs:")?;
+            for size in &sizes.largest {
+                writeln!(
+                    f,
+                    "{} {}: {}",
+                    size.krate,
+                    size.log,
+                    format_bytes(size.bytes)
+                )?;
+            }
+        }
+        if let Some(durations) = &self.durations {
+            writeln!(f, "----------------------------------")?;
+            writeln!(f, "Slowest builds:")?;
+            for duration in &durations.slowest {
+                writeln!(
+                    f,
+                    "{} {}: {}",
+                    duration.krate,
+                    duration.log,
+                    format_duration(duration.seconds)
+                )?;
+            }
+            if !durations.regressions.is_empty() {
+                writeln!(f, "Slower than the baseline:")?;
+                for regression in &durations.regressions {
+                    writeln!(
+                        f,
+                        "{}: {} -> {}",
+                        regression.krate,
+                        format_duration(regression.baseline_seconds),
+                        format_duration(regression.candidate_seconds)
+                    )?;
+                }
+            }
+        }
+        if !self.crate_urls.is_empty() {
+            writeln!(f, "----------------------------------")?;
+            writeln!(f, "Crate URLs:")?;
+            for (krate, url) in &self.crate_urls {
+                writeln!(f, "{krate}: {url}")?;
+            }
+        }
+        Ok(())
+    }
+}
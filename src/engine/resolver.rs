@@ -0,0 +1,115 @@
+//! Packages cargo failed to resolve, see [`resolver_failures`].
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::LazyLock,
+};
+
+use regex::Regex;
+
+/// Lines after an error searched for the package name or version requirement it refers to.
+const MAX_DETAIL_LINES: usize = 10;
+
+/// A dependency cargo failed to resolve.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ResolverFailure {
+    pub package: String,
+    /// The version requirement that couldn't be satisfied, e.g. `^1.2`, if cargo reported it
+    pub requirement: Option<String>,
+}
+
+/// A package cargo failed to resolve with all crates failing because of it.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct ResolverCulprit {
+    pub package: String,
+    /// The version requirements that couldn't be satisfied, sorted
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub requirements: Vec<String>,
+    /// Sorted by name
+    pub crates: Vec<String>,
+}
+
+impl ResolverCulprit {
+    /// One culprit per package, the packages affecting the most crates first.
+    pub fn aggregate(failures: BTreeMap<ResolverFailure, BTreeSet<String>>) -> Vec<Self> {
+        let mut packages = BTreeMap::<String, (BTreeSet<String>, BTreeSet<String>)>::new();
+        for (failure, crates) in failures {
+            let (requirements, all_crates) = packages.entry(failure.package).or_default();
+            requirements.extend(failure.requirement);
+            all_crates.extend(crates);
+        }
+        let mut culprits = packages
+            .into_iter()
+            .map(|(package, (requirements, crates))| ResolverCulprit {
+                package,
+                requirements: requirements.into_iter().collect(),
+                crates: crates.into_iter().collect(),
+            })
+            .collect::<Vec<_>>();
+        // the sort is stable, ties keep the order of the packages
+        culprits.sort_by_key(|culprit| std::cmp::Reverse(culprit.crates.len()));
+        culprits
+    }
+}
+
+/// ``error: failed to select a version for the requirement `foo = "^1.2"` ``
+static REQUIREMENT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"failed to select a version for the requirement `([^` =]+) = "([^"]*)"`"#).unwrap()
+});
+/// ``error: failed to select a version for `foo`.``, followed by
+/// ``versions that meet the requirements `^1.2` are: 1.2.0``
+static SELECTION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"failed to select a version for `([^`]+)`").unwrap());
+static MEETING_REQUIREMENTS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"versions that meet the requirements `([^`]+)`").unwrap());
+/// ``error: no matching package named `foo` found``
+static NAMED: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"no matching package named `([^`]+)` found").unwrap());
+/// ``error: no matching package found``, followed by ``searched package name: `foo` ``
+static UNNAMED: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"no matching package found").unwrap());
+static SEARCHED: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"searched package name: `([^`]+)`").unwrap());
+
+/// The dependencies cargo failed to resolve according to `log`, from its
+/// `failed to select a version` and `no matching package` errors.
+pub fn resolver_failures(log: &[u8]) -> BTreeSet<ResolverFailure> {
+    let log = String::from_utf8_lossy(log);
+    let lines = log.lines().collect::<Vec<_>>();
+    // the package or requirement of some errors is reported on one of the following lines
+    let detail = |index: usize, regex: &Regex| {
+        lines
+            .iter()
+            .skip(index + 1)
+            .take(MAX_DETAIL_LINES)
+            .find_map(|line| Some(regex.captures(line)?[1].to_string()))
+    };
+
+    let mut failures = BTreeSet::new();
+    for (index, line) in lines.iter().enumerate() {
+        if let Some(captures) = REQUIREMENT.captures(line) {
+            failures.insert(ResolverFailure {
+                package: captures[1].to_string(),
+                requirement: Some(captures[2].to_string()),
+            });
+        } else if let Some(captures) = SELECTION.captures(line) {
+            failures.insert(ResolverFailure {
+                package: captures[1].to_string(),
+                requirement: detail(index, &MEETING_REQUIREMENTS),
+            });
+        } else if let Some(captures) = NAMED.captures(line) {
+            failures.insert(ResolverFailure {
+                package: captures[1].to_string(),
+                requirement: None,
+            });
+        } else if UNNAMED.is_match(line)
+            && let Some(package) = detail(index, &SEARCHED)
+        {
+            failures.insert(ResolverFailure {
+                package,
+                requirement: None,
+            });
+        }
+    }
+    failures
+}
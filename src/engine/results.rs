@@ -0,0 +1,278 @@
+//! The subset of crater's `results.json` used by the analysis.
+
+use std::{collections::BTreeMap, fmt};
+
+use serde::{
+    Deserialize as _,
+    de::{self, DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor},
+};
+
+use super::crates_io_version;
+
+#[derive(serde::Deserialize)]
+pub struct Results {
+    pub crates: Vec<CrateResult>,
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct CrateResult {
+    pub name: String,
+    pub url: Option<String>,
+    pub res: String,
+    pub runs: Vec<Option<RunResult>>,
+}
+
+impl CrateResult {
+    /// Whether the crate comes from a local crate list (`local/<name>`) or is a path crate (`path/<path>`),
+    /// whose URL, if any, points to the machine crater ran on rather than a registry or repository.
+    pub fn is_local(&self) -> bool {
+        self.name.starts_with("local/") || self.name.starts_with("path/")
+    }
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct RunResult {
+    pub res: String,
+    /// Empty if crater recorded none, see [`Results::reconstruct_missing_logs`]
+    #[serde(default)]
+    pub log: String,
+}
+
+impl Results {
+    /// Parses a `results.json`, only keeping the crates for which `keep` returns true.
+    ///
+    /// The crates are deserialized one at a time, so the skipped ones never pile up in memory,
+    /// as the reports of large experiments list hundreds of thousands of crates of which only few are analyzed.
+    pub fn from_slice_filtered(
+        json: &[u8],
+        keep: impl Fn(&CrateResult) -> bool,
+    ) -> serde_json::Result<Self> {
+        let mut deserializer = serde_json::Deserializer::from_slice(json);
+        let results = ResultsSeed(keep).deserialize(&mut deserializer)?;
+        deserializer.end()?;
+        Ok(results)
+    }
+}
+
+impl Results {
+    /// Fills in the logs missing from runs following crater's layout, `<toolchain>/<crate>`, returning the filled in logs.
+    ///
+    /// The crate part is taken from another run of the crate or derived from its URL or name, and the toolchain
+    /// of a run from the other crates' runs at the same position; runs for which either is unknown keep their empty log.
+    pub fn reconstruct_missing_logs(&mut self) -> Vec<String> {
+        // run index -> toolchain -> runs
+        let mut toolchain_counts = BTreeMap::<usize, BTreeMap<&str, usize>>::new();
+        for krate in &self.crates {
+            for (index, run) in krate.runs.iter().enumerate() {
+                if let Some((toolchain, _)) = run.as_ref().and_then(|run| run.log.split_once('/')) {
+                    *toolchain_counts
+                        .entry(index)
+                        .or_default()
+                        .entry(toolchain)
+                        .or_default() += 1;
+                }
+            }
+        }
+        let toolchains = toolchain_counts
+            .into_iter()
+            .filter_map(|(index, counts)| {
+                let (toolchain, _) = counts.into_iter().max_by_key(|(_, count)| *count)?;
+                Some((index, toolchain.to_string()))
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        let mut reconstructed = Vec::new();
+        for krate in &mut self.crates {
+            let Some(crate_part) = krate
+                .runs
+                .iter()
+                .flatten()
+                .find_map(|run| Some(run.log.split_once('/')?.1.to_string()))
+                .or_else(|| crate_log_part(krate))
+            else {
+                continue;
+            };
+            for (index, run) in krate.runs.iter_mut().enumerate() {
+                if let Some(run) = run
+                    && run.log.is_empty()
+                    && let Some(toolchain) = toolchains.get(&index)
+                {
+                    run.log = format!("{toolchain}/{crate_part}");
+                    reconstructed.push(run.log.clone());
+                }
+            }
+        }
+        reconstructed.sort_unstable();
+        reconstructed
+    }
+}
+
+/// The part of a crate's logs after the toolchain, e.g. `reg/serde-1.0.0` or `gh/rust-lang.cargo`.
+fn crate_log_part(krate: &CrateResult) -> Option<String> {
+    if ["gh/", "git/", "local/", "path/"]
+        .iter()
+        .any(|prefix| krate.name.starts_with(prefix))
+    {
+        return Some(krate.name.clone());
+    }
+    let url = krate.url.as_deref()?;
+    if let Some((name, version)) = crates_io_version(url) {
+        return Some(format!("reg/{name}-{version}"));
+    }
+    let mut parts = url.strip_prefix("https://github.com/")?.split('/');
+    let (owner, repo) = (parts.next()?, parts.next()?);
+    // repositories pinned to a commit are linked as `tree/<sha>`
+    Some(match (parts.next(), parts.next()) {
+        (Some("tree"), Some(sha)) => format!("gh/{owner}.{repo}.{sha}"),
+        _ => format!("gh/{owner}.{repo}"),
+    })
+}
+
+struct ResultsSeed<F>(F);
+
+impl<'de, F: Fn(&CrateResult) -> bool> DeserializeSeed<'de> for ResultsSeed<F> {
+    type Value = Results;
+
+    fn deserialize<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<Results, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de, F: Fn(&CrateResult) -> bool> Visitor<'de> for ResultsSeed<F> {
+    type Value = Results;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("crater results")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Results, A::Error> {
+        let mut crates = None;
+        while let Some(key) = map.next_key::<std::borrow::Cow<'de, str>>()? {
+            if key == "crates" {
+                if crates.is_some() {
+                    return Err(de::Error::duplicate_field("crates"));
+                }
+                crates = Some(map.next_value_seed(CratesSeed(&self.0))?);
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+        Ok(Results {
+            crates: crates.ok_or_else(|| de::Error::missing_field("crates"))?,
+        })
+    }
+}
+
+struct CratesSeed<'f, F>(&'f F);
+
+impl<'de, F: Fn(&CrateResult) -> bool> DeserializeSeed<'de> for CratesSeed<'_, F> {
+    type Value = Vec<CrateResult>;
+
+    fn deserialize<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Vec<CrateResult>, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, F: Fn(&CrateResult) -> bool> Visitor<'de> for CratesSeed<'_, F> {
+    type Value = Vec<CrateResult>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a list of crate results")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<CrateResult>, A::Error> {
+        let mut crates = Vec::new();
+        while let Some(krate) = seq.next_element::<CrateResult>()? {
+            if (self.0)(&krate) {
+                crates.push(krate);
+            }
+        }
+        Ok(crates)
+    }
+}
+
+/// Parses a `results.json` like [`Results::from_slice_filtered`], but keeps every field crater reports
+/// rather than those the analysis reads, so that the kept crates can be written as a `results.json` of the same schema.
+pub fn filter_results_json(
+    json: &[u8],
+    keep: impl Fn(&CrateResult) -> bool,
+) -> serde_json::Result<serde_json::Value> {
+    let mut deserializer = serde_json::Deserializer::from_slice(json);
+    let results = RawResultsSeed(keep).deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(results)
+}
+
+struct RawResultsSeed<F>(F);
+
+impl<'de, F: Fn(&CrateResult) -> bool> DeserializeSeed<'de> for RawResultsSeed<F> {
+    type Value = serde_json::Value;
+
+    fn deserialize<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<serde_json::Value, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de, F: Fn(&CrateResult) -> bool> Visitor<'de> for RawResultsSeed<F> {
+    type Value = serde_json::Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("crater results")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<serde_json::Value, A::Error> {
+        let mut results = serde_json::Map::new();
+        while let Some(key) = map.next_key::<String>()? {
+            let value = if key == "crates" {
+                map.next_value_seed(RawCratesSeed(&self.0))?
+            } else {
+                map.next_value()?
+            };
+            if results.insert(key, value).is_some() {
+                return Err(de::Error::custom("duplicate field in crater results"));
+            }
+        }
+        if !results.contains_key("crates") {
+            return Err(de::Error::missing_field("crates"));
+        }
+        Ok(serde_json::Value::Object(results))
+    }
+}
+
+struct RawCratesSeed<'f, F>(&'f F);
+
+impl<'de, F: Fn(&CrateResult) -> bool> DeserializeSeed<'de> for RawCratesSeed<'_, F> {
+    type Value = serde_json::Value;
+
+    fn deserialize<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<serde_json::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, F: Fn(&CrateResult) -> bool> Visitor<'de> for RawCratesSeed<'_, F> {
+    type Value = serde_json::Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a list of crate results")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<serde_json::Value, A::Error> {
+        let mut crates = Vec::new();
+        while let Some(raw) = seq.next_element::<serde_json::Value>()? {
+            let krate = CrateResult::deserialize(&raw).map_err(de::Error::custom)?;
+            if (self.0)(&krate) {
+                crates.push(raw);
+            }
+        }
+        Ok(serde_json::Value::Array(crates))
+    }
+}
@@ -0,0 +1,165 @@
+use super::{
+    Config, CrateResult, ExpectedResults, Results, RunResult, SectionConfig, SelectExpr,
+    WarningsConfig,
+};
+
+/// A run chosen for analysis.
+#[derive(Debug, Clone, Copy)]
+pub struct SelectedRun<'a> {
+    pub krate: &'a CrateResult,
+    /// Index into the runs of the crate, crater lists the baseline run first and the candidate second
+    pub index: usize,
+    pub run: &'a RunResult,
+}
+
+impl<'a> SelectedRun<'a> {
+    /// Describes which of the crate's runs this is.
+    pub fn role(&self) -> String {
+        run_role(self.index)
+    }
+
+    /// All runs of a crate that exist.
+    fn all_of(krate: &'a CrateResult) -> impl Iterator<Item = SelectedRun<'a>> {
+        krate
+            .runs
+            .iter()
+            .enumerate()
+            .filter_map(move |(index, run)| {
+                Some(SelectedRun {
+                    krate,
+                    index,
+                    run: run.as_ref()?,
+                })
+            })
+    }
+}
+
+/// Describes the run at `index` of a crate, crater lists the baseline run first and the candidate second.
+pub fn run_role(index: usize) -> String {
+    match index {
+        0 => "baseline".to_string(),
+        1 => "candidate".to_string(),
+        index => format!("run {index}"),
+    }
+}
+
+/// Whether any of the crate's runs may be analyzed, be it as selected, missing, warnings or [section](Config::sections) run.
+///
+/// All other crates can be dropped right when parsing the results, see [`Results::from_slice_filtered`].
+pub fn is_relevant_crate(config: &Config, krate: &CrateResult) -> bool {
+    let selected = match &config.select {
+        Some(select) => {
+            SelectedRun::all_of(krate).any(|selected| select.matches(krate, selected.run))
+        }
+        None => config.crate_result.contains(&krate.res),
+    };
+    selected
+        || config
+            .warnings
+            .as_ref()
+            .is_some_and(|warnings| warnings.crate_result.contains(&krate.res))
+        || config
+            .sections
+            .values()
+            .any(|section| section.crate_result.contains(&krate.res))
+}
+
+/// Returns the crates that would be analyzed but have runs without a recorded result,
+/// together with the indices of those runs.
+///
+/// Without a [selection expression](Config::select) these are the crates with the expected crate result,
+/// otherwise the crates with at least one selected run, as a missing run can't be matched against the expression.
+pub fn select_missing_runs<'a>(
+    config: &Config,
+    report: &'a Results,
+) -> Vec<(&'a CrateResult, Vec<usize>)> {
+    report
+        .crates
+        .iter()
+        .filter(|krate| match &config.select {
+            Some(select) => {
+                SelectedRun::all_of(krate).any(|selected| select.matches(krate, selected.run))
+            }
+            None => config.crate_result.contains(&krate.res),
+        })
+        .filter_map(|krate| {
+            let missing = krate
+                .runs
+                .iter()
+                .enumerate()
+                .filter(|(_, run)| run.is_none())
+                .map(|(index, _)| index)
+                .collect::<Vec<_>>();
+            (!missing.is_empty()).then_some((krate, missing))
+        })
+        .collect()
+}
+
+/// Returns the number of crates with the expected crate result
+/// and all of their runs with the expected run result.
+///
+/// When the config has a [selection expression](Config::select), the number of crates
+/// with at least one selected run and all selected runs are returned instead.
+pub fn select_interesting_runs<'a>(
+    config: &Config,
+    report: &'a Results,
+) -> (usize, Vec<SelectedRun<'a>>) {
+    match &config.select {
+        Some(select) => select_by_expr(report, select),
+        None => select_runs(report, &config.crate_result, &config.run_result),
+    }
+}
+
+/// Like [`select_interesting_runs`] but for the successful runs checked for warnings.
+pub fn select_warning_runs<'a>(
+    warnings: &WarningsConfig,
+    report: &'a Results,
+) -> (usize, Vec<SelectedRun<'a>>) {
+    select_runs(report, &warnings.crate_result, &warnings.run_result)
+}
+
+/// Like [`select_interesting_runs`] but for a further [section](Config::sections).
+pub fn select_section_runs<'a>(
+    section: &SectionConfig,
+    report: &'a Results,
+) -> (usize, Vec<SelectedRun<'a>>) {
+    select_runs(report, &section.crate_result, &section.run_result)
+}
+
+fn select_by_expr<'a>(report: &'a Results, select: &SelectExpr) -> (usize, Vec<SelectedRun<'a>>) {
+    let mut crate_count = 0;
+    let mut runs = Vec::new();
+
+    for krate in &report.crates {
+        let selected_before = runs.len();
+        runs.extend(
+            SelectedRun::all_of(krate).filter(|selected| select.matches(krate, selected.run)),
+        );
+        if runs.len() > selected_before {
+            crate_count += 1;
+        }
+    }
+
+    (crate_count, runs)
+}
+
+fn select_runs<'a>(
+    report: &'a Results,
+    crate_result: &ExpectedResults,
+    run_result: &ExpectedResults,
+) -> (usize, Vec<SelectedRun<'a>>) {
+    let mut crate_count = 0;
+
+    let runs = report
+        .crates
+        .iter()
+        .filter(|krate| crate_result.contains(&krate.res))
+        .inspect(|_| {
+            crate_count += 1;
+        })
+        .flat_map(SelectedRun::all_of)
+        .filter(|selected| run_result.contains(&selected.run.res))
+        .collect::<Vec<_>>();
+
+    (crate_count, runs)
+}
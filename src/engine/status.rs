@@ -0,0 +1,138 @@
+//! The status and dates of a crater experiment as reported by crater, see [`ExperimentStatus`].
+
+use std::fmt;
+
+/// What crater reports about an experiment, to tell stale results apart.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct ExperimentStatus {
+    /// e.g. `completed` or `running`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    /// RFC 3339 timestamps, as reported by crater
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<String>,
+    /// Days between the completion (or creation, if not completed) and the analysis
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub age_days: Option<u64>,
+    /// Whether the experiment was older than the configured [maximum age](super::Config::max_experiment_age_days)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub stale: bool,
+}
+
+impl ExperimentStatus {
+    /// Sets the age relative to `now` (a unix timestamp) and whether it exceeds `max_age_days`, 0 never being exceeded.
+    pub fn set_age(&mut self, now: u64, max_age_days: u64) {
+        self.age_days = self
+            .completed_at
+            .as_deref()
+            .or(self.created_at.as_deref())
+            .and_then(parse_rfc3339)
+            .map(|timestamp| now.saturating_sub(timestamp) / (24 * 60 * 60));
+        self.stale = max_age_days > 0 && self.age_days.is_some_and(|age| age > max_age_days);
+    }
+}
+
+/// e.g. `completed, created 2026-09-01T12:00:00Z, completed 2026-09-03T08:00:00Z, 42 days old`
+impl fmt::Display for ExperimentStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts = [
+            self.status.clone(),
+            self.created_at.as_ref().map(|at| format!("created {at}")),
+            self.started_at.as_ref().map(|at| format!("started {at}")),
+            self.completed_at
+                .as_ref()
+                .map(|at| format!("completed {at}")),
+            self.age_days.map(|age| format!("{age} days old")),
+        ];
+        let parts = parts.into_iter().flatten().collect::<Vec<_>>();
+        f.write_str(&parts.join(", "))
+    }
+}
+
+/// Parses an RFC 3339 timestamp like `2026-09-01T12:00:00.123+02:00` into a unix timestamp.
+pub fn parse_rfc3339(timestamp: &str) -> Option<u64> {
+    let (date, time) = timestamp.split_once(['T', 't', ' '])?;
+    let mut date = date.splitn(3, '-').map(str::parse::<i64>);
+    let (year, month, day) = (date.next()?.ok()?, date.next()?.ok()?, date.next()?.ok()?);
+
+    let (time, offset_seconds) = if let Some(time) = time.strip_suffix(['Z', 'z']) {
+        (time, 0)
+    } else {
+        let sign_index = time.rfind(['+', '-'])?;
+        let (time, offset) = time.split_at(sign_index);
+        let sign = if offset.starts_with('-') { -1 } else { 1 };
+        let (hours, minutes) = offset[1..].split_once(':')?;
+        let offset = hours.parse::<i64>().ok()? * 3600 + minutes.parse::<i64>().ok()? * 60;
+        (time, sign * offset)
+    };
+    // fractional seconds don't matter for ages in days
+    let time = time.split_once('.').map_or(time, |(time, _fraction)| time);
+    let mut time = time.splitn(3, ':').map(str::parse::<i64>);
+    let (hour, minute, second) = (time.next()?.ok()?, time.next()?.ok()?, time.next()?.ok()?);
+
+    let seconds = days_from_civil(year, month, day) * 86400 + hour * 3600 + minute * 60 + second
+        - offset_seconds;
+    u64::try_from(seconds).ok()
+}
+
+/// Parses an HTTP date like `Tue, 15 Sep 2026 08:12:31 GMT`, e.g. of a `Last-Modified` header, into a unix timestamp.
+pub fn parse_http_date(date: &str) -> Option<u64> {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    let (_weekday, date) = date.split_once(", ")?;
+    let mut parts = date.split_whitespace();
+    let day = parts.next()?.parse::<i64>().ok()?;
+    let month = parts.next()?;
+    let month = MONTHS.iter().position(|name| *name == month)? as i64 + 1;
+    let year = parts.next()?.parse::<i64>().ok()?;
+    let mut time = parts.next()?.splitn(3, ':').map(str::parse::<i64>);
+    let (hour, minute, second) = (time.next()?.ok()?, time.next()?.ok()?, time.next()?.ok()?);
+    if parts.next()? != "GMT" {
+        return None;
+    }
+
+    let seconds = days_from_civil(year, month, day) * 86400 + hour * 3600 + minute * 60 + second;
+    u64::try_from(seconds).ok()
+}
+
+/// The UTC date of a unix timestamp as `YYYY-MM-DD`.
+pub fn format_date(unix_seconds: u64) -> String {
+    let (year, month, day) = civil_from_days((unix_seconds / 86400) as i64);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// The date in the proleptic Gregorian calendar of a number of days since 1970-01-01,
+/// see <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Days since 1970-01-01 of a date in the proleptic Gregorian calendar,
+/// see <https://howardhinnant.github.io/date_algorithms.html#days_from_civil>.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
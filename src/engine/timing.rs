@@ -0,0 +1,70 @@
+//! Build durations estimated from the timestamps of the log lines, see [`log_duration`].
+
+use std::fmt::Write as _;
+
+use super::parse_rfc3339;
+
+/// The slowest selected runs and the runs that became much slower, see [`AnalysisReport::durations`](super::AnalysisReport::durations).
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct BuildDurations {
+    /// The slowest selected runs, slowest first
+    pub slowest: Vec<CrateDuration>,
+    /// Crates whose candidate run took longer than their baseline run, largest increase first,
+    /// only known for crates with the logs of both runs available
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub regressions: Vec<DurationRegression>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct CrateDuration {
+    pub krate: String,
+    /// The run's [stable ID](super::run_id)
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub run_id: String,
+    pub log: String,
+    pub seconds: u64,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct DurationRegression {
+    pub krate: String,
+    pub baseline_seconds: u64,
+    pub candidate_seconds: u64,
+}
+
+/// Seconds between the first and the last timestamped line of a log, or `None` if less than two lines have a timestamp.
+///
+/// A line is timestamped if it starts with an RFC 3339 timestamp, optionally in brackets,
+/// e.g. `[2026-09-01T12:00:00Z INFO] [stdout] ...`.
+pub fn log_duration(log: &[u8]) -> Option<u64> {
+    let mut lines = log.split(|&c| c == b'\n');
+    let first = lines.by_ref().find_map(line_timestamp)?;
+    let last = lines.rev().find_map(line_timestamp)?;
+    Some(last.saturating_sub(first))
+}
+
+fn line_timestamp(line: &[u8]) -> Option<u64> {
+    let line = line.strip_prefix(b"[").unwrap_or(line);
+    if !line.first().is_some_and(u8::is_ascii_digit) {
+        return None;
+    }
+    let end = line
+        .iter()
+        .position(|&c| c.is_ascii_whitespace() || c == b']')
+        .unwrap_or(line.len());
+    parse_rfc3339(std::str::from_utf8(&line[..end]).ok()?)
+}
+
+/// Formats a number of seconds, e.g. `1h 2m 3s`.
+pub fn format_duration(seconds: u64) -> String {
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    let mut out = String::new();
+    if hours > 0 {
+        let _ = write!(out, "{hours}h ");
+    }
+    if hours > 0 || minutes > 0 {
+        let _ = write!(out, "{minutes}m ");
+    }
+    let _ = write!(out, "{seconds}s");
+    out
+}
@@ -0,0 +1,57 @@
+//! Runs built with another compiler than their toolchain, see [`toolchain_mismatch`].
+
+use std::sync::LazyLock;
+
+use regex::bytes::Regex;
+
+/// `rustc 1.82.0-nightly (a1b2c3d4e 2024-08-01)` as printed by `rustc --version`
+static RUSTC_BANNER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"rustc (\d+\.\d+\.\d+)(?:-(beta|nightly|dev)(?:\.\d+)?)? \(([0-9a-f]{7,40}) (\d{4}-\d{2}-\d{2})\)")
+        .unwrap()
+});
+
+/// A run whose `rustc --version` banner doesn't fit the toolchain crater was supposed to run it with.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct ToolchainMismatch {
+    #[serde(rename = "crate")]
+    pub krate: String,
+    /// The run's [stable ID](super::run_id)
+    pub run_id: String,
+    pub log: String,
+    /// The toolchain of the run, the first component of its log path, e.g. `beta-2026-09-01` or `try#0123abc…`
+    pub expected: String,
+    /// The first `rustc --version` banner in the log
+    pub found: String,
+}
+
+/// The first `rustc --version` banner in `log` if it doesn't fit `toolchain`, the first component of the log's path.
+///
+/// Release channels are compared by the banner's pre-release suffix, versions like `1.80.0` exactly and
+/// CI builds (`master#<sha>`, `try#<sha>`) by their commit hash; other toolchains are never considered mismatched.
+pub fn toolchain_mismatch(toolchain: &str, log: &[u8]) -> Option<String> {
+    // `try%23<sha>` in log paths, without flags like `+rustflags=…`
+    let toolchain = toolchain.replace("%23", "#");
+    let toolchain = toolchain.split('+').next().unwrap_or_default();
+    let captures = RUSTC_BANNER.captures(log)?;
+    let version = std::str::from_utf8(&captures[1]).ok()?;
+    let channel = captures.get(2).map_or("stable", |channel| {
+        std::str::from_utf8(channel.as_bytes()).unwrap_or("")
+    });
+    let hash = std::str::from_utf8(&captures[3]).ok()?;
+
+    let fits = if let Some((_, sha)) = toolchain.split_once('#') {
+        sha.starts_with(hash) || hash.starts_with(sha)
+    } else if let Some(expected_channel) = ["stable", "beta", "nightly"]
+        .into_iter()
+        .find(|name| toolchain == *name || toolchain.starts_with(&format!("{name}-")))
+    {
+        channel == expected_channel
+    } else if toolchain.split('.').count() == 3
+        && toolchain.split('.').all(|part| part.parse::<u32>().is_ok())
+    {
+        version == toolchain && channel == "stable"
+    } else {
+        true
+    };
+    (!fits).then(|| String::from_utf8_lossy(&captures[0]).into_owned())
+}
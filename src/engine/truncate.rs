@@ -0,0 +1,36 @@
+//! Truncation of extremely long log lines, see [`truncate_long_lines`].
+
+use std::borrow::Cow;
+
+/// `log` with every line longer than `max_length` bytes cut to that length (at a character boundary),
+/// followed by a marker like `… [12345 bytes truncated]`; borrowed if no line is too long or `max_length` is 0.
+///
+/// Lines are only shortened, never removed, so that line numbers stay the same.
+pub fn truncate_long_lines(log: &[u8], max_length: usize) -> Cow<'_, [u8]> {
+    if max_length == 0
+        || !log
+            .split(|&c| c == b'\n')
+            .any(|line| line.len() > max_length)
+    {
+        return Cow::Borrowed(log);
+    }
+
+    let mut truncated = Vec::new();
+    for (index, line) in log.split(|&c| c == b'\n').enumerate() {
+        if index > 0 {
+            truncated.push(b'\n');
+        }
+        if line.len() <= max_length {
+            truncated.extend_from_slice(line);
+            continue;
+        }
+        let mut end = max_length;
+        // don't split a UTF-8 encoded character
+        while end > 0 && line[end] & 0b1100_0000 == 0b1000_0000 {
+            end -= 1;
+        }
+        truncated.extend_from_slice(&line[..end]);
+        truncated.extend_from_slice(format!("… [{} bytes truncated]", line.len() - end).as_bytes());
+    }
+    Cow::Owned(truncated)
+}
@@ -0,0 +1,19 @@
+#[derive(thiserror::Error, Debug)]
+#[error("{0}")]
+pub enum AnalysisError {
+    Reqwest(#[from] reqwest::Error),
+    Io(#[from] std::io::Error),
+    Json(#[from] serde_json::Error),
+    TomlDeserialization(toml::de::Error),
+    TomlSerialization(#[from] toml::ser::Error),
+    #[error("Config not found, create one with `init --profile <profile>`")]
+    MissingConfig,
+    #[error("'{0}' does not exist upstream")]
+    NotFound(String),
+    #[error("Invalid config: {0}")]
+    InvalidConfig(String),
+    #[error("Invalid arguments: {0}")]
+    InvalidArguments(String),
+    #[error("Upstream unreachable: {0}")]
+    Unreachable(String),
+}
@@ -0,0 +1,22 @@
+//! The [`FingerprintStore`] shared by all experiments, persisted as `results/fingerprints.json`.
+
+use std::io::ErrorKind;
+
+use crate::{AnalysisError, engine::FingerprintStore};
+
+const STORE_PATH: &str = "results/fingerprints.json";
+
+/// Loads the store, starting with an empty one if there is none yet.
+pub fn load_store() -> Result<FingerprintStore, AnalysisError> {
+    match std::fs::read(STORE_PATH) {
+        Ok(content) => Ok(serde_json::from_slice(&content)?),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(FingerprintStore::default()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+pub fn save_store(store: &FingerprintStore) -> Result<(), AnalysisError> {
+    std::fs::create_dir_all("results")?;
+    std::fs::write(STORE_PATH, serde_json::to_vec_pretty(store)?)?;
+    Ok(())
+}
@@ -0,0 +1,142 @@
+//! Minimal slices of real experiments for regression tests, see [`record_fixture`].
+//!
+//! A fixture is laid out like the cache of the experiment (`results.json` and `logs/<log>/log.txt`),
+//! so that copying it below `results/` lets the analysis run on it without network access.
+
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+
+use indicatif::MultiProgress;
+use reqwest::Client;
+
+use crate::{
+    AnalysisError,
+    cache::{LogStore, MissingLogs, encode_log_key, get_log, get_report_json},
+    engine::{Config, Redactor, Results, filter_results_json},
+};
+
+/// Logs with more lines are truncated to their first 200 and last lines.
+pub const MAX_LOG_LINES: usize = 2000;
+
+/// Lines kept from the start of truncated logs, the setup of the build.
+const HEAD_LINES: usize = 200;
+
+/// What [`record_fixture`] wrote.
+#[derive(Debug)]
+pub struct RecordedFixture {
+    pub dir: PathBuf,
+    pub crates: usize,
+    pub logs: usize,
+    /// Logs of the selected crates that could not be fetched
+    pub missing_logs: Vec<String>,
+}
+
+/// Writes the results of `crates` and their logs (redacted like report lines and truncated to [`MAX_LOG_LINES`])
+/// into `<output>/<experiment>/`, downloading what isn't cached yet.
+pub async fn record_fixture(
+    config: &Config,
+    client: &Client,
+    multi: &MultiProgress,
+    experiment: &str,
+    crates: &BTreeSet<String>,
+    output: &Path,
+) -> Result<RecordedFixture, AnalysisError> {
+    let redactor = config
+        .redactor()
+        .map_err(|err| AnalysisError::InvalidConfig(format!("invalid redact pattern: {err}")))?;
+    let results_json =
+        get_report_json(client, multi, experiment, config.regenerated_reports).await?;
+
+    let results = filter_results_json(&results_json, |krate| crates.contains(&krate.name))?;
+    let mut selected =
+        Results::from_slice_filtered(&results_json, |krate| crates.contains(&krate.name))?;
+    selected.reconstruct_missing_logs();
+    let found = selected
+        .crates
+        .iter()
+        .map(|krate| krate.name.as_str())
+        .collect::<BTreeSet<_>>();
+    let unknown = crates
+        .iter()
+        .filter(|krate| !found.contains(krate.as_str()))
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+    if !unknown.is_empty() {
+        return Err(AnalysisError::InvalidArguments(format!(
+            "{experiment} has no results for {}",
+            unknown.join(", ")
+        )));
+    }
+
+    let dir = output.join(experiment);
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(
+        dir.join("results.json"),
+        serde_json::to_vec_pretty(&results)?,
+    )?;
+
+    let missing = MissingLogs::load(experiment, config.missing_log_expiry());
+    let store = LogStore::new(config.share_logs).with_compressions(&config.compressed_logs);
+    let mut recorded = RecordedFixture {
+        dir: dir.clone(),
+        crates: selected.crates.len(),
+        logs: 0,
+        missing_logs: Vec::new(),
+    };
+    for run in selected
+        .crates
+        .iter()
+        .flat_map(|krate| krate.runs.iter().flatten())
+    {
+        // no rule set pruned logs with, fixtures need the whole log
+        let log = match get_log(client, multi, &store, &missing, experiment, &run.log, "").await {
+            Ok((log, _)) => log,
+            Err(err) => {
+                log::warn!("Failed to get log '{}': {err}", run.log);
+                recorded.missing_logs.push(run.log.clone());
+                continue;
+            }
+        };
+        let log_dir = dir.join("logs").join(encode_log_key(&run.log));
+        std::fs::create_dir_all(&log_dir)?;
+        std::fs::write(log_dir.join("log.txt"), sanitize_log(&log, &redactor))?;
+        recorded.logs += 1;
+    }
+    if let Err(err) = missing.save() {
+        log::warn!("Failed to save the missing logs of {experiment}: {err}");
+    }
+    Ok(recorded)
+}
+
+/// Redacts every line of `log` and truncates it to [`MAX_LOG_LINES`], marking where lines were left out.
+fn sanitize_log(log: &[u8], redactor: &Redactor) -> String {
+    let log = String::from_utf8_lossy(log);
+    let lines = log.lines().collect::<Vec<_>>();
+    let (head, tail) = if lines.len() <= MAX_LOG_LINES {
+        (lines.as_slice(), &[][..])
+    } else {
+        (
+            &lines[..HEAD_LINES],
+            &lines[lines.len() - (MAX_LOG_LINES - HEAD_LINES)..],
+        )
+    };
+
+    let mut sanitized = String::new();
+    for line in head {
+        sanitized.push_str(&redactor.redact(line));
+        sanitized.push('\n');
+    }
+    if !tail.is_empty() {
+        let left_out = lines.len() - MAX_LOG_LINES;
+        sanitized.push_str(&format!(
+            "[... {left_out} lines left out by record-fixture ...]\n"
+        ));
+    }
+    for line in tail {
+        sanitized.push_str(&redactor.redact(line));
+        sanitized.push('\n');
+    }
+    sanitized
+}
@@ -0,0 +1,166 @@
+//! Analyses requested in pull request comments, answered with the Markdown report, see [`read_comment_command`].
+//!
+//! Meant to run in a GitHub Actions workflow triggered by comments, e.g.
+//!
+//! ```yaml
+//! on:
+//!   issue_comment:
+//!     types: [created]
+//! jobs:
+//!   analyze:
+//!     if: github.event.issue.pull_request && contains(github.event.comment.body, '@crater-analysis')
+//!     runs-on: ubuntu-latest
+//!     permissions:
+//!       pull-requests: write
+//!     steps:
+//!       - uses: actions/checkout@v4
+//!       - run: cargo run --release -- github-comment
+//!         env:
+//!           GITHUB_TOKEN: ${{ secrets.GITHUB_TOKEN }}
+//! ```
+
+use reqwest::Client;
+
+use crate::{
+    AnalysisError,
+    engine::{
+        AnalysisReport, BotCommand, ExampleLimits, ReportFormat, parse_bot_command,
+        render_summary_markdown,
+    },
+};
+
+/// The name the bot is addressed by unless `--bot` says otherwise, e.g. `@crater-analysis analyze name=pr-123456`.
+pub const DEFAULT_BOT_NAME: &str = "crater-analysis";
+
+/// GitHub rejects comments with more characters.
+const MAX_COMMENT_LENGTH: usize = 65_536;
+
+/// Number of categories listed in the summary posted instead of a report too long for a comment.
+const TOP_CATEGORIES: usize = 20;
+
+/// Commenters whose commands are followed, the others could otherwise start arbitrary analyses.
+const TRUSTED_ASSOCIATIONS: [&str; 3] = ["OWNER", "MEMBER", "COLLABORATOR"];
+
+/// Where the report of a commented command is posted to.
+#[derive(Debug)]
+pub struct CommentTarget {
+    api_url: String,
+    repository: String,
+    issue: u64,
+    token: String,
+}
+
+#[derive(serde::Deserialize)]
+struct CommentEvent {
+    comment: Comment,
+    issue: Issue,
+    repository: Repository,
+}
+
+#[derive(serde::Deserialize)]
+struct Comment {
+    body: String,
+    #[serde(default)]
+    author_association: String,
+}
+
+#[derive(serde::Deserialize)]
+struct Issue {
+    number: u64,
+}
+
+#[derive(serde::Deserialize)]
+struct Repository {
+    full_name: String,
+}
+
+/// The command addressed to `@bot` in the comment of the GitHub Actions event (`GITHUB_EVENT_PATH`),
+/// with the experiment it names and where to answer; `None` if the comment isn't a command of a trusted commenter.
+pub async fn read_comment_command(
+    client: &Client,
+    bot: &str,
+) -> Result<Option<(BotCommand, String, CommentTarget)>, AnalysisError> {
+    let variable = |name: &str| {
+        std::env::var(name).map_err(|_| {
+            AnalysisError::InvalidConfig(format!("github-comment requires {name} to be set"))
+        })
+    };
+    let event = std::fs::read(variable("GITHUB_EVENT_PATH")?)?;
+    let event = serde_json::from_slice::<CommentEvent>(&event)?;
+    let target = CommentTarget {
+        api_url: std::env::var("GITHUB_API_URL")
+            .unwrap_or_else(|_| "https://api.github.com".to_string()),
+        repository: event.repository.full_name,
+        issue: event.issue.number,
+        token: variable("GITHUB_TOKEN")?,
+    };
+
+    let command = match parse_bot_command(&event.comment.body, bot) {
+        None => {
+            log::info!("The comment is not addressed to @{bot}");
+            return Ok(None);
+        }
+        Some(_) if !TRUSTED_ASSOCIATIONS.contains(&event.comment.author_association.as_str()) => {
+            log::warn!(
+                "Ignoring the command of a commenter associated with the repository as '{}'",
+                event.comment.author_association
+            );
+            return Ok(None);
+        }
+        Some(Err(err)) => {
+            post_comment(client, &target, &format!("Invalid command: {err}")).await?;
+            return Err(AnalysisError::InvalidArguments(err));
+        }
+        Some(Ok(command)) => command,
+    };
+    let experiment = command.experiment(target.issue);
+    Ok(Some((command, experiment, target)))
+}
+
+/// Posts the Markdown report, or its summary if the report is too long for a comment.
+pub async fn post_report(
+    client: &Client,
+    target: &CommentTarget,
+    report: &AnalysisReport,
+    limits: &ExampleLimits,
+) -> Result<(), AnalysisError> {
+    let mut body = ReportFormat::Markdown.render(report, limits);
+    if body.len() > MAX_COMMENT_LENGTH {
+        body = render_summary_markdown(report, TOP_CATEGORIES);
+        body.push_str("\n\nThe full report is too long for a comment.\n");
+    }
+    post_comment(client, target, &body).await
+}
+
+/// Posts that the analysis of the commanded experiment failed, so that the command isn't left unanswered.
+pub async fn post_error(
+    client: &Client,
+    target: &CommentTarget,
+    experiment: &str,
+    err: &AnalysisError,
+) -> Result<(), AnalysisError> {
+    let body = format!(":rotating_light: The analysis of `{experiment}` failed: {err}");
+    post_comment(client, target, &body).await
+}
+
+async fn post_comment(
+    client: &Client,
+    target: &CommentTarget,
+    body: &str,
+) -> Result<(), AnalysisError> {
+    client
+        .post(format!(
+            "{}/repos/{}/issues/{}/comments",
+            target.api_url.trim_end_matches('/'),
+            target.repository,
+            target.issue
+        ))
+        .bearer_auth(&target.token)
+        .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(serde_json::to_vec(&serde_json::json!({ "body": body }))?)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
@@ -0,0 +1,53 @@
+//! Post-processing hooks, see [`Config::hooks`].
+
+use crate::{
+    command::{CommandError, run_command},
+    engine::{AnalysisReport, Config, HookConfig},
+};
+
+/// Runs all configured hooks for the report of an experiment,
+/// returning the names of the failed hooks together with the reason.
+pub async fn run_hooks(config: &Config, report: &AnalysisReport) -> Vec<(String, CommandError)> {
+    if config.hooks.is_empty() {
+        return Vec::new();
+    }
+
+    // the report consists of strings, numbers and maps with string keys only
+    let json = serde_json::to_vec(report).unwrap();
+
+    let results = futures::future::join_all(config.hooks.iter().map(|(name, hook)| {
+        let json = &json;
+        async move {
+            log::info!("Running hook '{name}' for {}", report.experiment);
+            (name, run_hook(hook, &report.experiment, json).await)
+        }
+    }))
+    .await;
+
+    results
+        .into_iter()
+        .filter_map(|(name, result)| match result {
+            Ok(()) => None,
+            Err(err) => {
+                log::error!("Hook '{name}' failed for {}: {err}", report.experiment);
+                Some((name.clone(), err))
+            }
+        })
+        .collect()
+}
+
+async fn run_hook(hook: &HookConfig, experiment: &str, json: &[u8]) -> Result<(), CommandError> {
+    let stdout = run_command(
+        &hook.command,
+        &[("CRATER_ANALYSIS_EXPERIMENT", experiment)],
+        json,
+        hook.timeout_secs,
+    )
+    .await?;
+
+    let stdout = String::from_utf8_lossy(&stdout);
+    if !stdout.trim().is_empty() {
+        log::info!("Hook output: {}", stdout.trim_end());
+    }
+    Ok(())
+}
@@ -0,0 +1,56 @@
+//! Classification of the logs of [crater](https://github.com/rust-lang/crater) experiments.
+//!
+//! The [`engine`] is free of IO and always available, the download cache and the
+//! analysis pipeline require the `runtime` feature (enabled by default), which pulls in
+//! `tokio` and `reqwest`. The `blocking` feature adds synchronous wrappers for tools that aren't async.
+
+pub mod engine;
+
+#[cfg(feature = "runtime")]
+pub mod analysis;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+#[cfg(feature = "runtime")]
+pub mod cache;
+#[cfg(feature = "runtime")]
+pub mod classifiers;
+#[cfg(feature = "runtime")]
+pub mod color;
+#[cfg(feature = "runtime")]
+pub mod command;
+#[cfg(feature = "runtime")]
+pub mod connectivity;
+#[cfg(feature = "runtime")]
+mod error;
+#[cfg(feature = "runtime")]
+pub mod fingerprints;
+#[cfg(feature = "runtime")]
+pub mod fixture;
+#[cfg(feature = "runtime")]
+pub mod github;
+#[cfg(feature = "runtime")]
+pub mod hooks;
+#[cfg(feature = "runtime")]
+pub mod metrics;
+#[cfg(feature = "runtime")]
+pub mod output;
+#[cfg(feature = "runtime")]
+pub mod progress;
+#[cfg(feature = "runtime")]
+pub mod summary;
+#[cfg(feature = "runtime")]
+pub mod update;
+#[cfg(feature = "runtime")]
+pub mod zulip;
+
+#[cfg(feature = "runtime")]
+pub use error::AnalysisError;
+
+/// The user agent of all requests, identifying the tool to crater's and crates.io's servers.
+#[cfg(feature = "runtime")]
+pub static APP_USER_AGENT: &str = concat!(
+    env!("CARGO_PKG_NAME"),
+    "/",
+    env!("CARGO_PKG_VERSION"),
+    " (https://github.com/Skgland/Crater-Analysis)"
+);
@@ -1,250 +1,538 @@
 use std::{
-    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
-    env::args,
-    io::{ErrorKind, Write},
-    path::Path,
-    sync::{Arc, LazyLock},
+    collections::{BTreeMap, BTreeSet},
+    io::{ErrorKind, Write as _},
+    net::SocketAddr,
+    path::PathBuf,
+    sync::{Arc, OnceLock},
     time::Duration,
 };
 
+use clap::{Args, Parser, Subcommand};
+use console::style;
+use crater_analysis::{
+    APP_USER_AGENT, AnalysisError,
+    analysis::{prefetch, refine_analysis, reusable_analysis, run_analysis},
+    cache::{
+        fallback_cache_dir, get_report_json, get_top_crates, list_caches, prepare_experiment_dir,
+        purge_cache, unix_timestamp,
+    },
+    color::ColorMode,
+    command::CommandError,
+    engine::{
+        AnalysisReport, BotCommand, CONFIG_PROFILES, Config, CrateAnnotation, CrateResultCrossTab,
+        ExampleLimits, ExistingAnalysisHandling, Expectations, ExperimentDiff, Popularity,
+        RegeneratedReportHandling, ReportFormat, SelectExpr, TriageStatus, check_expectations,
+        check_experiment_name, dependency_chain, explain_category, filter_results_json,
+        lint_target, near_misses, process_log, relaxed_matches, report_schema, rule_hits,
+        truncate_long_lines,
+    },
+    fingerprints::{load_store, save_store},
+    fixture::record_fixture,
+    github::{CommentTarget, DEFAULT_BOT_NAME, post_error, post_report, read_comment_command},
+    hooks::run_hooks,
+    metrics::serve_metrics,
+    output::{
+        find_latest_analysis, find_previous_analysis, load_analysis, write_analysis, write_badges,
+        write_blacklist, write_report,
+    },
+    progress::PlainProgress,
+    summary::print_summary,
+    update::{check_for_update, install_release, latest_release},
+    zulip::post_summary,
+};
 use futures::StreamExt as _;
-use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
 use indicatif_log_bridge::LogWrapper;
-use memmap2::Mmap;
-use regex::bytes::Regex;
-use reqwest::Client;
-use tempfile::NamedTempFile;
-use tokio::io::AsyncWrite;
-use tokio::io::AsyncWriteExt;
-use tokio::io::BufWriter;
-
-#[derive(thiserror::Error, Debug)]
-#[error("{0}")]
-enum AnalysisError {
-    Reqwest(#[from] reqwest::Error),
-    Io(#[from] std::io::Error),
-    Json(#[from] serde_json::Error),
-    TomlDeserialization(toml::de::Error),
-    #[error("Config not found")]
-    MissingConfig,
-}
-
-static APP_USER_AGENT: &str = concat!(
-    env!("CARGO_PKG_NAME"),
-    "/",
-    env!("CARGO_PKG_VERSION"),
-    " (https://github.com/Skgland/Crater-Analysis)"
-);
-
-#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
-struct Config {
-    crate_result: String,
-    run_result: String,
-    targets: HashMap<String, Vec<Target>>,
-}
-impl Config {
-    fn example() -> Self {
-        const EXAMPLE_TARGETS: &[(&str, &[&str])] = &[
-            (
-                "docker",
-                [
-                    "[INFO] [stderr] Error response from daemon:",
-                    ": no such file or directory",
-                ]
-                .as_slice(),
-            ),
-            (
-                "docker",
-                &[
-                    "[INFO] [stderr] Error response from daemon:",
-                    ": file exists",
-                ],
-            ),
-            ("compile_error!", &["compile_error!"]),
-            (
-                "missing-env-var",
-                &["note: this error originates in the macro `env`"],
-            ),
-            (
-                "delimiter mismatch",
-                &["error: mismatched closing delimiter:"],
-            ),
-            ("no-space", &["no space left on device"]),
-            (
-                "linker-bus-error",
-                &["collect2: fatal error: ld terminated with signal 7 [Bus error]"],
-            ),
-            ("useless-conversion", &["error: this conversion is useless"]),
-            (
-                "build-script",
-                &["[INFO] [stderr] error: failed to run custom build command for"],
-            ),
-            ("download", &["[INFO] [stderr] error: failed to download"]),
-            (
-                "linker-undefined-symbol",
-                &["rust-lld: error: undefined symbol:"],
-            ),
-            (
-                "linker-missing-library",
-                &["rust-lld: error: unable to find library"],
-            ),
-            (
-                "linker-write-output",
-                &[
-                    "rust-lld: error: failed to write output",
-                    "No such file or directory",
-                ],
-            ),
-            (
-                "include_str-missing-file",
-                &["note: this error originates in the macro `include_str`"],
-            ),
-            (
-                "include_bytes-missing-file",
-                &["note: this error originates in the macro `include_bytes`"],
-            ),
-            ("ice", &["error: internal compiler error:"]),
-            (
-                "task or parent failed (no space)",
-                &["this task or one of its parent failed: No space left on device"],
-            ),
-            (
-                "task or parent failed (no space)",
-                &["this task or one of its parent failed: Io Error: No space left on device"],
-            ),
-            (
-                "task or parent failed (failed to clone)",
-                &["this task or one of its parent failed: failed to clone"],
-            ),
-            ("invalid manifest", &["error: failed to parse manifest at"]),
-            ("invalid manifest", &["error: invalid table header"]),
-            (
-                "invalid manifest",
-                &["error: invalid type: ", ", expected "],
-            ),
-            ("invalid lockfile", &["error: failed to parse lock file at"]),
-            (
-                "timeout",
-                &["[ERROR] error running command: no output for 300 seconds"],
-            ),
-            (
-                "checksum mismatch",
-                &["error: checksum for ", " changed between lock files"],
-            ),
-            (
-                "links conflict",
-                &[
-                    "the package ",
-                    " links to the native library ",
-                    ", but it conflicts with a previous package which links to ",
-                    " as well:",
-                ],
-            ),
-            (
-                "links conflict",
-                &["error: Attempting to resolve a dependency with more than one crate with links="],
-            ),
-            (
-                "version selection failed",
-                &["error: failed to select a version for "],
-            ),
-            (
-                "missing dep",
-                &["error: no matching package named ", " found"],
-            ),
-            ("missing dep", &["error: no matching package found"]),
-            (
-                "missing dep",
-                &["no matching package for override ", " found"],
-            ),
-            (
-                "dep removed feature",
-                &[
-                    "the package ",
-                    " depends on ",
-                    ", with features: ",
-                    " but ",
-                    " does not have these features",
-                ],
-            ),
-            (
-                "missing registry",
-                &["registry index was not found in any configuration:"],
-            ),
-            (
-                "cyclic package dependency",
-                &[
-                    "error: cyclic package dependency: package ",
-                    " depends on itself. Cycle:",
-                ],
-            ),
-            (
-                "cyclic feature dependency",
-                &[
-                    "error: cyclic feature dependency: feature ",
-                    " depends on itself",
-                ],
-            ),
-            (
-                "filename too long",
-                &["error: unable to create ", ": File name too long"],
-            ),
-            ("invalid UTF-8", &["stream did not contain valid UTF-8"]),
-        ];
 
-        let mut targets = HashMap::<String, Vec<Target>>::new();
+const DEFAULT_CONFIG_PATH: &str = "analysis-config.toml";
+/// The config file, [`DEFAULT_CONFIG_PATH`] unless the global `--config` option says otherwise.
+static CONFIG_PATH: OnceLock<String> = OnceLock::new();
+/// Number of reports written, posted and passed to hooks at the same time.
+const REPORT_WRITERS: usize = 4;
 
-        for (key, all) in EXAMPLE_TARGETS {
-            targets.entry(key.to_string()).or_default().push(Target {
-                all: all.iter().map(|part| part.to_string()).collect(),
-            });
-        }
+/// Categorizes why crates failed in crater experiments by matching their logs against the configured rules.
+#[derive(Parser)]
+#[command(version)]
+struct Cli {
+    /// When to color the output, `never` if NO_COLOR is set and `auto` otherwise
+    #[arg(
+        long,
+        global = true,
+        value_name = "auto|always|never",
+        display_order = 100
+    )]
+    color: Option<ColorMode>,
+    /// The config file
+    #[arg(
+        long,
+        global = true,
+        value_name = "path",
+        default_value = DEFAULT_CONFIG_PATH,
+        display_order = 100
+    )]
+    config: String,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Analyze the experiments and write their reports
+    Analyze(AnalyzeArgs),
+    /// Download the results and logs of the experiments without analyzing them
+    Prefetch(AnalyzeArgs),
+    /// Write the config of a profile, then analyze the experiments given with it
+    Init(InitArgs),
+    /// Analyze the experiment commanded in the GitHub comment that triggered the workflow, answering with the report
+    GithubComment(GithubCommentArgs),
+    /// Render the reports of already analyzed experiments from their analysis.json
+    Render(RenderArgs),
+    /// List the crates selected in both or only one of two analyzed experiments with their categories
+    Diff(DiffArgs),
+    /// Cross-tabulate crater's crate results against the categories of an analyzed experiment
+    Crosstab(CrosstabArgs),
+    /// Print the rules composing a category with example matches
+    Explain(ExplainArgs),
+    /// Record what was found out about the failure of a crate in the experiment's analysis.json
+    Annotate(AnnotateArgs),
+    /// List or triage the recorded failure fingerprints
+    #[command(subcommand)]
+    Fingerprints(FingerprintsCommand),
+    /// Classify a single log with the configured rules
+    ClassifyLog(ClassifyLogArgs),
+    /// Check that the current rules classify enough of the selected runs of the experiments
+    CheckRules(CheckRulesArgs),
+    /// Check the analysis of an experiment against the expected runs per category
+    Assert(AssertArgs),
+    /// Lint, quote or measure the rules
+    #[command(subcommand)]
+    Rules(RulesCommand),
+    /// Capture the results and logs of a few crates of an experiment as a fixture
+    RecordFixture(RecordFixtureArgs),
+    /// Write the crates of an experiment's results.json with a run matching an expression
+    FilterResults(FilterResultsArgs),
+    /// List or purge the cached experiments
+    #[command(subcommand)]
+    Cache(CacheCommand),
+    /// Purge the cache of an experiment, short for `cache purge`
+    Clean(PurgeArgs),
+    /// Install the latest release if it is newer
+    SelfUpdate(SelfUpdateArgs),
+    /// Print the JSON schema of the analysis
+    Schema,
+}
 
-        Self {
-            crate_result: "error".to_string(),
-            run_result: "error".to_string(),
-            targets,
+#[derive(Args, Default)]
+struct AnalyzeArgs {
+    #[arg(value_name = "experiment", value_parser = experiment_name)]
+    experiments: Vec<String>,
+    /// Also analyze the experiments listed in the file, one per line
+    #[arg(long, value_name = "file")]
+    experiments_file: Vec<String>,
+    /// Analyze experiments again even if their analysis could be reused
+    #[arg(long)]
+    force_reanalyze: bool,
+    /// Compare with this experiment instead of the previous one
+    #[arg(long, value_name = "experiment")]
+    compare_with: Option<String>,
+    /// Leave the categories out of the reports
+    #[arg(long, value_name = "category,...", value_delimiter = ',')]
+    ignore_category: Vec<String>,
+    /// Only split the category of the existing analysis by its refinement
+    #[arg(long, value_name = "category")]
+    refine_category: Option<String>,
+    /// Carry the annotations of an earlier analysis over to crates failing the same way
+    #[arg(long, value_name = "analysis.json")]
+    import_annotations: Option<PathBuf>,
+    /// Rewrite a preliminary report at this interval while the logs are analyzed
+    #[arg(long, value_name = "seconds", value_parser = clap::value_parser!(u64).range(1..))]
+    live_report: Option<u64>,
+    /// Serve Prometheus metrics of the analysis at this address, e.g. 127.0.0.1:9090
+    #[arg(long, value_name = "address")]
+    metrics_addr: Option<SocketAddr>,
+    #[command(flatten)]
+    jobs: JobsArgs,
+    #[command(flatten)]
+    output: OutputDirArgs,
+}
+
+impl AnalyzeArgs {
+    fn has_experiments(&self) -> bool {
+        !self.experiments.is_empty() || !self.experiments_file.is_empty()
+    }
+}
+
+/// `--jobs`, shared by the commands downloading and classifying logs.
+#[derive(Args, Default)]
+struct JobsArgs {
+    /// Logs downloaded and classified at the same time, the available parallelism by default
+    #[arg(long, value_name = "n", value_parser = clap::value_parser!(u64).range(1..))]
+    jobs: Option<u64>,
+}
+
+impl JobsArgs {
+    fn parallelism(&self) -> usize {
+        self.jobs.map_or_else(
+            || std::thread::available_parallelism().map_or(20, |available| available.get()),
+            |jobs| jobs as usize,
+        )
+    }
+}
+
+/// `--output-dir`, shared by the commands writing reports.
+#[derive(Args, Default)]
+struct OutputDirArgs {
+    /// Write the reports to this directory instead of the configured report paths
+    #[arg(long, value_name = "dir")]
+    output_dir: Option<String>,
+}
+
+impl OutputDirArgs {
+    fn apply(&self, mut config: Config) -> Config {
+        if let Some(dir) = &self.output_dir {
+            config.report_path = format!("{}/{{experiment}}.{{ext}}", dir.trim_end_matches('/'));
         }
+        config
     }
 }
 
-#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
-struct Target {
-    all: Vec<String>,
+#[derive(Args)]
+struct InitArgs {
+    /// The profile to write the config of
+    #[arg(long, value_name = "profile", default_value = "example")]
+    profile: String,
+    /// Overwrite an existing config
+    #[arg(long)]
+    force: bool,
+    #[command(flatten)]
+    analyze: AnalyzeArgs,
+}
+
+#[derive(Args)]
+struct GithubCommentArgs {
+    /// The name the bot is addressed by
+    #[arg(long, value_name = "name", default_value = DEFAULT_BOT_NAME)]
+    bot: String,
+    #[command(flatten)]
+    analyze: AnalyzeArgs,
+}
+
+#[derive(Args)]
+struct RenderArgs {
+    #[arg(value_name = "experiment", required = true)]
+    experiments: Vec<String>,
+    #[arg(long, value_name = "html|md|text", default_value = "text")]
+    format: ReportFormat,
+    /// List all crates per category instead of the configured number
+    #[arg(long)]
+    full: bool,
+    #[command(flatten)]
+    output: OutputDirArgs,
+}
+
+#[derive(Args)]
+struct DiffArgs {
+    #[arg(value_name = "old-experiment")]
+    old_experiment: String,
+    #[arg(value_name = "new-experiment")]
+    new_experiment: String,
+    /// Print JSON instead of text
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Args)]
+struct CrosstabArgs {
+    #[arg(value_name = "experiment")]
+    experiment: String,
+    /// Print JSON instead of text
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Args)]
+struct ExplainArgs {
+    #[arg(value_name = "category")]
+    category: String,
+    /// Take the examples from this experiment instead of the latest one with the category
+    #[arg(long, value_name = "experiment")]
+    experiment: Option<String>,
+}
+
+#[derive(Args)]
+struct AnnotateArgs {
+    #[arg(value_name = "experiment")]
+    experiment: String,
+    #[arg(value_name = "crate")]
+    krate: String,
+    /// The category the failure actually belongs to
+    #[arg(long, value_name = "category", required_unless_present = "note")]
+    category: Option<String>,
+    #[arg(long, value_name = "note")]
+    note: Option<String>,
+    /// The failure to annotate, if the crate fails in several ways
+    #[arg(long, value_name = "fingerprint")]
+    fingerprint: Option<String>,
+}
+
+#[derive(Subcommand)]
+enum FingerprintsCommand {
+    /// List the recorded failure fingerprints with their triage status
+    List,
+    /// Set the triage status of a fingerprint, shown in the reports of experiments analyzed afterwards
+    Triage {
+        #[arg(value_name = "fingerprint")]
+        fingerprint: String,
+        #[arg(value_name = "untriaged|reported|expected|fixed")]
+        status: TriageStatus,
+        #[arg(value_name = "note")]
+        note: Vec<String>,
+    },
+}
+
+#[derive(Args)]
+struct ClassifyLogArgs {
+    #[arg(value_name = "file-or-url")]
+    source: String,
+}
+
+#[derive(Args)]
+struct CheckRulesArgs {
+    #[arg(value_name = "experiment", required = true)]
+    experiments: Vec<String>,
+    /// The share of selected runs, between 0 and 1, that must be classified
+    #[arg(long, value_name = "share", value_parser = share)]
+    min_classified: f64,
+    #[command(flatten)]
+    jobs: JobsArgs,
+}
+
+#[derive(Args)]
+struct AssertArgs {
+    #[arg(value_name = "experiment")]
+    experiment: String,
+    /// The TOML file with the expected runs per category
+    #[arg(long, value_name = "file")]
+    expected: String,
+}
+
+#[derive(Subcommand)]
+enum RulesCommand {
+    /// Print every pattern with its non-ASCII and control characters escaped and flag suspicious ones
+    Lint,
+    /// Print each text as a TOML string for use as a pattern, put a `--` before a first text starting with `-`
+    Quote {
+        #[arg(
+            value_name = "text",
+            trailing_var_arg = true,
+            allow_hyphen_values = true
+        )]
+        texts: Vec<String>,
+    },
+    /// Print distinct lines each category matched in the analysis of the experiment
+    Stats(RuleStatsArgs),
+}
+
+#[derive(Args)]
+struct RuleStatsArgs {
+    #[arg(value_name = "experiment")]
+    experiment: String,
+    /// Distinct matched lines printed per category
+    #[arg(long, value_name = "n", default_value_t = RULE_HIT_SAMPLES)]
+    samples: usize,
+    /// Print JSON instead of text
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Args)]
+struct RecordFixtureArgs {
+    #[arg(value_name = "experiment")]
+    experiment: String,
+    /// The crates to capture
+    #[arg(long, value_name = "crate,...", value_delimiter = ',', required = true)]
+    crates: Vec<String>,
+    /// The directory the fixture is written to below `<experiment>/`
+    #[arg(long, value_name = "dir", default_value = "fixtures")]
+    output: PathBuf,
+}
+
+#[derive(Args)]
+struct FilterResultsArgs {
+    #[arg(value_name = "experiment")]
+    experiment: String,
+    /// The selection expression the runs must match
+    #[arg(long, value_name = "expression", value_parser = SelectExpr::parse)]
+    expr: SelectExpr,
+    /// The file to write to instead of stdout
+    #[arg(long, value_name = "file")]
+    output: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+enum CacheCommand {
+    /// List the cached experiments
+    List,
+    /// Remove the cache of an experiment
+    Purge(PurgeArgs),
+}
+
+#[derive(Args)]
+struct PurgeArgs {
+    #[arg(value_name = "experiment")]
+    experiment: String,
+    /// Only remove the logs, keeping the results
+    #[arg(long)]
+    logs_only: bool,
+    /// Download the results and logs again afterwards
+    #[arg(long)]
+    prefetch: bool,
+}
+
+#[derive(Args)]
+struct SelfUpdateArgs {
+    /// Only print the versions
+    #[arg(long)]
+    check: bool,
+}
+
+fn experiment_name(name: &str) -> Result<String, String> {
+    check_experiment_name(name).map(str::to_string)
+}
+
+fn share(value: &str) -> Result<f64, String> {
+    value
+        .parse::<f64>()
+        .ok()
+        .filter(|share| (0.0..=1.0).contains(share))
+        .ok_or_else(|| format!("expected a share between 0 and 1, got '{value}'"))
 }
 
 #[tokio::main(flavor = "multi_thread")]
 async fn main() -> Result<(), AnalysisError> {
-    let logger = env_logger::Builder::new()
+    let cli = Cli::parse();
+    let _ = CONFIG_PATH.set(cli.config);
+    let color = cli.color.unwrap_or_else(ColorMode::from_env);
+    color.apply();
+    let mut logger = env_logger::Builder::new();
+    logger
         .filter_level(log::LevelFilter::Info)
-        .parse_default_env()
-        .build();
+        .parse_default_env();
+    if color != ColorMode::Auto {
+        logger.write_style(color.write_style());
+    }
+    let logger = logger.build();
 
     let multi = MultiProgress::new();
     LogWrapper::new(multi.clone(), logger).try_init().unwrap();
 
-    let config_path = "analysis-config.toml";
-    let config = Arc::new(match std::fs::read_to_string(config_path) {
-        Ok(content) => match toml::from_str::<Config>(&content) {
-            Ok(content) => content,
-            Err(err) => {
-                println!("Failed to deserialize config '{config_path}': {err}");
-                return Err(AnalysisError::TomlDeserialization(err));
+    match cli.command {
+        Command::Analyze(args) => analyze(args, false, &multi, None).await,
+        Command::Prefetch(args) => analyze(args, true, &multi, None).await,
+        Command::Init(args) => {
+            init(&args)?;
+            if !args.analyze.has_experiments() {
+                return Ok(());
             }
-        },
-        Err(err) if err.kind() == ErrorKind::NotFound => {
-            let default_config = toml::to_string_pretty(&Config::example()).unwrap();
-
-            std::fs::write(config_path, default_config).unwrap();
-
-            return Err(AnalysisError::MissingConfig);
+            analyze(args.analyze, false, &multi, None).await
         }
-        Err(err) => {
-            return Err(AnalysisError::Io(err));
+        Command::GithubComment(args) => github_comment(args, &multi).await,
+        Command::Render(args) => render(args).await,
+        Command::Diff(args) => diff(args).await,
+        Command::Crosstab(args) => crosstab(args).await,
+        Command::Explain(args) => explain(args).await,
+        Command::Annotate(args) => annotate(args).await,
+        Command::Fingerprints(command) => fingerprints_command(command),
+        Command::ClassifyLog(args) => classify_log(args).await,
+        Command::CheckRules(args) => check_rules(args, &multi).await,
+        Command::Assert(args) => assert_expectations(args).await,
+        Command::Rules(command) => rules_command(command).await,
+        Command::RecordFixture(args) => record_fixture_command(args, &multi).await,
+        Command::FilterResults(args) => filter_results(args, &multi).await,
+        Command::Cache(CacheCommand::List) => list_caches_command(),
+        Command::Cache(CacheCommand::Purge(args)) | Command::Clean(args) => {
+            purge_cache_command(args, &multi).await
+        }
+        Command::SelfUpdate(args) => self_update(args).await,
+        Command::Schema => {
+            println!("{}", serde_json::to_string_pretty(&report_schema())?);
+            Ok(())
         }
+    }
+}
+
+/// Analyzes the experiments, including the one of a `github-comment` command, and writes their reports,
+/// or only downloads their results and logs if `prefetch_only`.
+async fn analyze(
+    args: AnalyzeArgs,
+    prefetch_only: bool,
+    multi: &MultiProgress,
+    github_comment: Option<&(BotCommand, String, CommentTarget)>,
+) -> Result<(), AnalysisError> {
+    let mut experiments = args.experiments;
+    for path in &args.experiments_file {
+        experiments.extend(read_experiments_file(path)?);
+    }
+    let force_reanalyze = args.force_reanalyze;
+    let mut compare_with = args.compare_with;
+    let mut ignored_categories = args
+        .ignore_category
+        .iter()
+        .map(|name| name.trim().to_string())
+        .collect::<BTreeSet<_>>();
+    let live_report = args.live_report.map(Duration::from_secs);
+    let refine_category = args.refine_category;
+    let metrics_addr = args.metrics_addr;
+    let annotations_from = match &args.import_annotations {
+        Some(path) => {
+            let content = std::fs::read(path)?;
+            Some(serde_json::from_slice::<AnalysisReport>(&content)?)
+        }
+        None => None,
+    };
+
+    if let Some((command, experiment, _)) = github_comment {
+        experiments.push(experiment.clone());
+        compare_with = compare_with.or_else(|| command.compare_with.clone());
+        ignored_categories.extend(command.ignore.iter().cloned());
+    }
+
+    // the same experiment analyzed twice at once would race on its cache and reports
+    let mut unique = BTreeSet::new();
+    let duplicates = experiments
+        .into_iter()
+        .filter(|experiment| !unique.insert(experiment.clone()))
+        .collect::<BTreeSet<_>>();
+    if !duplicates.is_empty() {
+        log::warn!(
+            "{} given more than once, analyzing each experiment only once",
+            duplicates.into_iter().collect::<Vec<_>>().join(", ")
+        );
+    }
+    let experiments = unique;
+
+    let config = Arc::new(args.output.apply(load_config()?));
+    let experiment_configs = load_experiment_configs(&config)?
+        .into_iter()
+        .map(|(path, config)| {
+            (
+                path,
+                Arc::new(args.output.apply(Arc::unwrap_or_clone(config))),
+            )
+        })
+        .collect::<BTreeMap<_, _>>();
+    let plain_progress = (!console::Term::stderr().is_term()).then(|| {
+        let plain = PlainProgress::new(Duration::from_secs(config.progress_interval_secs));
+        multi.set_draw_target(ProgressDrawTarget::term_like(Box::new(plain.clone())));
+        plain
     });
+    let fingerprint_store = Arc::new(tokio::sync::Mutex::new(load_store()?));
+    if let Some(addr) = metrics_addr {
+        serve_metrics(addr).await?;
+        log::info!("Serving metrics on http://{addr}/metrics");
+    }
 
-    let parallelism = std::thread::available_parallelism().map_or(20, |available| available.get());
+    let parallelism = args.jobs.parallelism();
 
     log::info!("Using a parallelism value of {parallelism}");
     log::info!("User-Agent: {APP_USER_AGENT}");
@@ -253,374 +541,998 @@ async fn main() -> Result<(), AnalysisError> {
         .user_agent(APP_USER_AGENT)
         .build()
         .unwrap();
+    if config.check_for_updates {
+        // warns while the analysis runs rather than delaying it
+        let client = client.clone();
+        tokio::spawn(async move { check_for_update(&client).await });
+    }
 
-    let experiments = BTreeSet::from_iter(args().skip(1));
-
-    let experiments_pb = multi.add(ProgressBar::new(experiments.len() as u64).with_message("Processing experiments"));
+    let experiments_pb = multi
+        .add(ProgressBar::new(experiments.len() as u64).with_message("Processing experiments"));
     experiments_pb.set_style(
         ProgressStyle::with_template("{msg} {wide_bar} {human_pos}/{human_len}").unwrap(),
     );
 
-    let reports = futures::stream::iter(experiments)
+    // reports are written by their own tasks, so that slow disks don't stall the analysis of other experiments
+    let (report_tx, report_rx) = tokio::sync::mpsc::channel(REPORT_WRITERS);
+    let writer = tokio::spawn(write_reports(
+        report_rx,
+        client.clone(),
+        experiments_pb.clone(),
+    ));
+
+    let analyses = futures::stream::iter(experiments)
         .map(|experiment| {
             let multi = multi.clone();
-            let config = config.clone();
+            let config = match config.experiment_config_path(&experiment) {
+                Some(path) => {
+                    log::info!("Using config '{}' for {experiment}", path.display());
+                    experiment_configs[&path].clone()
+                }
+                None => config.clone(),
+            };
             let client = client.clone();
             let experiments_pb = experiments_pb.clone();
+            let compare_with = compare_with.clone();
+            let refine_category = &refine_category;
+            let ignored_categories = &ignored_categories;
+            let annotations_from = &annotations_from;
+            let fingerprint_store = fingerprint_store.clone();
+            let report_tx = report_tx.clone();
 
             async move {
                 let report_ps = multi.add(ProgressBar::new_spinner());
-                let report = run_analysis(
-                    &config,
-                    &client,
-                    &experiment,
-                    &report_ps,
-                    &multi,
-                    parallelism,
-                )
-                .await?;
+                if prefetch_only {
+                    prefetch(
+                        &config,
+                        &client,
+                        &experiment,
+                        &report_ps,
+                        &multi,
+                        parallelism,
+                    )
+                    .await?;
+                    experiments_pb.inc(1);
+                    return Ok(());
+                }
+                let mut report = match refine_category {
+                    Some(category) => {
+                        refine_analysis(
+                            &config,
+                            &client,
+                            &experiment,
+                            category,
+                            &report_ps,
+                            &multi,
+                            parallelism,
+                        )
+                        .await?
+                    }
+                    None => {
+                        let reused = match config.existing_analyses {
+                            ExistingAnalysisHandling::Reuse if !force_reanalyze => {
+                                reusable_analysis(&config, &client, &multi, &experiment).await?
+                            }
+                            _ => None,
+                        };
+                        match reused {
+                            Some(report) => {
+                                log::info!(
+                                    "Reusing the analysis of {experiment}, which is up to date, use --force-reanalyze to analyze it again"
+                                );
+                                report
+                            }
+                            None => {
+                                run_analysis(
+                                    &config,
+                                    &client,
+                                    &experiment,
+                                    &report_ps,
+                                    &multi,
+                                    parallelism,
+                                    live_report,
+                                )
+                                .await?
+                            }
+                        }
+                    }
+                };
+                report.ignore_categories(ignored_categories);
+                let previous = match compare_with {
+                    Some(previous) => Some(load_analysis(&previous).await?),
+                    None => find_previous_analysis(&report).await?,
+                };
+                if let Some(previous) = previous {
+                    report.compare_with(&previous);
+                }
+                {
+                    let mut store = fingerprint_store.lock().await;
+                    store.annotate(&mut report);
+                    if let Err(err) = save_store(&store) {
+                        log::warn!("Failed to save the failure fingerprints: {err}");
+                    }
+                }
+                if let Some(previous) = annotations_from {
+                    let imported = report.import_annotations(previous);
+                    log::info!(
+                        "Imported {imported} annotation(s) from {} into {experiment}",
+                        previous.experiment
+                    );
+                }
+                if config.top_crates > 0 {
+                    let top_crates = get_top_crates(&client, &multi, config.top_crates).await?;
+                    report.popularity = Some(Popularity::new(&top_crates, &report.crate_urls));
+                }
                 report_ps.set_message(format!(
                     "Writing report for experiment {}",
                     report.experiment
                 ));
-                let path = format!("results/{experiment}/{experiment}.report");
-                let file = tokio::fs::File::create(&path).await?;
-                let mut buffered = BufWriter::new(file);
-                report.print_report(&mut buffered).await?;
-                buffered.flush().await?;
-                report_ps
-                    .finish_with_message(format!("Report for {experiment} written to '{path}'"));
-                experiments_pb.inc(1);
+                // only fails if the writer panicked, which surfaces when joining it
+                let _ = report_tx.send((report, report_ps, config)).await;
                 Ok(())
             }
         })
         .buffer_unordered(5)
         .collect::<Vec<Result<_, AnalysisError>>>()
         .await;
+    drop(report_tx);
+    let written = writer.await.unwrap();
 
-    for report in reports {
-        report?;
+    for analysis in analyses {
+        analysis?;
+    }
+    let mut failed_hooks = Vec::new();
+    let mut finished_reports = Vec::new();
+    for report in written {
+        let (report, path, failed) = report?;
+        finished_reports.push((report, path));
+        failed_hooks.extend(failed);
     }
 
-    Ok(())
-}
+    if let Some(plain) = &plain_progress {
+        plain.finish();
+    }
 
-async fn run_analysis(
-    config: &Arc<Config>,
-    client: &Client,
-    experiment: &str,
-    report_ps: &ProgressBar,
-    multi: &MultiProgress,
-    parallelism: usize,
-) -> Result<AnalysisReport, AnalysisError> {
-    if !std::fs::exists(format!("results/{experiment}"))? {
-        std::fs::create_dir_all(format!("results/{experiment}"))?;
-        std::fs::write(
-            format!("results/{experiment}/CACHEDIR.TAG"),
-            CACHEDIR_TAG_CONTENT,
-        )?;
+    finished_reports.sort_by(|(a, _), (b, _)| a.experiment.cmp(&b.experiment));
+    for (report, path) in &finished_reports {
+        print_summary(report, path);
     }
 
-    report_ps.set_message(format!("Getting Crater Report for {experiment}"));
-    report_ps.enable_steady_tick(Duration::from_millis(100));
-    let report = get_report(client, multi, experiment).await?;
-    report_ps.set_message(format!("Processing Crater Report for {experiment}"));
+    if let Some((_, _, target)) = github_comment {
+        for (report, _) in &finished_reports {
+            post_report(&client, target, report, &config.example_limits()).await?;
+        }
+    }
 
-    let mut other = Vec::new();
+    if let Some(dir) = fallback_cache_dir() {
+        println!(
+            "{} the cache below 'results/' was not writable, downloads of this run were cached in '{}' instead",
+            style("warning:").red().bold(),
+            dir.display()
+        );
+    }
 
-    let mut regressed_count = 0;
+    if !failed_hooks.is_empty() {
+        log::error!("{} hook run(s) failed:", failed_hooks.len());
+        for (experiment, hook, err) in failed_hooks {
+            log::error!("  {hook} for {experiment}: {err}");
+        }
+    }
 
-    let interesting_runs = report
-        .crates
-        .iter()
-        .filter(|krate| krate.res == config.crate_result)
-        .inspect(|_| {
-            regressed_count += 1;
-        })
-        .flat_map(|krate| krate.runs.iter().flatten().map(|run| (&krate.name, run)))
-        .filter(|(_, run)| run.res == config.run_result)
-        .collect::<Vec<_>>();
-
-    let interesting_results_count = interesting_runs.len();
-    let run_pb = multi.add(
-        ProgressBar::new(interesting_results_count as u64)
-            .with_message(format!("Processing logs for {experiment}")),
-    );
-    run_pb.set_style(
-        ProgressStyle::with_template("{msg} {wide_bar} {human_pos}/{human_len} ETA {eta_precise}").unwrap(),
-    );
+    Ok(())
+}
 
-    let mut stream = futures::stream::iter(interesting_runs)
-        .map(|(krate_name, run)| {
-            let experiment = &experiment;
-            async move {
-                let log = get_log(client, multi, experiment, &run.log).await;
-                match log {
-                    Err(err) => {
-                        log::warn!("Failed to get log '{}': {err}", run.log);
-                        None
-                    }
-                    Ok(log) => Some((krate_name, run, log)),
-                }
+/// Writes the analyzed reports received from `reports`, up to [`REPORT_WRITERS`] at a time,
+/// then posts them to Zulip and runs the hooks as configured for their experiment,
+/// returning each report with the path of its text report and its failed hooks.
+async fn write_reports(
+    reports: tokio::sync::mpsc::Receiver<(AnalysisReport, ProgressBar, Arc<Config>)>,
+    client: reqwest::Client,
+    experiments_pb: ProgressBar,
+) -> Vec<Result<(AnalysisReport, String, Vec<(String, String, CommandError)>), AnalysisError>> {
+    futures::stream::unfold(reports, |mut reports| async move {
+        Some((reports.recv().await?, reports))
+    })
+    .map(|(report, report_ps, config)| {
+        let client = &client;
+        let experiments_pb = &experiments_pb;
+        async move {
+            let experiment = report.experiment.clone();
+            let json_path = write_analysis(&report).await?;
+            let path = write_report(
+                &report,
+                ReportFormat::Text,
+                &config.example_limits(),
+                &config,
+            )
+            .await?;
+            report_ps.finish_with_message(format!(
+                "Report for {experiment} written to '{path}' and '{json_path}'"
+            ));
+            if let Some(blacklist_path) = write_blacklist(&report, &config).await? {
+                log::info!(
+                    "Crates of {experiment} to skip in crater written to '{blacklist_path}'"
+                );
             }
-        })
-        .buffer_unordered(parallelism)
-        .filter_map(std::future::ready)
-        .map(|(krate_name, run, log)| async move {
-            let config = config.clone();
-            let run_findings = tokio::task::spawn_blocking(move || process_log(&config, &log))
-                .await
-                .unwrap();
-            (krate_name, run, run_findings)
-        })
-        .buffer_unordered(parallelism);
+            if let Some(badges_dir) = write_badges(&report, &config).await? {
+                log::info!("Badges of {experiment} written to '{badges_dir}'");
+            }
+            if let Some(zulip) = &config.zulip
+                && let Err(err) = post_summary(client, zulip, &report).await
+            {
+                log::error!("Failed to post the summary of {experiment} to Zulip: {err}");
+            }
+            let failed_hooks = run_hooks(&config, &report).await;
+            experiments_pb.inc(1);
+            let failed_hooks = failed_hooks
+                .into_iter()
+                .map(|(hook, err)| (experiment.clone(), hook, err))
+                .collect();
+            Ok((report, path, failed_hooks))
+        }
+    })
+    .buffer_unordered(REPORT_WRITERS)
+    .collect()
+    .await
+}
 
-    let mut findings = BTreeMap::new();
+/// Loads the config including the enabled built-in rules, failing with a hint to `init` if there is none yet.
+fn load_config() -> Result<Config, AnalysisError> {
+    let config_path = config_path();
+    match std::fs::read_to_string(config_path) {
+        Ok(content) => parse_config(config_path, &content),
+        Err(err) if err.kind() == ErrorKind::NotFound => {
+            let profiles = CONFIG_PROFILES.map(|(name, _)| name);
+            log::error!(
+                "No config found at '{config_path}', create one with `init --profile <{}>`",
+                profiles.join("|")
+            );
+            Err(AnalysisError::MissingConfig)
+        }
+        Err(err) => Err(AnalysisError::Io(err)),
+    }
+}
 
-    while let Some((krate_name, run, log_findings)) = stream.next().await {
-        if log_findings.is_empty() {
-            other.push((krate_name, &run.log));
+/// Reads the comment of the GitHub Actions event that triggered the workflow and, if it is a command like
+/// `@crater-analysis analyze name=pr-123456-1` of a trusted commenter, analyzes the experiment it names
+/// along with the given ones, answering with the report.
+///
+/// Like craterbot, a failed analysis is answered as well, instead of leaving the command unanswered.
+async fn github_comment(
+    args: GithubCommentArgs,
+    multi: &MultiProgress,
+) -> Result<(), AnalysisError> {
+    let client = reqwest::Client::builder()
+        .user_agent(APP_USER_AGENT)
+        .build()
+        .unwrap();
+    let Some(comment) = read_comment_command(&client, &args.bot).await? else {
+        return Ok(());
+    };
+    let result = analyze(args.analyze, false, multi, Some(&comment)).await;
+    if let Err(err) = &result {
+        let (_, experiment, target) = &comment;
+        if let Err(post_err) = post_error(&client, target, experiment, err).await {
+            log::error!("Failed to post the error to the comment: {post_err}");
         }
+    }
+    result
+}
+
+/// Writes the config of a [profile](CONFIG_PROFILES), `example` by default, refusing to overwrite an existing
+/// config without `--force`.
+fn init(args: &InitArgs) -> Result<(), AnalysisError> {
+    let profile = &args.profile;
+    let config = Config::profile(profile).ok_or_else(|| {
+        let profiles = CONFIG_PROFILES
+            .iter()
+            .map(|(name, purpose)| format!("{name} ({purpose})"))
+            .collect::<Vec<_>>();
+        AnalysisError::InvalidArguments(format!(
+            "unknown profile '{profile}', known are: {}",
+            profiles.join(", ")
+        ))
+    })?;
+    if !args.force && std::fs::exists(config_path())? {
+        return Err(AnalysisError::InvalidArguments(format!(
+            "'{}' already exists, pass --force to overwrite it",
+            config_path()
+        )));
+    }
+    std::fs::write(config_path(), toml::to_string_pretty(&config)?)?;
+    log::info!("Wrote the {profile} config to '{}'", config_path());
+    Ok(())
+}
 
-        for finding in log_findings {
-            *findings.entry(finding).or_default() += 1;
+fn parse_config(config_path: &str, content: &str) -> Result<Config, AnalysisError> {
+    match toml::from_str::<Config>(content) {
+        Ok(content) => content
+            .with_builtin_rules()
+            .map_err(|err| AnalysisError::InvalidConfig(err.to_string())),
+        Err(err) => {
+            println!("Failed to deserialize config '{config_path}': {err}");
+            Err(AnalysisError::TomlDeserialization(err))
         }
+    }
+}
 
-        run_pb.inc(1);
-    }
-
-    report_ps.set_message(format!("Processed Crated Report for {experiment}"));
-
-    Ok(AnalysisReport {
-        experiment: experiment.to_string(),
-        regressed_count,
-        interesting_results_count,
-        findings,
-        other: other
-            .into_iter()
-            .map(|(a, b)| (a.to_string(), b.to_string()))
-            .fold(BTreeMap::new(), |mut acc, (krate, run)| {
-                acc.entry(krate.to_string())
-                    .or_default()
-                    .push(run.to_string());
-                acc
-            }),
-        expected_krate_result: config.crate_result.clone(),
-        expected_run_result: config.run_result.clone(),
-    })
+/// Loads all [experiment configs](Config::experiment_configs) of `config` by their path,
+/// so that a broken one fails the run before any experiment is analyzed.
+fn load_experiment_configs(
+    config: &Config,
+) -> Result<BTreeMap<PathBuf, Arc<Config>>, AnalysisError> {
+    config
+        .experiment_config_paths()
+        .into_iter()
+        .map(|path| {
+            let content = std::fs::read_to_string(&path).map_err(|err| {
+                AnalysisError::InvalidConfig(format!(
+                    "failed to read experiment config '{}': {err}",
+                    path.display()
+                ))
+            })?;
+            let experiment_config = parse_config(&path.to_string_lossy(), &content)?;
+            Ok((path, Arc::new(experiment_config)))
+        })
+        .collect()
 }
 
-fn process_log(config: &Config, log: &[u8]) -> HashSet<String> {
-    let mut log_findings = HashSet::new();
+/// Renders the reports of already analyzed experiments from their `analysis.json`.
+///
+/// The crates listed per category are limited as configured in the config, if any, unless `--full` is given.
+async fn render(args: RenderArgs) -> Result<(), AnalysisError> {
+    let config = match std::fs::read_to_string(config_path()) {
+        Ok(content) => {
+            toml::from_str::<Config>(&content).map_err(AnalysisError::TomlDeserialization)?
+        }
+        Err(err) if err.kind() == ErrorKind::NotFound => Config::example(),
+        Err(err) => return Err(err.into()),
+    };
+    let config = args.output.apply(config);
+    let limits = if args.full {
+        // still on separate pages for large categories, so that the HTML report stays responsive
+        ExampleLimits::unlimited().with_page_size(config.html_page_size)
+    } else {
+        config.example_limits()
+    };
 
-    for line in log
-        .split(|c| matches!(c, b'\r' | b'\n'))
-        .filter(|s| !s.is_empty())
-    {
-        for (target_name, targets) in &config.targets {
-            if targets.iter().any(|target| {
-                target
-                    .all
-                    .iter()
-                    .all(|pat| contains_bytes(line, pat.as_bytes()))
-            }) {
-                log_findings.insert(target_name.clone());
+    for experiment in args.experiments.into_iter().collect::<BTreeSet<_>>() {
+        let mut report = load_analysis(&experiment).await?;
+        report.thousands_separator = config.thousands_separator.clone();
+        let path = write_report(&report, args.format, &limits, &config).await?;
+        log::info!("Report for {experiment} written to '{path}'");
+    }
+
+    Ok(())
+}
+
+fn config_path() -> &'static str {
+    CONFIG_PATH
+        .get()
+        .map_or(DEFAULT_CONFIG_PATH, String::as_str)
+}
+
+/// Reads experiment names from a file with one name per line,
+/// ignoring empty lines and everything after a `#`.
+fn read_experiments_file(path: &str) -> Result<Vec<String>, AnalysisError> {
+    let content = std::fs::read_to_string(path)?;
+    content
+        .lines()
+        .map(|line| line.split_once('#').map_or(line, |(line, _comment)| line))
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            check_experiment_name(line)
+                .map(str::to_string)
+                .map_err(AnalysisError::InvalidArguments)
+        })
+        .collect()
+}
+
+/// Lists the recorded failure fingerprints or sets the triage status of one,
+/// which is shown in the reports of all experiments analyzed afterwards.
+fn fingerprints_command(command: FingerprintsCommand) -> Result<(), AnalysisError> {
+    match command {
+        FingerprintsCommand::List => {
+            for (fingerprint, known) in &load_store()?.failures {
+                println!(
+                    "{fingerprint} {:<10} {} ({}, first seen in {})",
+                    known.status, known.error_line, known.category, known.first_seen
+                );
             }
+            Ok(())
+        }
+        FingerprintsCommand::Triage {
+            fingerprint,
+            status,
+            note,
+        } => {
+            let note = note.join(" ");
+            let mut store = load_store()?;
+            let Some(known) = store.failures.get_mut(&fingerprint) else {
+                return Err(AnalysisError::InvalidArguments(format!(
+                    "unknown fingerprint '{fingerprint}'"
+                )));
+            };
+            known.status = status;
+            known.note = (!note.is_empty()).then_some(note);
+            save_store(&store)
         }
     }
+}
+
+fn list_caches_command() -> Result<(), AnalysisError> {
+    let now = unix_timestamp();
+    let age = |timestamp: u64| {
+        let days = now.saturating_sub(timestamp) / (24 * 60 * 60);
+        format!("{days}d ago")
+    };
 
-    for needle in ERROR_REGEX.captures_iter(log) {
-        if let Some(capture) = needle.get(1) {
-            log_findings.insert(String::from_utf8_lossy(capture.as_bytes()).into_owned());
+    println!(
+        "{:<40} {:>8} {:>12} {:>12} {:>8} {:>16}",
+        "experiment", "version", "created", "last used", "logs", "ruleset"
+    );
+    for (experiment, manifest) in list_caches()? {
+        match manifest {
+            Some(manifest) => println!(
+                "{experiment:<40} {:>8} {:>12} {:>12} {:>8} {:>16}",
+                manifest.tool_version,
+                age(manifest.created_at),
+                age(manifest.last_used_at),
+                manifest.log_count,
+                manifest.ruleset_hash.as_deref().unwrap_or("-"),
+            ),
+            None => println!("{experiment:<40} (no manifest)"),
         }
     }
+    Ok(())
+}
 
-    log_findings
+/// Purges the cache of the experiment, downloading it again with `--prefetch`.
+async fn purge_cache_command(args: PurgeArgs, multi: &MultiProgress) -> Result<(), AnalysisError> {
+    let experiment = args.experiment;
+    let removed = purge_cache(&experiment, args.logs_only)?;
+    for path in &removed {
+        log::info!("Removed '{}'", path.display());
+    }
+    if removed.is_empty() {
+        log::info!("Nothing to purge for {experiment}");
+    }
+    if !args.prefetch {
+        return Ok(());
+    }
+    let prefetch = AnalyzeArgs {
+        experiments: vec![experiment],
+        ..AnalyzeArgs::default()
+    };
+    analyze(prefetch, true, multi, None).await
 }
 
-fn contains_bytes(haystack: &[u8], needle: &[u8]) -> bool {
-    haystack
-        .windows(needle.len())
-        .any(|window| window == needle)
+/// Lists the crates selected in both or only one of two analyzed experiments with their categories.
+async fn diff(args: DiffArgs) -> Result<(), AnalysisError> {
+    let diff = ExperimentDiff::new(
+        &load_analysis(&args.old_experiment).await?,
+        &load_analysis(&args.new_experiment).await?,
+    );
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&diff)?);
+    } else {
+        print!("{diff}");
+    }
+    Ok(())
 }
 
-static ERROR_REGEX: LazyLock<Regex> = LazyLock::new(|| {
-    regex::bytes::RegexBuilder::new(r#"^\[INFO\] \[stdout\] error\[(E\d+)\]:"#)
-        .multi_line(true)
-        .build()
-        .unwrap()
-});
+/// Cross-tabulates crater's crate results against the categories of an analyzed experiment,
+/// listing the crates where crater and the configured `spurious_categories` disagree.
+async fn crosstab(args: CrosstabArgs) -> Result<(), AnalysisError> {
+    let config = load_config()?;
+    let crosstab = CrateResultCrossTab::new(
+        &load_analysis(&args.experiment).await?,
+        &config.spurious_categories,
+    );
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&crosstab)?);
+    } else {
+        print!("{crosstab}");
+    }
+    Ok(())
+}
 
-struct AnalysisReport {
-    experiment: String,
-    expected_krate_result: String,
-    expected_run_result: String,
-    regressed_count: usize,
-    interesting_results_count: usize,
-    findings: BTreeMap<String, usize>,
-    other: BTreeMap<String, Vec<String>>,
-}
-
-impl AnalysisReport {
-    pub async fn print_report<W: AsyncWrite + Unpin>(
-        &self,
-        writer: &mut W,
-    ) -> Result<(), std::io::Error> {
-        writer
-            .write_all(format!("Report for Crater Experiment {}\n", self.experiment).as_bytes())
-            .await?;
-        writer
-            .write_all(
-                format!(
-                    "{} crates: {}\n",
-                    self.expected_krate_result, self.regressed_count
-                )
-                .as_bytes(),
-            )
-            .await?;
-        writer
-            .write_all(
-                format!(
-                    "{} runs: {}\n",
-                    self.expected_run_result, self.interesting_results_count
-                )
-                .as_bytes(),
-            )
-            .await?;
+/// Classifies a single log file or URL with the configured rules,
+/// printing the matched categories, the targets that almost matched
+/// and the targets that would match with whitespace-normalized or case-insensitive matching.
+async fn classify_log(args: ClassifyLogArgs) -> Result<(), AnalysisError> {
+    let source = args.source;
+    let config = load_config()?;
+    let log = if source.starts_with("https://") || source.starts_with("http://") {
+        let client = reqwest::Client::builder()
+            .user_agent(APP_USER_AGENT)
+            .build()
+            .unwrap();
+        let response = client.get(&source).send().await?.error_for_status()?;
+        response.bytes().await?.to_vec()
+    } else {
+        std::fs::read(&source)?
+    };
+    let log = truncate_long_lines(&log, config.max_line_length);
 
-        writer
-            .write_all("----------------------------------\n".as_bytes())
-            .await?;
-        writer.write_all("Results:\n".as_bytes()).await?;
+    let findings = process_log(&config, &log);
+    if findings.is_empty() {
+        println!("{}", style("no category matched").yellow());
+    } else {
+        println!("matched:");
+        for (name, line_match) in &findings {
+            println!("  {} (line {})", style(name).cyan(), line_match.line_number);
+            if let Some(chain) = dependency_chain(&log, line_match.line_number) {
+                println!("    required by {}", chain.join(" <- "));
+            }
+        }
+    }
 
-        for (name, &count) in &self.findings {
-            writer
-                .write_all(format!("{name}: {count}\n").as_bytes())
-                .await?;
+    let misses = near_misses(&config.targets, &log, &findings);
+    if !misses.is_empty() {
+        println!("almost matched:");
+        for (name, miss) in &misses {
+            println!(
+                "  {} (line {}, {}/{} patterns)",
+                style(name).cyan(),
+                miss.line_number,
+                miss.matched.len(),
+                miss.matched.len() + miss.missing.len()
+            );
+            for pattern in &miss.missing {
+                println!("    missing \"{}\"", pattern.escape_default());
+            }
         }
+    }
 
-        let sum: usize = self.findings.values().sum();
-        writer
-            .write_all("----------------------------------\n".as_bytes())
-            .await?;
-        writer.write_all(format!("sum: {sum}\n").as_bytes()).await?;
-        writer
-            .write_all(format!("others: {}\n", self.other.len()).as_bytes())
-            .await?;
-        writer
-            .write_all("----------------------------------\n".as_bytes())
-            .await?;
-        writer
-            .write_all(format!("{:#?}\n", self.other).as_bytes())
-            .await?;
-        Ok(())
+    let relaxed = relaxed_matches(&config.targets, &log, &findings);
+    if !relaxed.is_empty() {
+        println!("would match with relaxed matching:");
+        for (name, relaxed) in &relaxed {
+            println!(
+                "  {} (line {}, {})",
+                style(name).cyan(),
+                relaxed.line_number,
+                relaxed.relaxation
+            );
+        }
     }
-}
 
-#[derive(serde::Deserialize)]
-struct Results {
-    crates: Vec<CrateResult>,
+    Ok(())
 }
 
-#[derive(serde::Deserialize, Debug)]
-struct CrateResult {
-    name: String,
-    #[allow(dead_code)]
-    url: Option<String>,
-    res: String,
-    runs: Vec<Option<RunResult>>,
+/// Captures the results and logs of a few crates of an experiment into `<dir>/<experiment>/` (`fixtures/` by default),
+/// laid out like the cache below `results/`, to turn real-world misclassifications into regression tests.
+async fn record_fixture_command(
+    args: RecordFixtureArgs,
+    multi: &MultiProgress,
+) -> Result<(), AnalysisError> {
+    let experiment = args.experiment;
+    let crates = args
+        .crates
+        .iter()
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect::<BTreeSet<_>>();
+    let output = args.output;
+
+    let config = load_config()?;
+    let client = reqwest::Client::builder()
+        .user_agent(APP_USER_AGENT)
+        .build()
+        .unwrap();
+    prepare_experiment_dir(&experiment)?;
+    let recorded = record_fixture(&config, &client, multi, &experiment, &crates, &output).await?;
+    println!(
+        "Recorded {} crate(s) with {} log(s) of {experiment} in '{}'",
+        recorded.crates,
+        recorded.logs,
+        recorded.dir.display()
+    );
+    if !recorded.missing_logs.is_empty() {
+        println!(
+            "{} {} log(s) could not be fetched: {}",
+            style("warning:").red().bold(),
+            recorded.missing_logs.len(),
+            recorded.missing_logs.join(", ")
+        );
+    }
+    Ok(())
 }
 
-#[derive(serde::Deserialize, Debug)]
-struct RunResult {
-    res: String,
-    log: String,
+/// Writes the crates of the experiment's `results.json` with a run matching the [selection expression](SelectExpr)
+/// as a `results.json` of the same schema to `<file>` or stdout, for other tools working with crater results.
+async fn filter_results(
+    args: FilterResultsArgs,
+    multi: &MultiProgress,
+) -> Result<(), AnalysisError> {
+    let FilterResultsArgs {
+        experiment,
+        expr,
+        output,
+    } = args;
+
+    let client = reqwest::Client::builder()
+        .user_agent(APP_USER_AGENT)
+        .build()
+        .unwrap();
+    prepare_experiment_dir(&experiment)?;
+    let results_json = get_report_json(
+        &client,
+        multi,
+        &experiment,
+        RegeneratedReportHandling::default(),
+    )
+    .await?;
+    let filtered = filter_results_json(&results_json, |krate| {
+        krate
+            .runs
+            .iter()
+            .flatten()
+            .any(|run| expr.matches(krate, run))
+    })?;
+    let crates = filtered["crates"].as_array().map_or(0, Vec::len);
+    match output {
+        Some(path) => {
+            let mut file = std::io::BufWriter::new(std::fs::File::create(&path)?);
+            serde_json::to_writer(&mut file, &filtered)?;
+            file.flush()?;
+            println!(
+                "Wrote {crates} crate(s) of {experiment} to '{}'",
+                path.display()
+            );
+        }
+        None => {
+            let mut stdout = std::io::BufWriter::new(std::io::stdout().lock());
+            serde_json::to_writer(&mut stdout, &filtered)?;
+            writeln!(stdout)?;
+            stdout.flush()?;
+            log::info!("Kept {crates} crate(s) of {experiment}");
+        }
+    }
+    Ok(())
 }
 
-const CACHEDIR_TAG_CONTENT: &str = "\
-Signature: 8a477f597d28d172789f06886806bc55
-# This file is a cache directory tag created by https://github.com/Skgland/Crater-Analysis
-# For information about cache directory tags, see:
-#	http://www.brynosaurus.com/cachedir/
-";
+/// Records what a human found out about the failure of a crate in the experiment's `analysis.json`,
+/// from where `--import-annotations` carries it over to later experiments in which the crate fails the same way.
+/// The failure is identified by its fingerprint, which can be left out if the crate has only one
+/// or only one in the confirmed category.
+async fn annotate(args: AnnotateArgs) -> Result<(), AnalysisError> {
+    let AnnotateArgs {
+        experiment,
+        krate,
+        category,
+        note,
+        fingerprint,
+    } = args;
 
-async fn get_report(client: &Client, multi: &MultiProgress, experiment: &str) -> Result<Results, AnalysisError> {
-    let result_json_path = format!("results/{experiment}/results.json");
-    let result_json_url =
-        format!("https://crater-reports.s3.amazonaws.com/{experiment}/results.json");
-    let results = get_or_download_file(client, multi, result_json_path.as_ref(), &result_json_url).await?;
-    Ok(serde_json::from_slice(&results)?)
+    let mut report = load_analysis(&experiment).await?;
+    let fingerprint = match fingerprint {
+        Some(fingerprint) => {
+            if report
+                .crate_fingerprints(&krate, None)
+                .iter()
+                .all(|(known, _)| **known != fingerprint)
+            {
+                return Err(AnalysisError::InvalidArguments(format!(
+                    "{krate} does not fail with fingerprint '{fingerprint}' in {experiment}"
+                )));
+            }
+            fingerprint
+        }
+        None => {
+            let mut candidates = report.crate_fingerprints(&krate, None);
+            if candidates.len() > 1 && category.is_some() {
+                candidates = report.crate_fingerprints(&krate, category.as_deref());
+            }
+            match candidates.as_slice() {
+                [(fingerprint, _)] => (*fingerprint).clone(),
+                [] => {
+                    return Err(AnalysisError::InvalidArguments(format!(
+                        "{krate} has no fingerprinted failure in {experiment}"
+                    )));
+                }
+                _ => {
+                    let candidates = report
+                        .crate_fingerprints(&krate, None)
+                        .into_iter()
+                        .map(|(fingerprint, failure)| {
+                            format!("{fingerprint} ({})", failure.category)
+                        })
+                        .collect::<Vec<_>>();
+                    return Err(AnalysisError::InvalidArguments(format!(
+                        "{krate} fails in several ways in {experiment}, choose one with --fingerprint: {}",
+                        candidates.join(", ")
+                    )));
+                }
+            }
+        }
+    };
+    report.annotations.insert(
+        krate.clone(),
+        CrateAnnotation {
+            fingerprint,
+            category,
+            note,
+            imported_from: None,
+        },
+    );
+    let path = write_analysis(&report).await?;
+    println!("Annotated {krate} in '{path}', render the reports of {experiment} to include it");
+    Ok(())
 }
 
-async fn get_log(client: &Client, multi: &MultiProgress, experiment: &str, log: &str) -> Result<Mmap, AnalysisError> {
-    let mut log_folder = format!("results/{experiment}/logs/{log}/");
-    log_folder = log_folder
-        .replace("./", "/dot/")
-        .trim_end_matches('/')
-        .to_string();
+/// Distinct matched lines printed per category by `rules stats`, unless `--samples` says otherwise.
+const RULE_HIT_SAMPLES: usize = 5;
+
+/// Example matches of a category printed by `explain`.
+const EXPLAIN_EXAMPLES: usize = 5;
+
+/// Prints the rules composing a category, its description and the extractors feeding it, followed by
+/// example matches from the analysis of the experiment, by default the most recently analyzed one with the category.
+async fn explain(args: ExplainArgs) -> Result<(), AnalysisError> {
+    let ExplainArgs {
+        category,
+        experiment,
+    } = args;
 
-    if let Err(err) = tokio::fs::create_dir_all(&log_folder).await {
-        log::warn!("Failed to create cache folder: {err}");
+    let config = load_config()?;
+    let explanation = explain_category(&config, &category);
+    let has_category = |report: &AnalysisReport| {
+        report.category_crates.contains_key(&category)
+            || report.finding_matches.contains_key(&category)
+    };
+    let report = match experiment {
+        Some(experiment) => Some(load_analysis(&experiment).await?),
+        None => find_latest_analysis(has_category).await?,
+    };
+    if !explanation.is_known() && !report.as_ref().is_some_and(has_category) {
+        return Err(AnalysisError::InvalidArguments(format!(
+            "no rule or extractor produces the category '{category}' and no analysis found it"
+        )));
     }
-    let log_path = format!("{log_folder}/log.txt");
-    let log_url = format!("https://crater-reports.s3.amazonaws.com/{experiment}/{log}/log.txt");
 
-    get_or_download_file(client, multi, log_path.as_ref(), &log_url).await
+    print!("{explanation}");
+    let Some(report) = report else {
+        println!("\nNo analyzed experiment found the category yet.");
+        return Ok(());
+    };
+    let crates = report.category_crates.get(&category).map_or(0, Vec::len);
+    let matches = report
+        .finding_matches
+        .get(&category)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    println!(
+        "\nIn {}: {crates} crate(s), {} matched line(s)",
+        report.experiment,
+        matches.len()
+    );
+    for example in matches.iter().take(EXPLAIN_EXAMPLES) {
+        println!(
+            "  {} ({}:{}): {}",
+            example.krate,
+            example.log,
+            example.line_number,
+            example.line.trim()
+        );
+    }
+    if matches.len() > EXPLAIN_EXAMPLES {
+        println!("  ... and {} more", matches.len() - EXPLAIN_EXAMPLES);
+    }
+    Ok(())
 }
 
-async fn get_or_download_file(
-    client: &Client,
-    multi: &MultiProgress,
-    cache_path: &Path,
-    download_url: &str,
-) -> Result<Mmap, AnalysisError> {
-    let file = if !tokio::fs::try_exists(cache_path).await? {
-        let parent = cache_path.parent().unwrap();
-        let entry = if let Some(name) = parent.file_name() {
-            name.to_string_lossy().into_owned()
-        } else {
-            "parent-has-no-name".to_string()
+/// Compares the running version with the latest release and installs the release with `cargo install` if it is newer.
+///
+/// With `--check` only the versions are printed.
+async fn self_update(args: SelfUpdateArgs) -> Result<(), AnalysisError> {
+    let client = reqwest::Client::builder()
+        .user_agent(APP_USER_AGENT)
+        .build()
+        .unwrap();
+    let release = latest_release(&client).await?;
+    println!(
+        "running {}, latest release {} ({})",
+        env!("CARGO_PKG_VERSION"),
+        release.version(),
+        release.html_url
+    );
+    if !release.is_newer() {
+        println!("Already up to date.");
+        return Ok(());
+    }
+    if args.check {
+        return Ok(());
+    }
+
+    let status = install_release(&release).await?;
+    if !status.success() {
+        return Err(AnalysisError::Io(std::io::Error::other(format!(
+            "installing {} with cargo failed: {status}",
+            release.tag_name
+        ))));
+    }
+    println!("Installed crater-analysis {}.", release.version());
+    Ok(())
+}
+
+/// Reclassifies the experiments with the current rules, reusing the cached logs and without writing any reports,
+/// and exits with status 1 if less than `--min-classified` (between 0 and 1) of the selected runs of any experiment
+/// are classified, so that changes to the rules can be checked against real experiments in CI.
+async fn check_rules(args: CheckRulesArgs, multi: &MultiProgress) -> Result<(), AnalysisError> {
+    let min_classified = args.min_classified;
+    let config = Arc::new(load_config()?);
+    let experiment_configs = load_experiment_configs(&config)?;
+    let client = reqwest::Client::builder()
+        .user_agent(APP_USER_AGENT)
+        .build()
+        .unwrap();
+    let parallelism = args.jobs.parallelism();
+
+    let mut failed = false;
+    for experiment in args.experiments.into_iter().collect::<BTreeSet<_>>() {
+        let config = match config.experiment_config_path(&experiment) {
+            Some(path) => experiment_configs[&path].clone(),
+            None => config.clone(),
         };
+        let report_ps = multi.add(ProgressBar::new_spinner());
+        let report = run_analysis(
+            &config,
+            &client,
+            &experiment,
+            &report_ps,
+            multi,
+            parallelism,
+            None,
+        )
+        .await?;
+        report_ps.finish_and_clear();
 
-        log::debug!("Failed to access cached results for {entry}, falling back to downloading");
+        let classified = report.classified_share();
+        let passed = classified >= min_classified;
+        failed |= !passed;
+        println!(
+            "{} {experiment}: {:.1}% of {} runs classified, at least {:.1}% required",
+            if passed {
+                style("ok").green()
+            } else {
+                style("FAILED").red().bold()
+            },
+            classified * 100.0,
+            report.format_count(report.interesting_results_count),
+            min_classified * 100.0
+        );
+    }
 
-        let mut response = client.get(download_url).send().await?;
+    if failed {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Checks the analysis of the experiment against the expected runs per category in the TOML file,
+/// see [`Expectations`], and exits with status 1 listing the expectations that don't hold,
+/// so that the outcome of a known experiment can be pinned down in CI.
+async fn assert_expectations(args: AssertArgs) -> Result<(), AnalysisError> {
+    let AssertArgs {
+        experiment,
+        expected,
+    } = args;
 
-        let mut tempfile = NamedTempFile::new_in(parent)?;
+    let content = std::fs::read_to_string(&expected).map_err(|err| {
+        AnalysisError::InvalidArguments(format!(
+            "failed to read the expectations '{expected}': {err}"
+        ))
+    })?;
+    let expectations = toml::from_str::<Expectations>(&content).map_err(|err| {
+        AnalysisError::InvalidArguments(format!("invalid expectations '{expected}': {err}"))
+    })?;
+    let report = load_analysis(&experiment).await?;
 
-        let download_pb = multi.add(ProgressBar::no_length().with_message(format!("Downloading {download_url}")));
-        download_pb.set_style(
-            ProgressStyle::with_template("{msg} {wide_bar} {binary_bytes}/{binary_total_bytes} {binary_bytes_per_sec} ETA {eta_precise}").unwrap(),
+    let results = check_expectations(&report, &expectations);
+    let failed = results.iter().filter(|result| !result.holds()).count();
+    for result in &results {
+        println!(
+            "{} {result}",
+            if result.holds() {
+                style("ok").green()
+            } else {
+                style("FAILED").red().bold()
+            }
         );
+    }
+    if failed > 0 {
+        println!(
+            "\n{experiment}: {failed} of {} expectations don't hold",
+            results.len()
+        );
+        std::process::exit(1);
+    }
+    Ok(())
+}
 
-        if let Some(len) = response.content_length() {
-            download_pb.set_length(len);
-            let _ = tempfile.as_file().set_len(len);
-        }
+/// `lint` prints every pattern of the config with all non-ASCII and control characters escaped
+/// and flags suspicious patterns, `quote` prints each text as a TOML string for use as a pattern,
+/// `stats` prints up to `--samples` (default 5) distinct lines each category matched in the analysis of the experiment,
+/// to catch rules matching unrelated errors.
+async fn rules_command(command: RulesCommand) -> Result<(), AnalysisError> {
+    match command {
+        RulesCommand::Lint => {
+            let config = load_config()?;
+            let warning_targets = config.warnings.iter().flat_map(|warnings| {
+                warnings
+                    .targets
+                    .iter()
+                    .map(|(name, targets)| (format!("warnings.{name}"), targets))
+            });
+            let mut categories = config
+                .targets
+                .iter()
+                .map(|(name, targets)| (name.clone(), targets))
+                .chain(warning_targets)
+                .collect::<Vec<_>>();
+            categories.sort_by(|(a, _), (b, _)| a.cmp(b));
 
-        while let Some(chunk) = response.chunk().await? {
-            tempfile = match tokio::task::spawn_blocking({
-                let download_pb = download_pb.clone();
-                move || {
-                    download_pb.inc(chunk.len() as u64);
-                    tempfile.write_all(&chunk).map(|_| tempfile)
+            let mut suspicious = 0;
+            for (name, targets) in categories {
+                println!("{name}:");
+                for target in targets {
+                    let relaxation = target
+                        .relaxation()
+                        .map(|relaxation| format!(" ({relaxation})"))
+                        .unwrap_or_default();
+                    let issues = lint_target(target);
+                    // the issues count the `any` and `none` patterns after the `all` patterns
+                    let mut index = 0;
+                    let groups = [
+                        ("all", &target.all),
+                        ("any", &target.any),
+                        ("none", &target.none),
+                    ];
+                    for (group, patterns) in groups {
+                        if patterns.is_empty() {
+                            continue;
+                        }
+                        println!("  {group}{relaxation}:");
+                        for pattern in patterns {
+                            println!("    \"{}\"", pattern.escape_default());
+                            for (_, issue) in issues
+                                .iter()
+                                .filter(|(issue_index, _)| *issue_index == index)
+                            {
+                                suspicious += 1;
+                                println!("      {} {issue}", style("warning:").yellow().bold());
+                            }
+                            index += 1;
+                        }
+                    }
+                    if let Some(regex) = &target.regex {
+                        println!("  regex{relaxation}:\n    /{regex}/");
+                    }
                 }
-            })
-            .await
-            .unwrap()
-            {
-                Err(err) => {
-                    log::warn!("Failed to cache result to {cache_path:?}: {err}");
-                    return Err(err.into());
+            }
+
+            println!("{suspicious} suspicious pattern issue(s)");
+            Ok(())
+        }
+        RulesCommand::Quote { texts } => {
+            for text in texts {
+                println!("{}", toml::Value::String(text));
+            }
+            Ok(())
+        }
+        RulesCommand::Stats(args) => {
+            let hits = rule_hits(&load_analysis(&args.experiment).await?, args.samples);
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&hits)?);
+            } else {
+                for category in &hits {
+                    print!("{category}");
                 }
-                Ok(tempfile) => tempfile,
-            };
+            }
+            Ok(())
         }
-
-        tempfile.persist(cache_path).map_err(std::io::Error::from)?
-    } else {
-        std::fs::File::open(cache_path)?
-    };
-
-    Ok(unsafe { Mmap::map(&file)? })
+    }
 }
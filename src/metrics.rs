@@ -0,0 +1,134 @@
+//! Process-wide counters of the analysis, exported in the Prometheus text format, see [`serve_metrics`].
+//!
+//! The counters are plain atomics updated wherever the pipeline downloads or classifies something,
+//! so that long batch runs can be monitored and alerted on when they stall.
+
+use std::{
+    fmt::Write as _,
+    net::SocketAddr,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use tokio::{
+    io::{AsyncReadExt as _, AsyncWriteExt as _},
+    net::TcpListener,
+};
+
+use crate::cache::unix_timestamp;
+
+/// A counter or gauge exported as `crater_analysis_<name>`.
+pub struct Metric {
+    name: &'static str,
+    help: &'static str,
+    kind: &'static str,
+    value: AtomicU64,
+}
+
+impl Metric {
+    const fn counter(name: &'static str, help: &'static str) -> Self {
+        Self {
+            name,
+            help,
+            kind: "counter",
+            value: AtomicU64::new(0),
+        }
+    }
+
+    const fn gauge(name: &'static str, help: &'static str) -> Self {
+        Self {
+            name,
+            help,
+            kind: "gauge",
+            value: AtomicU64::new(0),
+        }
+    }
+
+    pub fn inc(&self) {
+        self.value.fetch_add(1, Ordering::Relaxed);
+        LAST_PROGRESS.set(unix_timestamp());
+    }
+
+    pub fn dec(&self) {
+        self.value.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub fn set(&self, value: u64) {
+        self.value.store(value, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.value.load(Ordering::Relaxed)
+    }
+}
+
+pub static DOWNLOADS_IN_FLIGHT: Metric =
+    Metric::gauge("downloads_in_flight", "Downloads currently in progress");
+pub static CACHE_HITS: Metric = Metric::counter(
+    "cache_hits_total",
+    "Reports and logs read from the cache below results/",
+);
+pub static CACHE_MISSES: Metric = Metric::counter(
+    "cache_misses_total",
+    "Reports and logs that had to be downloaded",
+);
+pub static DOWNLOAD_ERRORS: Metric =
+    Metric::counter("download_errors_total", "Downloads that failed");
+pub static LOGS_CLASSIFIED: Metric = Metric::counter("logs_classified_total", "Logs classified");
+pub static EXPERIMENTS_ANALYZED: Metric = Metric::counter(
+    "experiments_analyzed_total",
+    "Experiments analyzed completely",
+);
+pub static LAST_PROGRESS: Metric = Metric::gauge(
+    "last_progress_timestamp_seconds",
+    "Unix timestamp of the last change of any counter, to alert on stalled runs",
+);
+
+static ALL: [&Metric; 7] = [
+    &DOWNLOADS_IN_FLIGHT,
+    &CACHE_HITS,
+    &CACHE_MISSES,
+    &DOWNLOAD_ERRORS,
+    &LOGS_CLASSIFIED,
+    &EXPERIMENTS_ANALYZED,
+    &LAST_PROGRESS,
+];
+
+/// All metrics in the Prometheus text exposition format.
+pub fn render_metrics() -> String {
+    // writing to a String never fails
+    let mut out = String::new();
+    for metric in ALL {
+        let name = format!("crater_analysis_{}", metric.name);
+        let _ = writeln!(out, "# HELP {name} {}", metric.help);
+        let _ = writeln!(out, "# TYPE {name} {}", metric.kind);
+        let _ = writeln!(out, "{name} {}", metric.get());
+    }
+    out
+}
+
+/// Answers every HTTP request on `addr` with the [metrics](render_metrics), regardless of its path,
+/// in the background until the process exits; only fails if `addr` can't be bound.
+pub async fn serve_metrics(addr: SocketAddr) -> std::io::Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+    LAST_PROGRESS.set(unix_timestamp());
+    tokio::spawn(async move {
+        loop {
+            let Ok((mut stream, _)) = listener.accept().await else {
+                continue;
+            };
+            tokio::spawn(async move {
+                // the request itself is irrelevant, but reading it avoids resetting the connection
+                let mut request = [0; 1024];
+                let _ = stream.read(&mut request).await;
+                let body = render_metrics();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+                let _ = stream.shutdown().await;
+            });
+        }
+    });
+    Ok(())
+}
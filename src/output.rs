@@ -0,0 +1,180 @@
+//! The analysis results written below `results/<experiment>/`.
+
+use std::{borrow::Cow, path::Path};
+
+use crate::{
+    AnalysisError,
+    cache::{list_caches, unix_timestamp},
+    color::keep_ansi_in_reports,
+    engine::{
+        AnalysisReport, Config, ExampleLimits, ReportFormat, category_anchor, category_badges,
+        crater_blacklist, strip_ansi, summary_badge,
+    },
+};
+
+/// The structured analysis result, from which all report formats can be rendered.
+pub fn analysis_path(experiment: &str) -> String {
+    format!("results/{experiment}/analysis.json")
+}
+
+/// The path of the report of `experiment` written now, see [`Config::report_path`].
+pub fn report_path(config: &Config, experiment: &str, format: ReportFormat) -> String {
+    config.report_path(experiment, format, unix_timestamp())
+}
+
+/// The crates of the [blacklisted categories](Config::blacklist_categories) in the syntax of crater's `config.toml`.
+pub fn blacklist_path(experiment: &str) -> String {
+    format!("results/{experiment}/blacklist.toml")
+}
+
+/// The folder of the [shields.io badges](Config::badges) of an experiment.
+pub fn badges_dir(experiment: &str) -> String {
+    format!("results/{experiment}/badges")
+}
+
+/// The effective config an experiment was last analyzed with, after applying its mode and per-experiment config.
+pub fn config_snapshot_path(experiment: &str) -> String {
+    format!("results/{experiment}/config-used.toml")
+}
+
+/// Stores `config` as the config `experiment` was analyzed with,
+/// returning whether it differs from the config used the last time.
+pub async fn write_config_snapshot(
+    experiment: &str,
+    config: &Config,
+) -> Result<bool, AnalysisError> {
+    let path = config_snapshot_path(experiment);
+    // a table sorts the keys, so that neither formatting nor the order of the targets count as changes
+    let snapshot = toml::Table::try_from(config)?;
+    let changed = match tokio::fs::read_to_string(&path).await {
+        Ok(previous) => toml::from_str::<Config>(&previous)
+            .ok()
+            .and_then(|previous| toml::Table::try_from(previous).ok())
+            .is_none_or(|previous| previous != snapshot),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => false,
+        Err(err) => return Err(err.into()),
+    };
+    tokio::fs::write(&path, toml::to_string_pretty(&snapshot)?).await?;
+    Ok(changed)
+}
+
+pub async fn write_analysis(report: &AnalysisReport) -> Result<String, AnalysisError> {
+    let path = analysis_path(&report.experiment);
+    tokio::fs::write(&path, serde_json::to_vec_pretty(report)?).await?;
+    Ok(path)
+}
+
+pub async fn load_analysis(experiment: &str) -> Result<AnalysisReport, AnalysisError> {
+    let content = tokio::fs::read(analysis_path(experiment)).await?;
+    Ok(serde_json::from_slice(&content)?)
+}
+
+/// The most recently analyzed other experiment whose analysis is comparable to `report`.
+pub async fn find_previous_analysis(
+    report: &AnalysisReport,
+) -> Result<Option<AnalysisReport>, AnalysisError> {
+    find_latest_analysis(|previous| {
+        previous.experiment != report.experiment && report.is_comparable(previous)
+    })
+    .await
+}
+
+/// The analysis of the most recently analyzed experiment for which `keep` returns true.
+pub async fn find_latest_analysis(
+    keep: impl Fn(&AnalysisReport) -> bool,
+) -> Result<Option<AnalysisReport>, AnalysisError> {
+    let mut candidates = list_caches()?
+        .into_iter()
+        .filter_map(|(experiment, manifest)| Some((manifest?.last_analyzed_at?, experiment)))
+        .collect::<Vec<_>>();
+    candidates.sort_by(|a, b| b.cmp(a));
+
+    for (_, experiment) in candidates {
+        match load_analysis(&experiment).await {
+            Ok(analysis) if keep(&analysis) => return Ok(Some(analysis)),
+            Ok(_) => {}
+            Err(err) => log::warn!("Skipping the analysis of {experiment}: {err}"),
+        }
+    }
+    Ok(None)
+}
+
+/// Writes the crates of the [blacklisted categories](Config::blacklist_categories) to [`blacklist_path`],
+/// returning the path unless no category is blacklisted or none of them was found.
+pub async fn write_blacklist(
+    report: &AnalysisReport,
+    config: &Config,
+) -> Result<Option<String>, AnalysisError> {
+    if config.blacklist_categories.is_empty() {
+        return Ok(None);
+    }
+    let path = blacklist_path(&report.experiment);
+    match crater_blacklist(report, &config.blacklist_categories) {
+        Some(blacklist) => {
+            tokio::fs::write(&path, blacklist).await?;
+            Ok(Some(path))
+        }
+        None => {
+            // don't leave the crates of an earlier analysis behind
+            match tokio::fs::remove_file(&path).await {
+                Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+                _ => Ok(None),
+            }
+        }
+    }
+}
+
+/// Writes the [badges](Config::badges) of the analysis to [`badges_dir`], named after the [anchors](category_anchor)
+/// of their categories, returning the folder if badges are enabled. Badges of an earlier analysis are removed.
+pub async fn write_badges(
+    report: &AnalysisReport,
+    config: &Config,
+) -> Result<Option<String>, AnalysisError> {
+    if !config.badges {
+        return Ok(None);
+    }
+    let dir = badges_dir(&report.experiment);
+    match tokio::fs::remove_dir_all(&dir).await {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => return Err(err.into()),
+        _ => {}
+    }
+    tokio::fs::create_dir_all(&dir).await?;
+    tokio::fs::write(
+        format!("{dir}/summary.json"),
+        serde_json::to_vec(&summary_badge(report))?,
+    )
+    .await?;
+    for (category, badge) in category_badges(report) {
+        tokio::fs::write(
+            format!("{dir}/{}.json", category_anchor(category)),
+            serde_json::to_vec(&badge)?,
+        )
+        .await?;
+    }
+    Ok(Some(dir))
+}
+
+pub async fn write_report(
+    report: &AnalysisReport,
+    format: ReportFormat,
+    limits: &ExampleLimits,
+    config: &Config,
+) -> Result<String, AnalysisError> {
+    let path = report_path(config, &report.experiment, format);
+    if let Some(parent) = Path::new(&path).parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let mut rendered = format.render(report, limits);
+    if format == ReportFormat::Text
+        && !keep_ansi_in_reports()
+        && let Cow::Owned(stripped) = strip_ansi(&rendered)
+    {
+        rendered = stripped;
+    }
+    tokio::fs::write(&path, rendered).await?;
+    let dir = Path::new(&path).parent().unwrap_or(Path::new(""));
+    for (file_name, page) in format.pages(report, limits) {
+        tokio::fs::write(dir.join(file_name), page).await?;
+    }
+    Ok(path)
+}
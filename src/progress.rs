@@ -0,0 +1,121 @@
+//! Plain text progress output for when stderr is not a terminal, e.g. in CI logs,
+//! where redrawn progress bars would end up as garbled lines.
+
+use std::{
+    io,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use indicatif::TermLike;
+
+/// Width the progress bars are laid out for, as there is no terminal to take it from.
+const PLAIN_WIDTH: u16 = 100;
+
+/// Prints the state of all progress bars as plain lines at most once per interval,
+/// instead of redrawing them in place.
+#[derive(Debug, Clone)]
+pub struct PlainProgress {
+    interval: Duration,
+    state: Arc<Mutex<PlainState>>,
+}
+
+#[derive(Debug, Default)]
+struct PlainState {
+    /// The frame currently being drawn
+    frame: String,
+    /// The last frame drawn, printed or not
+    last_frame: String,
+    last_printed: Option<Instant>,
+    /// Whether `last_frame` was printed
+    printed: bool,
+    /// Set by [`PlainProgress::finish`], after which nothing is printed anymore
+    finished: bool,
+}
+
+impl PlainProgress {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            state: Arc::default(),
+        }
+    }
+
+    /// Prints the last frame if it was skipped, so that the final state isn't lost,
+    /// and stops printing the progress.
+    pub fn finish(&self) {
+        let mut state = self.state.lock().unwrap();
+        if !state.printed {
+            print_frame(&state.last_frame);
+        }
+        state.finished = true;
+    }
+}
+
+fn print_frame(frame: &str) {
+    for line in frame
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.is_empty())
+    {
+        eprintln!("{line}");
+    }
+}
+
+impl TermLike for PlainProgress {
+    fn width(&self) -> u16 {
+        PLAIN_WIDTH
+    }
+
+    fn move_cursor_up(&self, _n: usize) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn move_cursor_down(&self, _n: usize) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn move_cursor_right(&self, _n: usize) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn move_cursor_left(&self, _n: usize) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn write_line(&self, s: &str) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.frame.push_str(s);
+        state.frame.push('\n');
+        Ok(())
+    }
+
+    fn write_str(&self, s: &str) -> io::Result<()> {
+        self.state.lock().unwrap().frame.push_str(s);
+        Ok(())
+    }
+
+    fn clear_line(&self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let frame = std::mem::take(&mut state.frame);
+        // clearing the bars, e.g. to print a log line, draws an empty frame
+        if state.finished || frame.trim().is_empty() || frame == state.last_frame {
+            return Ok(());
+        }
+
+        let due = state
+            .last_printed
+            .is_none_or(|last_printed| last_printed.elapsed() >= self.interval);
+        if due {
+            print_frame(&frame);
+            state.last_printed = Some(Instant::now());
+        }
+        state.printed = due;
+        state.last_frame = frame;
+        Ok(())
+    }
+}
@@ -0,0 +1,120 @@
+//! The summary printed to the terminal once an experiment was analyzed.
+
+use console::style;
+
+use crate::engine::{AnalysisReport, percent};
+
+/// Number of categories listed in the summary.
+const TOP_CATEGORIES: usize = 10;
+
+/// Prints the summary of `report`, whose full text report was written to `report_path`.
+pub fn print_summary(report: &AnalysisReport, report_path: &str) {
+    let unclassified = report.unclassified_count();
+    let analyzed = report.interesting_results_count;
+    let unclassified_percent = percent(unclassified, analyzed);
+
+    println!(
+        "{} {} {} runs, {} unclassified ({})",
+        style(&report.experiment).bold(),
+        report.format_count(analyzed),
+        report.expected_run_result,
+        report.format_count(unclassified),
+        style(format!("{unclassified_percent:.1}%")).fg(if unclassified_percent > 20.0 {
+            console::Color::Red
+        } else {
+            console::Color::Green
+        })
+    );
+
+    if let Some(status) = report.status.as_ref().filter(|status| status.stale) {
+        println!(
+            "  {}",
+            style(format!(
+                "warning: the experiment is {} days old, its results may be outdated",
+                status.age_days.unwrap_or_default()
+            ))
+            .yellow()
+        );
+    }
+
+    let categories = report.findings_by_count();
+
+    let width = categories
+        .iter()
+        .take(TOP_CATEGORIES)
+        .map(|(name, _)| console::measure_text_width(name))
+        .max()
+        .unwrap_or(0);
+
+    for &(name, &count) in categories.iter().take(TOP_CATEGORIES) {
+        let badge = if report.is_new_category(name) {
+            style(" NEW").red().bold().to_string()
+        } else {
+            String::new()
+        };
+        let top = match &report.popularity {
+            Some(popularity) => style(format!(
+                " ({} in top {})",
+                report.top_crate_count(name),
+                popularity.top
+            ))
+            .dim()
+            .to_string(),
+            None => String::new(),
+        };
+        let (of_runs, of_crates) = report.category_percentages(name, count);
+        println!(
+            "  {} {:>8} {}{top}{badge}",
+            style(format!("{name:<width$}")).cyan(),
+            report.format_count(count),
+            style(format!(
+                "{of_runs:5.1}% of runs, {of_crates:5.1}% of crates"
+            ))
+            .dim()
+        );
+    }
+    if categories.len() > TOP_CATEGORIES {
+        println!(
+            "  {}",
+            style(format!(
+                "... and {} more categories",
+                categories.len() - TOP_CATEGORIES
+            ))
+            .dim()
+        );
+    }
+
+    let total = report.format_count(report.findings.values().sum());
+    println!(
+        "  {} {total:>8}",
+        style(format!("{:<width$}", "total")).bold()
+    );
+    let (independent, blocked) = report.independent_and_blocked_counts();
+    if blocked > 0 {
+        println!(
+            "  {}",
+            style(format!(
+                "{independent} crates broken independently, {blocked} blocked on a dependency"
+            ))
+            .dim()
+        );
+    }
+    if !report.ignored_findings.is_empty() {
+        println!(
+            "  {}",
+            style(format!("ignored: {}", report.ignored_summary())).dim()
+        );
+    }
+
+    if !report.missing_runs.is_empty() {
+        println!(
+            "  {}",
+            style(format!(
+                "{} crates without a recorded run",
+                report.missing_runs.len()
+            ))
+            .yellow()
+        );
+    }
+    println!("  {}", style(format!("full report: {report_path}")).dim());
+}
@@ -0,0 +1,141 @@
+//! Checking for newer releases of this tool, whose built-in rules improve with each release, see [`check_for_update`].
+
+use std::process::{ExitStatus, Stdio};
+
+use reqwest::Client;
+
+use crate::{AnalysisError, cache::unix_timestamp};
+
+/// The latest release of this tool, see <https://docs.github.com/en/rest/releases/releases#get-the-latest-release>.
+const LATEST_RELEASE_URL: &str =
+    "https://api.github.com/repos/Skgland/Crater-Analysis/releases/latest";
+
+/// The repository `self-update` installs releases from.
+const REPOSITORY_URL: &str = "https://github.com/Skgland/Crater-Analysis";
+
+/// Where the latest release is remembered between runs, so that GitHub is asked at most once a day.
+const LATEST_RELEASE_PATH: &str = "results/latest-release.json";
+
+/// Seconds a remembered latest release is used before asking GitHub again.
+const CHECK_INTERVAL_SECS: u64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Release {
+    /// The git tag of the release, e.g. `v0.2.0`
+    pub tag_name: String,
+    pub html_url: String,
+}
+
+impl Release {
+    /// The version of the release, its tag without the leading `v`.
+    pub fn version(&self) -> &str {
+        self.tag_name.strip_prefix('v').unwrap_or(&self.tag_name)
+    }
+
+    /// Whether the release is newer than the running version of this tool.
+    pub fn is_newer(&self) -> bool {
+        match (
+            parse_version(self.version()),
+            parse_version(env!("CARGO_PKG_VERSION")),
+        ) {
+            (Some(release), Some(running)) => release > running,
+            _ => false,
+        }
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct RememberedRelease {
+    checked_at: u64,
+    release: Release,
+}
+
+/// The latest published release, fails with [`AnalysisError::NotFound`] if there is none.
+pub async fn latest_release(client: &Client) -> Result<Release, AnalysisError> {
+    let response = client
+        .get(LATEST_RELEASE_URL)
+        .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+        .send()
+        .await?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Err(AnalysisError::NotFound(
+            "a release of crater-analysis".to_string(),
+        ));
+    }
+    let body = response.error_for_status()?.bytes().await?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+/// Warns if a newer release than the running version was published, see [`Config::check_for_updates`](crate::engine::Config::check_for_updates).
+///
+/// The latest release is remembered for a day; failures are only logged, as the check must not fail the analysis.
+pub async fn check_for_update(client: &Client) {
+    let remembered = std::fs::read(LATEST_RELEASE_PATH)
+        .ok()
+        .and_then(|content| serde_json::from_slice::<RememberedRelease>(&content).ok())
+        .filter(|remembered| {
+            unix_timestamp().saturating_sub(remembered.checked_at) < CHECK_INTERVAL_SECS
+        });
+    let release = match remembered {
+        Some(remembered) => remembered.release,
+        None => match latest_release(client).await {
+            Ok(release) => {
+                let remembered = RememberedRelease {
+                    checked_at: unix_timestamp(),
+                    release,
+                };
+                if let Err(err) = remember_release(&remembered) {
+                    log::debug!("Failed to remember the latest release: {err}");
+                }
+                remembered.release
+            }
+            Err(err) => {
+                log::debug!("Failed to look up the latest release: {err}");
+                return;
+            }
+        },
+    };
+    if release.is_newer() {
+        log::warn!(
+            "crater-analysis {} is available (running {}), update with `self-update`: {}",
+            release.version(),
+            env!("CARGO_PKG_VERSION"),
+            release.html_url
+        );
+    }
+}
+
+fn remember_release(remembered: &RememberedRelease) -> Result<(), AnalysisError> {
+    std::fs::create_dir_all("results")?;
+    std::fs::write(LATEST_RELEASE_PATH, serde_json::to_vec_pretty(remembered)?)?;
+    Ok(())
+}
+
+/// Installs `release` with `cargo install`, replacing the installed binary.
+pub async fn install_release(release: &Release) -> Result<ExitStatus, AnalysisError> {
+    let status = tokio::process::Command::new("cargo")
+        .args([
+            "install",
+            "--locked",
+            "--force",
+            "--git",
+            REPOSITORY_URL,
+            "--tag",
+            &release.tag_name,
+            env!("CARGO_PKG_NAME"),
+        ])
+        .stdin(Stdio::null())
+        .status()
+        .await?;
+    Ok(status)
+}
+
+/// The numeric parts of a version like `1.2.3`, ignoring pre-release and build suffixes.
+fn parse_version(version: &str) -> Option<Vec<u64>> {
+    version
+        .split(['-', '+'])
+        .next()?
+        .split('.')
+        .map(|part| part.parse().ok())
+        .collect()
+}
@@ -0,0 +1,47 @@
+//! Posting summaries to Zulip, see [`Config::zulip`](crate::engine::Config::zulip).
+
+use reqwest::Client;
+
+use crate::{
+    AnalysisError,
+    engine::{AnalysisReport, ZulipConfig, render_summary_markdown},
+};
+
+/// Number of categories listed in the posted summary.
+const TOP_CATEGORIES: usize = 10;
+
+/// Posts the summary of the report to the configured stream and topic.
+pub async fn post_summary(
+    client: &Client,
+    zulip: &ZulipConfig,
+    report: &AnalysisReport,
+) -> Result<(), AnalysisError> {
+    let credential = |name: &str| {
+        std::env::var(name).map_err(|_| {
+            AnalysisError::InvalidConfig(format!("posting to Zulip requires {name} to be set"))
+        })
+    };
+    let email = credential("ZULIP_BOT_EMAIL")?;
+    let api_key = credential("ZULIP_API_KEY")?;
+
+    let topic = zulip.topic.replace("{experiment}", &report.experiment);
+    let content = render_summary_markdown(report, TOP_CATEGORIES);
+
+    client
+        .post(format!(
+            "{}/api/v1/messages",
+            zulip.site.trim_end_matches('/')
+        ))
+        .basic_auth(email, Some(api_key))
+        .form(&[
+            ("type", "stream"),
+            ("to", zulip.stream.as_str()),
+            ("topic", topic.as_str()),
+            ("content", content.as_str()),
+        ])
+        .send()
+        .await?
+        .error_for_status()?;
+
+    Ok(())
+}
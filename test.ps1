@@ -1 +1 @@
-cargo run --release -- $(Get-ChildItem -Path results -Directory | Select-Object -expand Name)
\ No newline at end of file
+cargo run --release -- analyze $(Get-ChildItem -Path results -Directory | Select-Object -expand Name)
\ No newline at end of file
@@ -4,4 +4,4 @@ for folder in results/* ; do
     experiments+=(${folder#results/})
 done
 
-cargo run --release -- "${experiments[@]}" "$@"
+cargo run --release -- analyze "${experiments[@]}" "$@"